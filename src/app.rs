@@ -1,26 +1,45 @@
+use smithay_client_toolkit::reexports::calloop::ping::{Ping, make_ping};
+use smithay_client_toolkit::reexports::{
+    calloop::{
+        EventLoop, LoopHandle, RegistrationToken,
+        timer::{TimeoutAction, Timer},
+    },
+    calloop_wayland_source::WaylandSource,
+};
 use smithay_client_toolkit::{
-    compositor::{CompositorHandler, CompositorState},
+    compositor::{CompositorHandler, CompositorState, Region},
     data_device_manager::{
         DataDeviceManagerState, WritePipe,
-        data_device::DataDeviceHandler,
-        data_offer::DataOfferHandler,
-        data_source::{DataSourceHandler, DragSource},
+        data_device::{DataDevice, DataDeviceHandler},
+        data_offer::{DataOfferHandler, DragOffer},
+        data_source::{CopyPasteSource, DataSourceHandler, DragSource},
     },
+    globals::ProvidesBoundGlobal,
     output::{OutputHandler, OutputState},
+    primary_selection::{
+        PrimarySelectionManagerState,
+        device::{PrimarySelectionDevice, PrimarySelectionDeviceHandler},
+        selection::{PrimarySelectionSource, PrimarySelectionSourceHandler},
+    },
     reexports::client::{
-        Connection, Dispatch, EventQueue, QueueHandle,
+        Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+        backend::ObjectId,
+        event_created_child,
         globals::registry_queue_init,
         protocol::{
-            wl_keyboard, wl_output, wl_pointer, wl_seat, wl_shm, wl_subcompositor, wl_subsurface,
-            wl_surface,
+            wl_data_device::WlDataDevice, wl_data_device_manager::DndAction,
+            wl_data_source::WlDataSource, wl_keyboard, wl_output, wl_pointer, wl_seat, wl_shm,
+            wl_subcompositor, wl_subsurface, wl_surface, wl_touch,
         },
     },
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
     seat::{
         SeatHandler, SeatState,
-        keyboard::{KeyEvent as SctkKeyEvent, KeyboardHandler, Keysym, Modifiers},
-        pointer::{PointerEvent as SctkPointerEvent, PointerHandler},
+        keyboard::{KeyEvent as SctkKeyEvent, KeyboardHandler, Keysym, Modifiers, RepeatInfo},
+        pointer::{PointerEvent as SctkPointerEvent, PointerHandler, ThemeSpec, ThemedPointer},
+        pointer_constraints::{PointerConstraintsHandler, PointerConstraintsState},
+        relative_pointer::{RelativeMotionEvent, RelativePointerHandler, RelativePointerState},
     },
     shell::{
         WaylandSurface,
@@ -34,7 +53,55 @@ use smithay_client_toolkit::{
             window::{Window as XdgWindow, WindowConfigure, WindowDecorations, WindowHandler},
         },
     },
-    shm::{Shm, ShmHandler, slot::SlotPool},
+    shm::{
+        Shm, ShmHandler,
+        slot::{Buffer, SlotPool},
+    },
+};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+    zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+};
+use wayland_protocols::wp::{
+    fractional_scale::v1::client::{
+        wp_fractional_scale_manager_v1::{self, WpFractionalScaleManagerV1},
+        wp_fractional_scale_v1::{self, WpFractionalScaleV1},
+    },
+    pointer_constraints::zv1::client::{
+        zwp_confined_pointer_v1::ZwpConfinedPointerV1, zwp_locked_pointer_v1::ZwpLockedPointerV1,
+        zwp_pointer_constraints_v1::Lifetime,
+    },
+    pointer_gestures::zv1::client::{
+        zwp_pointer_gesture_hold_v1::{self, ZwpPointerGestureHoldV1},
+        zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+        zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+        zwp_pointer_gestures_v1::{self, ZwpPointerGesturesV1},
+    },
+    relative_pointer::zv1::client::zwp_relative_pointer_v1::ZwpRelativePointerV1,
+    tablet::zv2::client::{
+        zwp_tablet_manager_v2::{self, ZwpTabletManagerV2},
+        zwp_tablet_pad_dial_v2::{self, ZwpTabletPadDialV2},
+        zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+        zwp_tablet_pad_ring_v2::{self, ZwpTabletPadRingV2},
+        zwp_tablet_pad_strip_v2::{self, ZwpTabletPadStripV2},
+        zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+        zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
+        zwp_tablet_tool_v2::{self, ZwpTabletToolV2},
+        zwp_tablet_v2::{self, ZwpTabletV2},
+    },
+    text_input::zv3::client::{
+        zwp_text_input_manager_v3::{self, ZwpTextInputManagerV3},
+        zwp_text_input_v3::{self, ContentHint, ContentPurpose, ZwpTextInputV3},
+    },
+    viewporter::client::{
+        wp_viewport::{self, WpViewport},
+        wp_viewporter::{self, WpViewporter},
+    },
 };
 use wayland_protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment;
 
@@ -44,26 +111,107 @@ use crate::attached_surface::{
     protocol::zwlr_attached_surface_manager_v1::ZwlrAttachedSurfaceManagerV1,
     protocol::zwlr_attached_surface_v1::ZwlrAttachedSurfaceV1,
 };
-use crate::input::{Key, KeyEvent, KeyState, Modifiers as InputModifiers, PointerEvent};
+use crate::clipboard::{
+    ClipboardData, ClipboardEvent, ClipboardReadId, Selection, SelectionContent, TEXT_MIME_TYPES,
+    best_mime_type, read_pipe,
+};
+use crate::cursor::CursorShape;
+use crate::dnd::{DragAction, DragEvent, DragIcon, DropData, MIME_URI_LIST, files_to_uri_list};
+use crate::ime::{ImeEvent, ImePurpose, ImeState, clamp_surrounding};
+use crate::input::{
+    GestureEvent, GestureEventKind, Key, KeyEvent, KeyRepeat, KeyState,
+    Modifiers as InputModifiers, PointerEvent, TabletEvent, TabletEventKind, TabletToolState,
+    TabletToolType, TouchEvent, TouchState,
+};
 use crate::render::Canvas;
+use crate::scale::SurfaceScale;
 use crate::window::{
-    Overlay, OverlayId, Popup, PopupConfig, PopupId, Subsurface, SubsurfaceId, Window, WindowId,
-    WindowManager,
+    FrameState, Overlay, OverlayId, Popup, PopupConfig, PopupId, Subsurface, SubsurfaceId,
+    SurfaceTarget, Window, WindowId, WindowManager,
 };
 
+/// Callbacks invoked by [`App::run`].
+///
+/// Every method has a default no-op implementation so handlers only need to
+/// implement the surfaces they actually create.
 pub trait AppHandler {
-    fn on_window_configure(&mut self, app: &mut App, window_id: WindowId, width: u32, height: u32);
-    fn on_popup_configure(&mut self, app: &mut App, popup_id: PopupId, width: u32, height: u32);
-    fn on_key(&mut self, app: &mut App, window_id: WindowId, event: KeyEvent);
-    fn on_pointer(&mut self, app: &mut App, window_id: WindowId, event: PointerEvent);
-    fn on_render(&mut self, app: &mut App, window_id: WindowId, canvas: &mut Canvas);
-    fn on_render_popup(&mut self, app: &mut App, popup_id: PopupId, canvas: &mut Canvas);
-    fn on_close_request(&mut self, app: &mut App, window_id: WindowId) -> bool;
+    fn on_window_configure(
+        &mut self,
+        _app: &mut App,
+        _window_id: WindowId,
+        _width: u32,
+        _height: u32,
+    ) {
+    }
+    fn on_popup_configure(
+        &mut self,
+        _app: &mut App,
+        _popup_id: PopupId,
+        _width: u32,
+        _height: u32,
+    ) {
+    }
+    fn on_overlay_configure(
+        &mut self,
+        _app: &mut App,
+        _overlay_id: OverlayId,
+        _width: u32,
+        _height: u32,
+    ) {
+    }
+    fn on_attached_surface_configure(
+        &mut self,
+        _app: &mut App,
+        _surface_id: AttachedSurfaceId,
+        _width: u32,
+        _height: u32,
+    ) {
+    }
+    fn on_key(&mut self, _app: &mut App, _target: SurfaceTarget, _event: KeyEvent) {}
+    fn on_pointer(&mut self, _app: &mut App, _target: SurfaceTarget, _event: PointerEvent) {}
+    fn on_touch(&mut self, _app: &mut App, _target: SurfaceTarget, _event: TouchEvent) {}
+    fn on_gesture(&mut self, _app: &mut App, _target: SurfaceTarget, _event: GestureEvent) {}
+    fn on_tablet(&mut self, _app: &mut App, _target: SurfaceTarget, _event: TabletEvent) {}
+    fn on_render(&mut self, _app: &mut App, _window_id: WindowId, _canvas: &mut Canvas) {}
+    fn on_render_popup(&mut self, _app: &mut App, _popup_id: PopupId, _canvas: &mut Canvas) {}
+    fn on_render_overlay(&mut self, _app: &mut App, _overlay_id: OverlayId, _canvas: &mut Canvas) {}
+    fn on_render_subsurface(
+        &mut self,
+        _app: &mut App,
+        _subsurface_id: SubsurfaceId,
+        _canvas: &mut Canvas,
+    ) {
+    }
+    fn on_render_attached_surface(
+        &mut self,
+        _app: &mut App,
+        _surface_id: AttachedSurfaceId,
+        _canvas: &mut Canvas,
+    ) {
+    }
+    /// Return true to let the window close. When the last window closes the
+    /// event loop exits.
+    fn on_close_request(&mut self, _app: &mut App, _window_id: WindowId) -> bool {
+        true
+    }
+    fn on_clipboard(&mut self, _app: &mut App, _event: ClipboardEvent) {}
+    fn on_drag(&mut self, _app: &mut App, _event: DragEvent) {}
+    fn on_ime(&mut self, _app: &mut App, _event: ImeEvent) {}
+}
+
+/// A configure event waiting to be delivered to the [`AppHandler`]
+#[derive(Clone, Copy, Debug)]
+enum PendingConfigure {
+    Window(WindowId),
+    Popup(PopupId),
+    Overlay(OverlayId),
+    AttachedSurface(AttachedSurfaceId),
 }
 
 pub struct App {
     pub running: bool,
     conn: Connection,
+    qh: QueueHandle<Self>,
     registry_state: RegistryState,
     seat_state: SeatState,
     output_state: OutputState,
@@ -72,34 +220,94 @@ pub struct App {
     xdg_shell: XdgShell,
     layer_shell: Option<LayerShell>,
     attached_surface_manager: Option<AttachedSurfaceManager>,
+    // HiDPI: fractional scaling is only used when both globals are present
+    fractional_scale_manager: Option<WpFractionalScaleManagerV1>,
+    viewporter: Option<WpViewporter>,
+    // Input method support (zwp_text_input_v3), one text input per seat
+    text_input_manager: Option<ZwpTextInputManagerV3>,
+    text_input: Option<ZwpTextInputV3>,
+    ime: ImeState,
+    ime_events: Vec<ImeEvent>,
     shm: Shm,
     pool: Option<SlotPool>,
     pub windows: WindowManager,
-    keyboard_focus: Option<WindowId>,
-    pointer_focus: Option<WindowId>,
+    keyboard_focus: Option<SurfaceTarget>,
+    pointer_focus: Option<SurfaceTarget>,
+    pointer: Option<wl_pointer::WlPointer>,
+    // Touchpad gestures (zwp_pointer_gestures_v1) for the pointer
+    pointer_gestures_manager: Option<ZwpPointerGesturesV1>,
+    pointer_gestures: Option<PointerGestures>,
+    gesture_target: Option<SurfaceTarget>,
+    gesture_events: Vec<GestureEvent>,
+    // Pointer lock/confinement for drags, and relative motion for the pointer
+    pointer_constraints: PointerConstraintsState,
+    pointer_constraint: Option<PointerConstraint>,
+    pointer_constrained: bool,
+    relative_pointer_state: RelativePointerState,
+    relative_pointer: Option<ZwpRelativePointerV1>,
+    // Cursor shapes requested per surface, applied whenever the pointer enters
+    themed_pointer: Option<ThemedPointer>,
+    cursor_shapes: HashMap<SurfaceTarget, CursorShape>,
     last_serial: u32,
     key_events: Vec<KeyEvent>,
     current_modifiers: InputModifiers,
-    // Key repeat state
-    repeat_key: Option<KeyEvent>,
-    repeat_start: Option<std::time::Instant>,
-    last_repeat: Option<std::time::Instant>,
-    repeat_delay_ms: u32,
-    repeat_rate_ms: u32,
+    // Key repeat state; the timer only exists while `App::run` is driving the loop
+    key_repeat: KeyRepeat,
+    repeat_timer: Option<RegistrationToken>,
+    loop_handle: Option<LoopHandle<'static, Self>>,
     // Pointer state
     pointer_events: Vec<crate::input::PointerEvent>,
+    // Touch state
+    touch: Option<wl_touch::WlTouch>,
+    touch_state: TouchState,
+    touch_events: Vec<TouchEvent>,
+    // Drawing tablets (zwp_tablet_v2): tool events follow each tool's proximity
+    // focus, pad events follow each pad's enter/leave focus
+    tablet_manager: Option<ZwpTabletManagerV2>,
+    tablet_seat: Option<ZwpTabletSeatV2>,
+    tablet_tools: HashMap<ObjectId, TabletToolState>,
+    tablet_pad_focus: HashMap<ObjectId, SurfaceTarget>,
+    tablet_events: Vec<TabletEvent>,
     pointer_x: f64,
     pointer_y: f64,
     // Data device state (drag & drop, clipboard)
     data_device_manager: Option<DataDeviceManagerState>,
     drop_events: Vec<DropEvent>,
-    pending_drag_source: Option<DragSource>,
-    pending_drag_data: Option<Vec<u8>>,
+    drag_events: Vec<DragEvent>,
+    // Incoming drag: the surface it is over and the MIME type we accepted
+    drag_target: Option<SurfaceTarget>,
+    drag_accepted: Option<String>,
+    // A dropped offer waiting for `resolve_drop` or for its payload to be read
+    dropped_offer: Option<DragOffer>,
+    drop_results: (Sender<DropData>, Receiver<DropData>),
+    // Outgoing drag started with `start_drag`
+    drag_source: Option<DragSource>,
+    drag_content: Option<SelectionContent>,
+    drag_source_action: DragAction,
+    drag_icon: Option<(wl_surface::WlSurface, Buffer)>,
     // Seat for drag & drop
     current_seat: Option<wl_seat::WlSeat>,
+    data_device: Option<DataDevice>,
+    // Clipboard state
+    clipboard_source: Option<CopyPasteSource>,
+    clipboard_content: Option<SelectionContent>,
+    primary_selection_manager: Option<PrimarySelectionManagerState>,
+    primary_selection_device: Option<PrimarySelectionDevice>,
+    primary_selection_source: Option<PrimarySelectionSource>,
+    primary_selection_content: Option<SelectionContent>,
+    clipboard_events: Vec<ClipboardEvent>,
+    clipboard_results: (Sender<ClipboardData>, Receiver<ClipboardData>),
+    next_clipboard_read_id: u64,
+    // Wakes `App::run` when a background pipe read completes
+    wake: Option<Ping>,
+    // Handler-driven event loop state (see `App::run`)
+    handler_active: bool,
+    pending_configures: Vec<PendingConfigure>,
+    close_requests: Vec<WindowId>,
 }
 
-/// Represents a completed drop event with file URIs
+/// Represents a completed drop of local files.
+/// See [`DragEvent`] for drops of any MIME type.
 #[derive(Debug, Clone)]
 pub struct DropEvent {
     pub x: f64,
@@ -132,15 +340,38 @@ impl App {
             .ok()
             .map(AttachedSurfaceManager::new);
 
+        // Bind fractional scale + viewporter for non-integer HiDPI scales
+        let fractional_scale_manager: Option<WpFractionalScaleManagerV1> =
+            globals.bind(&qh, 1..=1, ()).ok();
+        let viewporter: Option<WpViewporter> = globals.bind(&qh, 1..=1, ()).ok();
+
+        // Bind pointer gestures for touchpad swipe, pinch and hold
+        let pointer_gestures_manager: Option<ZwpPointerGesturesV1> =
+            globals.bind(&qh, 1..=3, ()).ok();
+
+        // Bind pointer constraints and relative pointer for locked drags
+        let pointer_constraints = PointerConstraintsState::bind(&globals, &qh);
+        let relative_pointer_state = RelativePointerState::bind(&globals, &qh);
+
+        // Bind the tablet manager for stylus and pad input
+        let tablet_manager: Option<ZwpTabletManagerV2> = globals.bind(&qh, 1..=2, ()).ok();
+
+        // Bind text input for IME composition (CJK, compose, dead keys)
+        let text_input_manager: Option<ZwpTextInputManagerV3> = globals.bind(&qh, 1..=1, ()).ok();
+
         // Bind data device manager for drag & drop and clipboard support
         let data_device_manager = DataDeviceManagerState::bind(&globals, &qh).ok();
 
+        // Bind primary selection for select-to-copy / middle-click paste
+        let primary_selection_manager = PrimarySelectionManagerState::bind(&globals, &qh).ok();
+
         let pool = SlotPool::new(1920 * 1080 * 4, &shm)?;
 
         Ok((
             Self {
                 running: true,
                 conn,
+                qh: qh.clone(),
                 registry_state,
                 seat_state,
                 output_state,
@@ -149,27 +380,72 @@ impl App {
                 xdg_shell,
                 layer_shell,
                 attached_surface_manager,
+                fractional_scale_manager,
+                viewporter,
+                text_input_manager,
+                text_input: None,
+                ime: ImeState::default(),
+                ime_events: Vec::new(),
                 shm,
                 pool: Some(pool),
                 windows: WindowManager::new(),
                 keyboard_focus: None,
                 pointer_focus: None,
+                pointer: None,
+                pointer_gestures_manager,
+                pointer_gestures: None,
+                gesture_target: None,
+                gesture_events: Vec::new(),
+                pointer_constraints,
+                pointer_constraint: None,
+                pointer_constrained: false,
+                relative_pointer_state,
+                relative_pointer: None,
+                themed_pointer: None,
+                cursor_shapes: HashMap::new(),
                 last_serial: 0,
                 key_events: Vec::new(),
                 current_modifiers: InputModifiers::default(),
-                repeat_key: None,
-                repeat_start: None,
-                last_repeat: None,
-                repeat_delay_ms: 400, // Typical default: 400ms delay
-                repeat_rate_ms: 33,   // ~30 repeats per second
+                key_repeat: KeyRepeat::default(),
+                repeat_timer: None,
+                loop_handle: None,
                 pointer_events: Vec::new(),
+                touch: None,
+                touch_state: TouchState::default(),
+                touch_events: Vec::new(),
+                tablet_manager,
+                tablet_seat: None,
+                tablet_tools: HashMap::new(),
+                tablet_pad_focus: HashMap::new(),
+                tablet_events: Vec::new(),
                 pointer_x: 0.0,
                 pointer_y: 0.0,
                 data_device_manager,
                 drop_events: Vec::new(),
-                pending_drag_source: None,
-                pending_drag_data: None,
+                drag_events: Vec::new(),
+                drag_target: None,
+                drag_accepted: None,
+                dropped_offer: None,
+                drop_results: mpsc::channel(),
+                drag_source: None,
+                drag_content: None,
+                drag_source_action: DragAction::None,
+                drag_icon: None,
                 current_seat: None,
+                data_device: None,
+                clipboard_source: None,
+                clipboard_content: None,
+                primary_selection_manager,
+                primary_selection_device: None,
+                primary_selection_source: None,
+                primary_selection_content: None,
+                clipboard_events: Vec::new(),
+                clipboard_results: mpsc::channel(),
+                next_clipboard_read_id: 1,
+                wake: None,
+                handler_active: false,
+                pending_configures: Vec::new(),
+                close_requests: Vec::new(),
             },
             event_queue,
         ))
@@ -210,6 +486,7 @@ impl App {
         decorations: bool,
     ) -> WindowId {
         let surface = self.compositor_state.create_surface(qh);
+        let scale = self.create_surface_scale(qh, &surface);
         let decoration_mode = if decorations {
             WindowDecorations::ServerDefault
         } else {
@@ -232,6 +509,8 @@ impl App {
                 width,
                 height,
                 dirty: true,
+                scale,
+                frame: FrameState::default(),
             },
         );
 
@@ -247,6 +526,8 @@ impl App {
         let parent = self.windows.get_window(parent_id)?;
 
         let surface = self.compositor_state.create_surface(qh);
+        let mut scale = self.create_surface_scale(qh, &surface);
+        scale.inherit(&parent.scale);
 
         let positioner = XdgPositioner::new(&self.xdg_shell).ok()?;
         positioner.set_size(config.size.0 as i32, config.size.1 as i32);
@@ -287,6 +568,8 @@ impl App {
                 width: config.size.0,
                 height: config.size.1,
                 dirty: false, // Wait for configure event
+                scale,
+                frame: FrameState::default(),
             },
         );
 
@@ -330,6 +613,7 @@ impl App {
         let layer_shell = self.layer_shell.as_ref()?;
 
         let surface = self.compositor_state.create_surface(qh);
+        let scale = self.create_surface_scale(qh, &surface);
         let layer = layer_shell.create_layer_surface(
             qh,
             surface,
@@ -354,6 +638,8 @@ impl App {
                 width,
                 height,
                 dirty: false, // Wait for configure
+                scale,
+                frame: FrameState::default(),
             },
         );
 
@@ -385,6 +671,8 @@ impl App {
 
         // Create a new surface for the subsurface
         let surface = self.compositor_state.create_surface(qh);
+        let mut scale = self.create_surface_scale(qh, &surface);
+        scale.inherit(&parent.scale);
 
         // Create the subsurface relationship
         let subsurface = subcompositor.get_subsurface(&surface, parent_surface, qh, ());
@@ -411,6 +699,8 @@ impl App {
                 width,
                 height,
                 dirty: true, // Ready to render immediately
+                scale,
+                frame: FrameState::default(),
             },
         );
 
@@ -463,6 +753,8 @@ impl App {
 
         // Create a new surface for the attached surface
         let surface = self.compositor_state.create_surface(qh);
+        let mut scale = self.create_surface_scale(qh, &surface);
+        scale.inherit(&parent.scale);
 
         // Create the attached surface
         let id = self.windows.next_attached_surface_id();
@@ -492,6 +784,8 @@ impl App {
                 width,
                 height,
                 dirty: false, // Wait for configure
+                scale,
+                frame: FrameState::default(),
                 configured: false,
                 pending_configure: None,
             },
@@ -546,73 +840,395 @@ impl App {
             return;
         }
 
-        let width = attached.width;
-        let height = attached.height;
-        let surface = attached.surface.clone();
         attached.dirty = false;
 
-        let Some(pool) = self.pool.as_mut() else {
-            return;
-        };
+        self.present(SurfaceTarget::AttachedSurface(id), |_, canvas| draw(canvas));
+    }
+
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
 
-        let stride = width * 4;
-        let buffer_size = (stride * height) as usize;
+    /// Run the event loop, driving `handler` until [`App::quit`] is called or
+    /// the last window closes.
+    ///
+    /// Configure, close, input and render callbacks are dispatched per surface.
+    /// Surfaces are rendered when they are dirty or have requested an animation
+    /// frame, but never while a previous frame callback is still outstanding, so
+    /// rendering is paced by the compositor instead of spinning.
+    pub fn run<H: AppHandler>(
+        &mut self,
+        event_queue: EventQueue<Self>,
+        handler: &mut H,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut event_loop: EventLoop<'static, Self> = EventLoop::try_new()?;
+        WaylandSource::new(self.conn.clone(), event_queue)
+            .insert(event_loop.handle())
+            .map_err(|e| e.error)?;
+
+        let (wake, wake_source) = make_ping()?;
+        event_loop
+            .handle()
+            .insert_source(wake_source, |_, _, _| {})
+            .map_err(|e| e.error)?;
+        self.wake = Some(wake);
+        self.loop_handle = Some(event_loop.handle());
+        // A key may already be held from before the loop started
+        self.schedule_key_repeat();
+
+        self.handler_active = true;
+
+        while self.running {
+            self.dispatch_to_handler(handler);
+            if !self.running {
+                break;
+            }
+            self.render_dirty(handler);
+            self.flush();
 
-        if pool.len() < buffer_size {
-            pool.resize(buffer_size).ok();
+            event_loop.dispatch(None, self)?;
         }
 
-        let (buffer, canvas_data) = match pool.create_buffer(
-            width as i32,
-            height as i32,
-            stride as i32,
-            wl_shm::Format::Argb8888,
-        ) {
-            Ok((buf, data)) => (buf, data),
-            Err(_) => return,
-        };
+        self.handler_active = false;
+        self.wake = None;
+        self.loop_handle = None;
+        self.repeat_timer = None;
+        Ok(())
+    }
 
-        {
-            let mut canvas = Canvas::new(canvas_data, width, height);
-            draw(&mut canvas);
-            canvas.finalize_for_wayland();
+    /// Mark every surface dirty so the next loop iteration re-renders them all
+    pub fn request_redraw(&mut self) {
+        self.windows
+            .windows
+            .values_mut()
+            .for_each(|w| w.dirty = true);
+        self.windows
+            .popups
+            .values_mut()
+            .for_each(|p| p.dirty = true);
+        self.windows
+            .overlays
+            .values_mut()
+            .for_each(|o| o.dirty = true);
+        self.windows
+            .subsurfaces
+            .values_mut()
+            .for_each(|s| s.dirty = true);
+        self.windows
+            .attached_surfaces
+            .values_mut()
+            .filter(|s| s.configured)
+            .for_each(|s| s.dirty = true);
+    }
+
+    fn dispatch_to_handler<H: AppHandler>(&mut self, handler: &mut H) {
+        for configure in std::mem::take(&mut self.pending_configures) {
+            match configure {
+                PendingConfigure::Window(id) => {
+                    if let Some((width, height)) = self.window_size(id) {
+                        handler.on_window_configure(self, id, width, height);
+                    }
+                }
+                PendingConfigure::Popup(id) => {
+                    if let Some(p) = self.windows.get_popup(id) {
+                        let (width, height) = (p.width, p.height);
+                        handler.on_popup_configure(self, id, width, height);
+                    }
+                }
+                PendingConfigure::Overlay(id) => {
+                    if let Some(o) = self.windows.get_overlay(id) {
+                        let (width, height) = (o.width, o.height);
+                        handler.on_overlay_configure(self, id, width, height);
+                    }
+                }
+                PendingConfigure::AttachedSurface(id) => {
+                    if let Some(s) = self.windows.get_attached_surface(id) {
+                        let (width, height) = (s.width, s.height);
+                        handler.on_attached_surface_configure(self, id, width, height);
+                    }
+                }
+            }
         }
 
-        surface.attach(Some(buffer.wl_buffer()), 0, 0);
-        surface.damage_buffer(0, 0, width as i32, height as i32);
-        surface.commit();
+        for id in std::mem::take(&mut self.close_requests) {
+            if handler.on_close_request(self, id) {
+                self.close_window(id);
+                if self.windows.windows.is_empty() {
+                    self.quit();
+                }
+            }
+        }
+
+        for event in self.poll_key_events() {
+            handler.on_key(self, event.target, event);
+        }
+
+        for event in self.poll_ime_events() {
+            handler.on_ime(self, event);
+        }
+
+        for event in self.poll_pointer_events() {
+            handler.on_pointer(self, event.target, event);
+        }
+
+        for event in self.poll_touch_events() {
+            handler.on_touch(self, event.target, event);
+        }
+
+        for event in self.poll_gesture_events() {
+            handler.on_gesture(self, event.target, event);
+        }
+
+        for event in self.poll_tablet_events() {
+            handler.on_tablet(self, event.target, event);
+        }
+
+        for event in self.poll_clipboard_events() {
+            handler.on_clipboard(self, event);
+        }
+
+        for event in self.poll_drag_events() {
+            handler.on_drag(self, event);
+        }
+        // Handlers see file drops as `DragEvent::Drop`
+        self.drop_events.clear();
     }
 
-    pub fn quit(&mut self) {
-        self.running = false;
+    fn render_dirty<H: AppHandler>(&mut self, handler: &mut H) {
+        let dirty_windows: Vec<WindowId> = self
+            .windows
+            .windows
+            .values_mut()
+            .filter(|w| w.frame.should_render(w.dirty))
+            .map(|w| {
+                w.dirty = false;
+                w.id
+            })
+            .collect();
+        for id in dirty_windows {
+            self.present(SurfaceTarget::Window(id), |app, canvas| {
+                handler.on_render(app, id, canvas)
+            });
+        }
+
+        let dirty_popups: Vec<PopupId> = self
+            .windows
+            .popups
+            .values_mut()
+            .filter(|p| p.frame.should_render(p.dirty))
+            .map(|p| {
+                p.dirty = false;
+                p.id
+            })
+            .collect();
+        for id in dirty_popups {
+            self.present(SurfaceTarget::Popup(id), |app, canvas| {
+                handler.on_render_popup(app, id, canvas)
+            });
+        }
+
+        let dirty_overlays: Vec<OverlayId> = self
+            .windows
+            .overlays
+            .values_mut()
+            .filter(|o| o.frame.should_render(o.dirty))
+            .map(|o| {
+                o.dirty = false;
+                o.id
+            })
+            .collect();
+        for id in dirty_overlays {
+            self.present(SurfaceTarget::Overlay(id), |app, canvas| {
+                handler.on_render_overlay(app, id, canvas)
+            });
+        }
+
+        let dirty_subsurfaces: Vec<SubsurfaceId> = self
+            .windows
+            .subsurfaces
+            .values_mut()
+            .filter(|s| s.frame.should_render(s.dirty))
+            .map(|s| {
+                s.dirty = false;
+                s.id
+            })
+            .collect();
+        for id in dirty_subsurfaces {
+            self.present(SurfaceTarget::Subsurface(id), |app, canvas| {
+                handler.on_render_subsurface(app, id, canvas)
+            });
+        }
+
+        let dirty_attached: Vec<AttachedSurfaceId> = self
+            .windows
+            .attached_surfaces
+            .values_mut()
+            .filter(|s| s.configured && s.frame.should_render(s.dirty))
+            .map(|s| {
+                s.dirty = false;
+                s.id
+            })
+            .collect();
+        for id in dirty_attached {
+            self.present(SurfaceTarget::AttachedSurface(id), |app, canvas| {
+                handler.on_render_attached_surface(app, id, canvas)
+            });
+        }
     }
 
     /// Drain and return all pending key events (including repeat events)
     pub fn poll_key_events(&mut self) -> Vec<KeyEvent> {
-        // Generate repeat events if a key is held
-        if let (Some(key), Some(start)) = (&self.repeat_key, self.repeat_start) {
-            let now = std::time::Instant::now();
-            let elapsed = now.duration_since(start);
-            let delay = std::time::Duration::from_millis(self.repeat_delay_ms as u64);
-
-            if elapsed >= delay {
-                // Past initial delay, check for repeats
-                let rate = std::time::Duration::from_millis(self.repeat_rate_ms as u64);
-                let should_repeat = match self.last_repeat {
-                    None => true,
-                    Some(last) => now.duration_since(last) >= rate,
-                };
-
-                if should_repeat {
-                    self.last_repeat = Some(now);
-                    let mut repeat_event = key.clone();
-                    repeat_event.state = KeyState::Pressed;
-                    self.key_events.push(repeat_event);
+        // Without `App::run` there is no timer, so repeats are generated here
+        if self.repeat_timer.is_none()
+            && let Some(event) = self.key_repeat.due(Instant::now())
+        {
+            self.key_events.push(event);
+        }
+
+        std::mem::take(&mut self.key_events)
+    }
+
+    /// (Re)arm the key repeat timer for the current repeat deadline
+    fn schedule_key_repeat(&mut self) {
+        let Some(handle) = self.loop_handle.clone() else {
+            return;
+        };
+        if let Some(token) = self.repeat_timer.take() {
+            handle.remove(token);
+        }
+        let Some(deadline) = self.key_repeat.deadline() else {
+            return;
+        };
+
+        self.repeat_timer = handle
+            .insert_source(Timer::from_deadline(deadline), |_, _, app| {
+                if let Some(event) = app.key_repeat.due(Instant::now()) {
+                    app.key_events.push(event);
                 }
+                match app.key_repeat.deadline() {
+                    Some(next) => TimeoutAction::ToInstant(next),
+                    None => {
+                        app.repeat_timer = None;
+                        TimeoutAction::Drop
+                    }
+                }
+            })
+            .ok();
+    }
+
+    /// Drain input method events: preedit updates, commits and deletions
+    pub fn poll_ime_events(&mut self) -> Vec<ImeEvent> {
+        std::mem::take(&mut self.ime_events)
+    }
+
+    /// Check if the compositor supports input methods (text-input-v3)
+    pub fn has_ime(&self) -> bool {
+        self.text_input_manager.is_some()
+    }
+
+    /// Use the input method for `target` whenever it has text focus.
+    /// Text fields call this when they gain focus.
+    pub fn enable_ime(&mut self, target: SurfaceTarget) {
+        self.ime.wanted.insert(target);
+        self.sync_ime();
+    }
+
+    /// Stop using the input method for `target`
+    pub fn disable_ime(&mut self, target: SurfaceTarget) {
+        self.ime.wanted.remove(&target);
+        self.sync_ime();
+    }
+
+    /// Tell the input method what kind of text is being edited
+    pub fn set_ime_purpose(&mut self, purpose: ImePurpose) {
+        self.ime.purpose = purpose;
+        if self.ime.active {
+            self.send_ime_state();
+        }
+    }
+
+    /// Set the text cursor rectangle, in surface-local logical coordinates,
+    /// so the input method can place its candidate popup next to it
+    pub fn set_ime_cursor_rect(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        self.ime.cursor_rect = Some((x, y, width, height));
+        if self.ime.active {
+            self.send_ime_state();
+        }
+    }
+
+    /// Give the input method the text around the cursor, with `cursor` and
+    /// `anchor` (the other end of the selection) as byte offsets into `text`
+    pub fn set_ime_surrounding_text(&mut self, text: &str, cursor: usize, anchor: usize) {
+        self.ime.surrounding = Some(clamp_surrounding(text, cursor, anchor));
+        if self.ime.active {
+            self.send_ime_state();
+        }
+    }
+
+    /// Enable or disable the text input to match the focused surface's wishes
+    fn sync_ime(&mut self) {
+        let Some(text_input) = self.text_input.clone() else {
+            return;
+        };
+        let want = self
+            .ime
+            .focus
+            .is_some_and(|target| self.ime.wanted.contains(&target));
+
+        if want && !self.ime.active {
+            self.ime.active = true;
+            // Enabling resets all state, so everything is sent again
+            text_input.enable();
+            self.send_ime_state();
+        } else if !want && self.ime.active {
+            self.ime.active = false;
+            text_input.disable();
+            self.commit_ime();
+            if let Some(target) = self.ime.focus
+                && let Some(event) = self.ime.clear_preedit(target)
+            {
+                self.ime_events.push(event);
             }
         }
+    }
+
+    fn send_ime_state(&mut self) {
+        let Some(ref text_input) = self.text_input else {
+            return;
+        };
+        let (hint, purpose) = match self.ime.purpose {
+            ImePurpose::Normal => (ContentHint::None, ContentPurpose::Normal),
+            ImePurpose::Digits => (ContentHint::None, ContentPurpose::Digits),
+            ImePurpose::Number => (ContentHint::None, ContentPurpose::Number),
+            ImePurpose::Phone => (ContentHint::None, ContentPurpose::Phone),
+            ImePurpose::Url => (ContentHint::None, ContentPurpose::Url),
+            ImePurpose::Email => (ContentHint::None, ContentPurpose::Email),
+            ImePurpose::Name => (ContentHint::None, ContentPurpose::Name),
+            ImePurpose::Password => (
+                ContentHint::HiddenText | ContentHint::SensitiveData,
+                ContentPurpose::Password,
+            ),
+            ImePurpose::Pin => (
+                ContentHint::HiddenText | ContentHint::SensitiveData,
+                ContentPurpose::Pin,
+            ),
+            ImePurpose::Terminal => (ContentHint::None, ContentPurpose::Terminal),
+        };
+        text_input.set_content_type(hint, purpose);
+        if let Some((x, y, width, height)) = self.ime.cursor_rect {
+            text_input.set_cursor_rectangle(x, y, width, height);
+        }
+        if let Some((ref text, cursor, anchor)) = self.ime.surrounding {
+            text_input.set_surrounding_text(text.clone(), cursor, anchor);
+        }
+        self.commit_ime();
+    }
 
-        std::mem::take(&mut self.key_events)
+    fn commit_ime(&mut self) {
+        if let Some(ref text_input) = self.text_input {
+            text_input.commit();
+            self.ime.commits = self.ime.commits.wrapping_add(1);
+        }
     }
 
     /// Poll for pointer events (clicks, motion, scroll)
@@ -620,17 +1236,173 @@ impl App {
         std::mem::take(&mut self.pointer_events)
     }
 
-    /// Get current pointer position
+    /// Poll for touchpad gesture events (swipe, pinch, hold)
+    pub fn poll_gesture_events(&mut self) -> Vec<GestureEvent> {
+        std::mem::take(&mut self.gesture_events)
+    }
+
+    /// Check if the compositor supports touchpad gestures
+    pub fn has_pointer_gestures(&self) -> bool {
+        self.pointer_gestures_manager.is_some()
+    }
+
+    /// Lock the pointer in place over `surface`, e.g. while dragging a split
+    /// divider or panning a canvas. Motion is then only reported as
+    /// `PointerEventKind::RelativeMotion`. The lock starts once the pointer is
+    /// over `surface` and ends when it loses pointer focus or on `release_pointer`.
+    pub fn lock_pointer(&mut self, surface: SurfaceTarget) -> bool {
+        self.constrain_pointer(surface, None, true)
+    }
+
+    /// Keep the pointer inside `region` of `surface`, as (x, y, width, height)
+    /// in surface-local coordinates, or inside the whole surface if None.
+    /// Ends like `lock_pointer`.
+    pub fn confine_pointer(
+        &mut self,
+        surface: SurfaceTarget,
+        region: Option<(i32, i32, i32, i32)>,
+    ) -> bool {
+        self.constrain_pointer(surface, region, false)
+    }
+
+    /// Release a pointer lock or confinement
+    pub fn release_pointer(&mut self) {
+        if let Some(constraint) = self.pointer_constraint.take() {
+            constraint.destroy();
+        }
+        self.pointer_constrained = false;
+    }
+
+    /// Whether a pointer lock or confinement is currently in effect
+    pub fn is_pointer_constrained(&self) -> bool {
+        self.pointer_constrained
+    }
+
+    /// Check if the compositor supports locking and confining the pointer
+    pub fn has_pointer_constraints(&self) -> bool {
+        self.pointer_constraints.bound_global().is_ok()
+    }
+
+    fn constrain_pointer(
+        &mut self,
+        target: SurfaceTarget,
+        region: Option<(i32, i32, i32, i32)>,
+        lock: bool,
+    ) -> bool {
+        // A surface can only have one constraint at a time
+        self.release_pointer();
+        let (Some(pointer), Some(surface)) = (&self.pointer, self.windows.surface_for(target))
+        else {
+            return false;
+        };
+        let region = match region {
+            Some((x, y, width, height)) => match Region::new(&self.compositor_state) {
+                Ok(region) => {
+                    region.add(x, y, width, height);
+                    Some(region)
+                }
+                Err(_) => return false,
+            },
+            None => None,
+        };
+        let wl_region = region.as_ref().map(Region::wl_region);
+
+        let lifetime = Lifetime::Oneshot;
+        let constraint = if lock {
+            self.pointer_constraints
+                .lock_pointer(surface, pointer, wl_region, lifetime, &self.qh)
+                .map(PointerConstraint::Locked)
+        } else {
+            self.pointer_constraints
+                .confine_pointer(surface, pointer, wl_region, lifetime, &self.qh)
+                .map(PointerConstraint::Confined)
+        };
+        match constraint {
+            Ok(constraint) => {
+                self.pointer_constraint = Some(constraint);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn push_gesture(&mut self, kind: GestureEventKind) {
+        if let Some(target) = self.gesture_target {
+            self.gesture_events.push(GestureEvent { target, kind });
+        }
+    }
+
+    /// Poll for drawing tablet events (tools and pads)
+    pub fn poll_tablet_events(&mut self) -> Vec<TabletEvent> {
+        std::mem::take(&mut self.tablet_events)
+    }
+
+    /// Check if the compositor supports drawing tablets
+    pub fn has_tablet(&self) -> bool {
+        self.tablet_manager.is_some()
+    }
+
+    fn push_pad_event(&mut self, pad: &ZwpTabletPadV2, kind: TabletEventKind) {
+        if let Some(&target) = self.tablet_pad_focus.get(&pad.id()) {
+            self.tablet_events.push(TabletEvent { target, kind });
+        }
+    }
+
+    /// Poll for touch events (down, motion, up, cancel, frame)
+    pub fn poll_touch_events(&mut self) -> Vec<TouchEvent> {
+        std::mem::take(&mut self.touch_events)
+    }
+
+    /// Get current pointer position, local to the surface in `pointer_focus`
     pub fn pointer_position(&self) -> (f64, f64) {
         (self.pointer_x, self.pointer_y)
     }
 
+    /// The surface currently under the pointer
+    pub fn pointer_focus(&self) -> Option<SurfaceTarget> {
+        self.pointer_focus
+    }
+
+    /// Set the cursor shown while the pointer is over `surface`
+    pub fn set_cursor(&mut self, surface: SurfaceTarget, shape: CursorShape) {
+        let previous = self.cursor_shapes.insert(surface, shape);
+        if previous != Some(shape) && self.pointer_focus == Some(surface) {
+            self.apply_cursor(surface);
+        }
+    }
+
+    /// The cursor shape set for `surface`
+    pub fn cursor(&self, surface: SurfaceTarget) -> CursorShape {
+        self.cursor_shapes
+            .get(&surface)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn apply_cursor(&self, surface: SurfaceTarget) {
+        let Some(ref pointer) = self.themed_pointer else {
+            return;
+        };
+        let result = match self.cursor(surface).icon() {
+            Some(icon) => pointer.set_cursor(&self.conn, icon),
+            None => pointer.hide_cursor(),
+        };
+        if let Err(e) = result {
+            log::warn!("failed to set cursor: {}", e);
+        }
+    }
+
+    /// The surface currently holding keyboard focus
+    pub fn keyboard_focus(&self) -> Option<SurfaceTarget> {
+        self.keyboard_focus
+    }
+
     /// Get current modifier state
     pub fn modifiers(&self) -> InputModifiers {
         self.current_modifiers
     }
 
-    /// Poll for completed drop events
+    /// Poll for completed drops of local files (`text/uri-list`)
     pub fn poll_drop_events(&mut self) -> Vec<DropEvent> {
         std::mem::take(&mut self.drop_events)
     }
@@ -645,152 +1417,421 @@ impl App {
         self.current_seat.is_some()
     }
 
-    /// Start a drag operation with the given file paths
-    /// Returns true if the drag was started successfully
-    pub fn start_drag(
-        &mut self,
-        qh: &QueueHandle<Self>,
-        window_id: WindowId,
-        files: &[std::path::PathBuf],
-    ) -> bool {
-        // Need data device manager, seat, and a window surface
-        let Some(ref ddm) = self.data_device_manager else {
-            return false;
-        };
-        let Some(ref seat) = self.current_seat else {
+    /// Take ownership of the clipboard, offering `data` under each of `mime_types`.
+    /// Must be called in response to user input (a key press or click).
+    /// Returns false if there is no data device or seat.
+    pub fn set_clipboard(&mut self, mime_types: &[&str], data: &[u8]) -> bool {
+        let (Some(ddm), Some(data_device)) = (&self.data_device_manager, &self.data_device) else {
             return false;
         };
-        let Some(window) = self.windows.get_window(window_id) else {
+
+        let source = ddm.create_copy_paste_source(&self.qh, mime_types.iter().copied());
+        source.set_selection(data_device, self.last_serial);
+
+        self.clipboard_source = Some(source);
+        self.clipboard_content = Some(SelectionContent::new(mime_types, data));
+        self.flush();
+        true
+    }
+
+    /// Copy text to the clipboard under all the common text MIME types
+    pub fn set_clipboard_text(&mut self, text: &str) -> bool {
+        self.set_clipboard(TEXT_MIME_TYPES, text.as_bytes())
+    }
+
+    /// Take ownership of the primary selection, offering `data` under each of
+    /// `mime_types`. Text widgets call this whenever the user selects text.
+    /// Returns false if the compositor doesn't support primary selection.
+    pub fn set_primary_selection(&mut self, mime_types: &[&str], data: &[u8]) -> bool {
+        let (Some(manager), Some(device)) = (
+            &self.primary_selection_manager,
+            &self.primary_selection_device,
+        ) else {
             return false;
         };
 
-        // Build URI list data with absolute paths
-        let uri_list: String = files
-            .iter()
-            .filter_map(|p| p.canonicalize().ok())
-            .filter_map(|p| p.to_str().map(|s| s.to_string()))
-            .map(|s| format!("file://{}\r\n", s))
-            .collect();
+        let source = manager.create_selection_source(&self.qh, mime_types.iter().copied());
+        source.set_selection(device, self.last_serial);
 
-        if uri_list.is_empty() {
-            return false;
-        }
+        self.primary_selection_source = Some(source);
+        self.primary_selection_content = Some(SelectionContent::new(mime_types, data));
+        self.flush();
+        true
+    }
 
-        // Store data to send later
-        self.pending_drag_data = Some(uri_list.into_bytes());
+    /// Set the primary selection to text under all the common text MIME types
+    pub fn set_primary_selection_text(&mut self, text: &str) -> bool {
+        self.set_primary_selection(TEXT_MIME_TYPES, text.as_bytes())
+    }
 
-        // Create drag source with text/uri-list MIME type
-        use smithay_client_toolkit::reexports::client::protocol::wl_data_device_manager::DndAction;
-        let drag_source = ddm.create_drag_and_drop_source(
-            qh,
-            ["text/uri-list"],
-            DndAction::Copy | DndAction::Move,
-        );
+    /// Check if the compositor supports the primary selection protocol
+    pub fn has_primary_selection(&self) -> bool {
+        self.primary_selection_manager.is_some()
+    }
 
-        // Get data device for this seat
-        let data_device = ddm.get_data_device(qh, seat);
+    /// MIME types offered by the current clipboard selection
+    pub fn clipboard_mime_types(&self) -> Vec<String> {
+        self.selection_mime_types(Selection::Clipboard)
+    }
 
-        // Start the drag
-        let surface = window.xdg.wl_surface();
-        drag_source.start_drag(&data_device, surface, None, self.last_serial);
+    /// MIME types offered by the current primary selection
+    pub fn primary_selection_mime_types(&self) -> Vec<String> {
+        self.selection_mime_types(Selection::Primary)
+    }
 
-        // Store the drag source to keep it alive
-        self.pending_drag_source = Some(drag_source);
+    /// Start reading the clipboard as `mime`.
+    ///
+    /// The data is read in the background and delivered as a
+    /// [`ClipboardEvent::Data`] with the returned id, through
+    /// [`App::poll_clipboard_events`] or [`AppHandler::on_clipboard`].
+    /// Returns None if nothing on the clipboard is offered as `mime`.
+    pub fn read_clipboard(&mut self, mime: &str) -> Option<ClipboardReadId> {
+        self.read_selection(Selection::Clipboard, mime)
+    }
 
-        true
+    /// Read the clipboard as text, picking the best text MIME type on offer
+    pub fn read_clipboard_text(&mut self) -> Option<ClipboardReadId> {
+        let mime = best_mime_type(&self.clipboard_mime_types(), TEXT_MIME_TYPES)?;
+        self.read_clipboard(&mime)
     }
 
-    pub fn render_window<F>(&mut self, window_id: WindowId, mut draw: F)
-    where
-        F: FnMut(&mut Canvas),
-    {
-        let Some(window) = self.windows.get_window_mut(window_id) else {
+    /// Start reading the primary selection as `mime`; see [`App::read_clipboard`]
+    pub fn read_primary_selection(&mut self, mime: &str) -> Option<ClipboardReadId> {
+        self.read_selection(Selection::Primary, mime)
+    }
+
+    /// Read the primary selection as text, typically on a middle click
+    pub fn read_primary_selection_text(&mut self) -> Option<ClipboardReadId> {
+        let mime = best_mime_type(&self.primary_selection_mime_types(), TEXT_MIME_TYPES)?;
+        self.read_primary_selection(&mime)
+    }
+
+    fn selection_content(&self, selection: Selection) -> Option<&SelectionContent> {
+        match selection {
+            Selection::Clipboard => self.clipboard_content.as_ref(),
+            Selection::Primary => self.primary_selection_content.as_ref(),
+        }
+    }
+
+    fn selection_mime_types(&self, selection: Selection) -> Vec<String> {
+        if let Some(content) = self.selection_content(selection) {
+            return content.mime_types();
+        }
+        match selection {
+            Selection::Clipboard => self
+                .data_device
+                .as_ref()
+                .and_then(|d| d.data().selection_offer())
+                .map(|offer| offer.with_mime_types(|m| m.to_vec())),
+            Selection::Primary => self
+                .primary_selection_device
+                .as_ref()
+                .and_then(|d| d.data().selection_offer())
+                .map(|offer| offer.with_mime_types(|m| m.to_vec())),
+        }
+        .unwrap_or_default()
+    }
+
+    fn read_selection(&mut self, selection: Selection, mime: &str) -> Option<ClipboardReadId> {
+        // Reading our own selection through the compositor works, but there is no need
+        if let Some(content) = self.selection_content(selection) {
+            let data = content.data(mime)?.to_vec();
+            let id = self.next_clipboard_read_id();
+            self.clipboard_events
+                .push(ClipboardEvent::Data(ClipboardData {
+                    id,
+                    selection,
+                    mime_type: mime.to_string(),
+                    data: Ok(data),
+                }));
+            return Some(id);
+        }
+
+        if !self
+            .selection_mime_types(selection)
+            .iter()
+            .any(|m| m == mime)
+        {
+            return None;
+        }
+        let pipe = match selection {
+            Selection::Clipboard => self
+                .data_device
+                .as_ref()?
+                .data()
+                .selection_offer()?
+                .receive(mime.to_string())
+                .ok()?,
+            Selection::Primary => self
+                .primary_selection_device
+                .as_ref()?
+                .data()
+                .selection_offer()?
+                .receive(mime.to_string())
+                .ok()?,
+        };
+        // The source only starts writing once it sees our receive request
+        self.flush();
+
+        let id = self.next_clipboard_read_id();
+        let mime_type = mime.to_string();
+        read_pipe(
+            pipe,
+            self.clipboard_results.0.clone(),
+            self.wake.clone(),
+            move |data| ClipboardData {
+                id,
+                selection,
+                mime_type,
+                data,
+            },
+        );
+        Some(id)
+    }
+
+    /// Drain clipboard and primary selection events: selection changes and completed reads
+    pub fn poll_clipboard_events(&mut self) -> Vec<ClipboardEvent> {
+        let mut events = std::mem::take(&mut self.clipboard_events);
+        events.extend(
+            self.clipboard_results
+                .1
+                .try_iter()
+                .map(ClipboardEvent::Data),
+        );
+        events
+    }
+
+    fn next_clipboard_read_id(&mut self) -> ClipboardReadId {
+        let id = ClipboardReadId(self.next_clipboard_read_id);
+        self.next_clipboard_read_id += 1;
+        id
+    }
+
+    /// Drain drag-and-drop events, for drags over our surfaces and drags we started
+    pub fn poll_drag_events(&mut self) -> Vec<DragEvent> {
+        let mut events = std::mem::take(&mut self.drag_events);
+        let results: Vec<DropData> = self.drop_results.1.try_iter().collect();
+        for data in results {
+            // The payload is in; let the source know it can clean up
+            if let Some(offer) = self.dropped_offer.take() {
+                offer.finish();
+                offer.destroy();
+            }
+            let files = data.files();
+            if !files.is_empty() {
+                self.drop_events.push(DropEvent {
+                    x: data.x,
+                    y: data.y,
+                    files,
+                });
+            }
+            events.push(DragEvent::Drop(data));
+        }
+        events
+    }
+
+    /// MIME types offered by the drag currently over one of our surfaces
+    pub fn drag_mime_types(&self) -> Vec<String> {
+        self.current_drag_offer()
+            .map(|offer| offer.with_mime_types(|m| m.to_vec()))
+            .unwrap_or_default()
+    }
+
+    /// Accept the current drag as `mime`, or reject it with None.
+    /// The accepted type is read and delivered as [`DragEvent::Drop`] when dropped.
+    pub fn accept_drag(&mut self, mime: Option<&str>) {
+        let Some(offer) = self.current_drag_offer() else {
             return;
         };
+        offer.accept_mime_type(offer.serial, mime.map(str::to_string));
+        self.drag_accepted = mime.map(str::to_string);
+    }
 
-        let width = window.width;
-        let height = window.height;
-        let surface = window.xdg.wl_surface().clone();
-        window.dirty = false;
+    /// Choose which of the source's actions a drop here may perform, and
+    /// which one is preferred when the user doesn't pick with a modifier
+    pub fn set_drag_actions(&mut self, actions: &[DragAction], preferred: DragAction) {
+        if let Some(offer) = self.current_drag_offer() {
+            offer.set_actions(DragAction::set_to_wl(actions), preferred.to_wl());
+        }
+    }
 
-        let Some(pool) = self.pool.as_mut() else {
+    /// Settle a drop made with [`DragAction::Ask`]. Passing `DragAction::None`
+    /// (or `Ask`) cancels the drop.
+    pub fn resolve_drop(&mut self, action: DragAction) {
+        let Some(offer) = self.dropped_offer.take() else {
+            return;
+        };
+        let target = self.windows.find_target_by_surface(&offer.surface);
+        let (Some(target), Some(mime), DragAction::Copy | DragAction::Move) =
+            (target, self.drag_accepted.clone(), action)
+        else {
+            offer.destroy();
             return;
         };
 
-        let stride = width * 4;
-        let buffer_size = (stride * height) as usize;
+        offer.set_actions(action.to_wl(), action.to_wl());
+        self.read_drop(offer, target, mime, action);
+    }
 
-        // Resize pool if needed
-        if pool.len() < buffer_size {
-            pool.resize(buffer_size).ok();
+    fn current_drag_offer(&self) -> Option<DragOffer> {
+        self.data_device.as_ref()?.data().drag_offer()
+    }
+
+    fn read_drop(
+        &mut self,
+        offer: DragOffer,
+        target: SurfaceTarget,
+        mime: String,
+        action: DragAction,
+    ) {
+        let Ok(pipe) = offer.receive(mime.clone()) else {
+            offer.destroy();
+            return;
+        };
+        // The source only starts writing once it sees our receive request
+        self.flush();
+
+        let (x, y) = (offer.x, offer.y);
+        self.dropped_offer = Some(offer);
+        read_pipe(
+            pipe,
+            self.drop_results.0.clone(),
+            self.wake.clone(),
+            move |data| DropData {
+                target,
+                x,
+                y,
+                action,
+                mime_type: mime,
+                data,
+            },
+        );
+    }
+
+    /// Start dragging from `origin`, offering each `(mime, data)` payload.
+    /// Must be called in response to a pointer press. Returns true if the drag
+    /// was started; its outcome arrives as [`DragEvent::SourceFinished`] or
+    /// [`DragEvent::SourceCancelled`].
+    pub fn start_drag(
+        &mut self,
+        origin: SurfaceTarget,
+        payloads: &[(&str, &[u8])],
+        actions: &[DragAction],
+        icon: Option<DragIcon>,
+    ) -> bool {
+        if self.data_device.is_none() || payloads.is_empty() {
+            return false;
         }
+        let Some(surface) = self.windows.surface_for(origin).cloned() else {
+            return false;
+        };
 
-        let (buffer, canvas_data) = match pool.create_buffer(
-            width as i32,
-            height as i32,
-            stride as i32,
-            wl_shm::Format::Argb8888,
-        ) {
-            Ok((buf, data)) => (buf, data),
-            Err(_) => return,
+        // Icons have no viewport, so render them at the next integer scale
+        let scale = self
+            .windows
+            .scale_of(origin)
+            .map(|s| s.factor().ceil() as i32)
+            .unwrap_or(1);
+        let icon = icon.and_then(|icon| self.create_drag_icon(icon, scale));
+
+        let (Some(ddm), Some(data_device)) = (&self.data_device_manager, &self.data_device) else {
+            return false;
         };
+        let content = SelectionContent::from_payloads(payloads);
+        let drag_source = ddm.create_drag_and_drop_source(
+            &self.qh,
+            content.mime_types(),
+            DragAction::set_to_wl(actions),
+        );
+        drag_source.start_drag(
+            data_device,
+            &surface,
+            icon.as_ref().map(|(surface, _)| surface),
+            self.last_serial,
+        );
 
-        // Create canvas and let user draw
-        {
-            let mut canvas = Canvas::new(canvas_data, width, height);
-            draw(&mut canvas);
-            canvas.finalize_for_wayland();
+        self.end_outgoing_drag();
+        self.drag_source = Some(drag_source);
+        self.drag_content = Some(content);
+        self.drag_icon = icon;
+        self.flush();
+        true
+    }
+
+    /// Start dragging local files as a `text/uri-list`
+    pub fn start_file_drag(&mut self, origin: SurfaceTarget, files: &[std::path::PathBuf]) -> bool {
+        let uri_list = files_to_uri_list(files);
+        if uri_list.is_empty() {
+            return false;
         }
+        self.start_drag(
+            origin,
+            &[(MIME_URI_LIST, uri_list.as_bytes())],
+            &[DragAction::Copy, DragAction::Move],
+            None,
+        )
+    }
 
-        // Attach and commit
-        surface.attach(Some(buffer.wl_buffer()), 0, 0);
+    fn create_drag_icon(
+        &mut self,
+        icon: DragIcon,
+        scale: i32,
+    ) -> Option<(wl_surface::WlSurface, Buffer)> {
+        let surface = self.compositor_state.create_surface(&self.qh);
+        let (width, height) = (icon.width * scale as u32, icon.height * scale as u32);
+        let draw = icon.draw;
+        let buffer = self.draw_to_buffer(width, height, scale as f32, |_, canvas| draw(canvas))?;
+
+        if surface.version() >= 3 {
+            surface.set_buffer_scale(scale);
+        }
+        let (hx, hy) = icon.hotspot;
+        if surface.version() >= 5 {
+            surface.offset(-hx, -hy);
+            surface.attach(Some(buffer.wl_buffer()), 0, 0);
+        } else {
+            surface.attach(Some(buffer.wl_buffer()), -hx, -hy);
+        }
         surface.damage_buffer(0, 0, width as i32, height as i32);
         surface.commit();
+        Some((surface, buffer))
     }
 
-    pub fn render_popup<F>(&mut self, popup_id: PopupId, mut draw: F)
+    fn end_outgoing_drag(&mut self) {
+        if let Some(source) = self.drag_source.take() {
+            source.inner().destroy();
+        }
+        if let Some((surface, _buffer)) = self.drag_icon.take() {
+            surface.destroy();
+        }
+        self.drag_content = None;
+        self.drag_source_action = DragAction::None;
+    }
+
+    pub fn render_window<F>(&mut self, window_id: WindowId, mut draw: F)
     where
         F: FnMut(&mut Canvas),
     {
-        let Some(popup) = self.windows.get_popup_mut(popup_id) else {
-            return;
-        };
-
-        let width = popup.width;
-        let height = popup.height;
-        let surface = popup.xdg.wl_surface().clone();
-        popup.dirty = false;
-
-        let Some(pool) = self.pool.as_mut() else {
+        let Some(window) = self.windows.get_window_mut(window_id) else {
             return;
         };
 
-        let stride = width * 4;
-        let buffer_size = (stride * height) as usize;
+        window.dirty = false;
 
-        if pool.len() < buffer_size {
-            pool.resize(buffer_size).ok();
-        }
+        self.present(SurfaceTarget::Window(window_id), |_, canvas| draw(canvas));
+    }
 
-        let (buffer, canvas_data) = match pool.create_buffer(
-            width as i32,
-            height as i32,
-            stride as i32,
-            wl_shm::Format::Argb8888,
-        ) {
-            Ok((buf, data)) => (buf, data),
-            Err(_) => return,
+    pub fn render_popup<F>(&mut self, popup_id: PopupId, mut draw: F)
+    where
+        F: FnMut(&mut Canvas),
+    {
+        let Some(popup) = self.windows.get_popup_mut(popup_id) else {
+            return;
         };
 
-        {
-            let mut canvas = Canvas::new(canvas_data, width, height);
-            draw(&mut canvas);
-            canvas.finalize_for_wayland();
-        }
+        popup.dirty = false;
 
-        surface.attach(Some(buffer.wl_buffer()), 0, 0);
-        surface.damage_buffer(0, 0, width as i32, height as i32);
-        surface.commit();
+        self.present(SurfaceTarget::Popup(popup_id), |_, canvas| draw(canvas));
     }
 
     pub fn is_window_dirty(&self, window_id: WindowId) -> bool {
@@ -822,86 +1863,163 @@ impl App {
             return;
         };
 
-        let width = overlay.width;
-        let height = overlay.height;
-        let surface = overlay.layer.wl_surface().clone();
         overlay.dirty = false;
 
-        let Some(pool) = self.pool.as_mut() else {
+        self.present(SurfaceTarget::Overlay(overlay_id), |_, canvas| draw(canvas));
+    }
+
+    pub fn render_subsurface<F>(&mut self, subsurface_id: SubsurfaceId, mut draw: F)
+    where
+        F: FnMut(&mut Canvas),
+    {
+        let Some(subsurface) = self.windows.get_subsurface_mut(subsurface_id) else {
             return;
         };
 
-        let stride = width * 4;
-        let buffer_size = (stride * height) as usize;
+        subsurface.dirty = false;
 
-        if pool.len() < buffer_size {
-            pool.resize(buffer_size).ok();
-        }
+        self.present(SurfaceTarget::Subsurface(subsurface_id), |_, canvas| {
+            draw(canvas)
+        });
+    }
 
-        let (buffer, canvas_data) = match pool.create_buffer(
-            width as i32,
-            height as i32,
-            stride as i32,
-            wl_shm::Format::Argb8888,
-        ) {
-            Ok((buf, data)) => (buf, data),
-            Err(_) => return,
+    /// Allocate a shm buffer at the surface's physical size, let `draw` fill it,
+    /// then attach and commit it.
+    /// The pool is taken out of `self` while drawing so `draw` can also borrow the app.
+    fn present<F>(&mut self, target: SurfaceTarget, draw: F)
+    where
+        F: FnOnce(&mut Self, &mut Canvas),
+    {
+        let (Some(surface), Some((width, height)), Some(scale)) = (
+            self.windows.surface_for(target).cloned(),
+            self.windows.size_of(target),
+            self.windows.scale_of(target),
+        ) else {
+            return;
         };
 
-        {
-            let mut canvas = Canvas::new(canvas_data, width, height);
-            draw(&mut canvas);
-            canvas.finalize_for_wayland();
+        let (buffer_width, buffer_height) = scale.physical_size(width, height);
+        let factor = scale.factor() as f32;
+        scale.apply(&surface, width, height);
+
+        if let Some(frame) = self.windows.frame_of_mut(target) {
+            frame.begin();
         }
+        let Some(buffer) = self.draw_to_buffer(buffer_width, buffer_height, factor, draw) else {
+            return;
+        };
 
+        // Ask to be told when the compositor wants the next frame, then attach and commit
+        surface.frame(&self.qh, surface.clone());
         surface.attach(Some(buffer.wl_buffer()), 0, 0);
-        surface.damage_buffer(0, 0, width as i32, height as i32);
+        surface.damage_buffer(0, 0, buffer_width as i32, buffer_height as i32);
         surface.commit();
+
+        if let Some(frame) = self.windows.frame_of_mut(target) {
+            frame.committed();
+        }
     }
 
-    pub fn render_subsurface<F>(&mut self, subsurface_id: SubsurfaceId, mut draw: F)
+    /// Allocate a buffer from the shared pool and let `draw` fill it
+    fn draw_to_buffer<F>(
+        &mut self,
+        buffer_width: u32,
+        buffer_height: u32,
+        factor: f32,
+        draw: F,
+    ) -> Option<Buffer>
     where
-        F: FnMut(&mut Canvas),
+        F: FnOnce(&mut Self, &mut Canvas),
     {
-        let Some(subsurface) = self.windows.get_subsurface_mut(subsurface_id) else {
-            return;
-        };
-
-        let width = subsurface.width;
-        let height = subsurface.height;
-        let surface = subsurface.surface.clone();
-        subsurface.dirty = false;
-
-        let Some(pool) = self.pool.as_mut() else {
-            return;
-        };
+        let mut pool = self.pool.take()?;
 
-        let stride = width * 4;
-        let buffer_size = (stride * height) as usize;
+        let stride = buffer_width * 4;
+        let buffer_size = (stride * buffer_height) as usize;
 
+        // Resize pool if needed
         if pool.len() < buffer_size {
             pool.resize(buffer_size).ok();
         }
 
         let (buffer, canvas_data) = match pool.create_buffer(
-            width as i32,
-            height as i32,
+            buffer_width as i32,
+            buffer_height as i32,
             stride as i32,
             wl_shm::Format::Argb8888,
         ) {
             Ok((buf, data)) => (buf, data),
-            Err(_) => return,
+            Err(_) => {
+                self.pool = Some(pool);
+                return None;
+            }
         };
 
+        // Create canvas and let user draw
         {
-            let mut canvas = Canvas::new(canvas_data, width, height);
-            draw(&mut canvas);
+            let mut canvas = Canvas::with_scale(canvas_data, buffer_width, buffer_height, factor);
+            draw(self, &mut canvas);
             canvas.finalize_for_wayland();
         }
 
-        surface.attach(Some(buffer.wl_buffer()), 0, 0);
-        surface.damage_buffer(0, 0, width as i32, height as i32);
-        surface.commit();
+        self.pool = Some(pool);
+        Some(buffer)
+    }
+
+    fn create_surface_scale(
+        &self,
+        qh: &QueueHandle<Self>,
+        surface: &wl_surface::WlSurface,
+    ) -> SurfaceScale {
+        // Fractional scaling needs both protocols: the preferred scale and a
+        // viewport to map the larger buffer back onto the logical size
+        match (&self.fractional_scale_manager, &self.viewporter) {
+            (Some(manager), Some(viewporter)) => SurfaceScale::new(
+                Some(manager.get_fractional_scale(surface, qh, surface.clone())),
+                Some(viewporter.get_viewport(surface, qh, ())),
+            ),
+            _ => SurfaceScale::default(),
+        }
+    }
+
+    /// Render `target` again on the compositor's next frame.
+    ///
+    /// Call this from a render callback to keep an animation running at the
+    /// display's refresh rate; use [`App::frame_time`] to advance it.
+    pub fn request_animation_frame(&mut self, target: SurfaceTarget) {
+        if let Some(frame) = self.windows.frame_of_mut(target) {
+            frame.wants_frame = true;
+        }
+    }
+
+    /// Whether `target` has asked for an animation frame that hasn't rendered yet
+    pub fn wants_frame(&self, target: SurfaceTarget) -> bool {
+        self.windows
+            .frame_of(target)
+            .map(|f| f.wants_frame)
+            .unwrap_or(false)
+    }
+
+    /// Whether `target` is still waiting on the compositor's frame callback.
+    /// Rendering while this is true produces frames that will never be shown.
+    pub fn is_frame_pending(&self, target: SurfaceTarget) -> bool {
+        self.windows
+            .frame_of(target)
+            .map(|f| f.pending)
+            .unwrap_or(false)
+    }
+
+    /// Timestamp in milliseconds of the last frame callback for `target`.
+    /// Only differences between timestamps are meaningful.
+    pub fn frame_time(&self, target: SurfaceTarget) -> Option<u32> {
+        self.windows.frame_of(target).and_then(|f| f.time)
+    }
+
+    /// The scale factor a surface is currently rendered at
+    pub fn scale_factor(&self, target: SurfaceTarget) -> f64 {
+        self.windows
+            .scale_of(target)
+            .map(|s| s.factor())
+            .unwrap_or(1.0)
     }
 
     pub fn window_size(&self, window_id: WindowId) -> Option<(u32, u32)> {
@@ -920,36 +2038,16 @@ impl App {
         self.conn.as_fd().as_raw_fd()
     }
 
-    /// Returns the suggested timeout in milliseconds for key repeat
+    /// Returns the suggested timeout in milliseconds for key repeat, for
+    /// apps driving their own loop with `poll_key_events`.
     /// Returns None if no key repeat is pending (can block indefinitely)
     pub fn key_repeat_timeout(&self) -> Option<u32> {
-        let start = match (&self.repeat_key, self.repeat_start) {
-            (Some(_), Some(s)) => s,
-            _ => return None,
-        };
-
-        let now = std::time::Instant::now();
-        let elapsed = now.duration_since(start);
-        let delay = std::time::Duration::from_millis(self.repeat_delay_ms as u64);
-
-        if elapsed < delay {
-            // Still in initial delay
-            Some((delay - elapsed).as_millis() as u32)
-        } else {
-            // Past delay, use repeat rate
-            let rate = std::time::Duration::from_millis(self.repeat_rate_ms as u64);
-            match self.last_repeat {
-                None => Some(0), // Should repeat immediately
-                Some(last) => {
-                    let since_last = now.duration_since(last);
-                    if since_last >= rate {
-                        Some(0)
-                    } else {
-                        Some((rate - since_last).as_millis() as u32)
-                    }
-                }
-            }
-        }
+        let deadline = self.key_repeat.deadline()?;
+        Some(
+            deadline
+                .saturating_duration_since(Instant::now())
+                .as_millis() as u32,
+        )
     }
 }
 
@@ -960,9 +2058,15 @@ impl CompositorHandler for App {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &wl_surface::WlSurface,
-        _new_factor: i32,
+        surface: &wl_surface::WlSurface,
+        new_factor: i32,
     ) {
+        if let Some(target) = self.windows.find_target_by_surface(surface)
+            && let Some(scale) = self.windows.scale_of_mut(target)
+            && scale.set_buffer_scale(new_factor)
+        {
+            self.windows.mark_dirty(target);
+        }
     }
 
     fn transform_changed(
@@ -978,9 +2082,14 @@ impl CompositorHandler for App {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &wl_surface::WlSurface,
-        _time: u32,
+        surface: &wl_surface::WlSurface,
+        time: u32,
     ) {
+        if let Some(target) = self.windows.find_target_by_surface(surface)
+            && let Some(frame) = self.windows.frame_of_mut(target)
+        {
+            frame.done(time);
+        }
     }
 
     fn surface_enter(
@@ -1035,6 +2144,11 @@ impl OutputHandler for App {
 impl WindowHandler for App {
     fn request_close(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, window: &XdgWindow) {
         if let Some(id) = self.windows.find_window_by_surface(window.wl_surface()) {
+            // Under `App::run` the handler decides whether the window closes
+            if self.handler_active {
+                self.close_requests.push(id);
+                return;
+            }
             self.close_window(id);
             if self.windows.windows.is_empty() {
                 self.quit();
@@ -1059,6 +2173,9 @@ impl WindowHandler for App {
                 w.height = height.get();
             }
             w.dirty = true;
+            if self.handler_active {
+                self.pending_configures.push(PendingConfigure::Window(id));
+            }
         }
     }
 }
@@ -1075,6 +2192,9 @@ impl PopupHandler for App {
             && let Some(p) = self.windows.get_popup_mut(id)
         {
             p.dirty = true;
+            if self.handler_active {
+                self.pending_configures.push(PendingConfigure::Popup(id));
+            }
         }
     }
 
@@ -1102,9 +2222,25 @@ impl SeatHandler for App {
     ) {
         use smithay_client_toolkit::seat::Capability;
 
-        // Store the seat for drag & drop
+        // Store the seat for drag & drop and clipboard
         if self.current_seat.is_none() {
             self.current_seat = Some(seat.clone());
+            self.data_device = self
+                .data_device_manager
+                .as_ref()
+                .map(|ddm| ddm.get_data_device(qh, &seat));
+            self.primary_selection_device = self
+                .primary_selection_manager
+                .as_ref()
+                .map(|psm| psm.get_selection_device(qh, &seat));
+            self.text_input = self
+                .text_input_manager
+                .as_ref()
+                .map(|tim| tim.get_text_input(&seat, qh, ()));
+            self.tablet_seat = self
+                .tablet_manager
+                .as_ref()
+                .map(|tm| tm.get_tablet_seat(&seat, qh, ()));
         }
 
         if capability == Capability::Keyboard
@@ -1113,8 +2249,42 @@ impl SeatHandler for App {
             eprintln!("[mkframe] Failed to get keyboard");
         }
 
-        if capability == Capability::Pointer && self.seat_state.get_pointer(qh, &seat).is_err() {
-            eprintln!("[mkframe] Failed to get pointer");
+        if capability == Capability::Touch && self.touch.is_none() {
+            self.touch = Some(seat.get_touch(qh, ()));
+        }
+
+        if capability == Capability::Pointer && self.pointer.is_none() {
+            // The themed pointer uses wp_cursor_shape_v1 when the compositor has it,
+            // and otherwise draws the XCursor theme into its own cursor surface
+            let cursor_surface = self.compositor_state.create_surface(qh);
+            self.pointer = match self.seat_state.get_pointer_with_theme(
+                qh,
+                &seat,
+                self.shm.wl_shm(),
+                cursor_surface,
+                ThemeSpec::default(),
+            ) {
+                Ok(themed) => {
+                    let pointer = themed.pointer().clone();
+                    self.themed_pointer = Some(themed);
+                    Some(pointer)
+                }
+                Err(_) => self.seat_state.get_pointer(qh, &seat).ok(),
+            };
+
+            self.relative_pointer = self.pointer.as_ref().and_then(|pointer| {
+                self.relative_pointer_state
+                    .get_relative_pointer(pointer, qh)
+                    .ok()
+            });
+
+            match (&self.pointer, &self.pointer_gestures_manager) {
+                (Some(pointer), Some(manager)) => {
+                    self.pointer_gestures = Some(PointerGestures::new(manager, pointer, qh));
+                }
+                (None, _) => eprintln!("[mkframe] Failed to get pointer"),
+                _ => {}
+            }
         }
     }
 
@@ -1138,11 +2308,12 @@ impl KeyboardHandler for App {
         _qh: &QueueHandle<Self>,
         _keyboard: &wl_keyboard::WlKeyboard,
         surface: &wl_surface::WlSurface,
-        _serial: u32,
+        serial: u32,
         _raw: &[u32],
         _keysyms: &[Keysym],
     ) {
-        self.keyboard_focus = self.windows.find_window_by_surface(surface);
+        self.last_serial = serial;
+        self.keyboard_focus = self.windows.find_target_by_surface(surface);
     }
 
     fn leave(
@@ -1154,6 +2325,10 @@ impl KeyboardHandler for App {
         _serial: u32,
     ) {
         self.keyboard_focus = None;
+        self.current_modifiers.alt_gr = false;
+        // Keys held while focus moves away must not keep repeating
+        self.key_repeat.cancel();
+        self.schedule_key_repeat();
     }
 
     fn press_key(
@@ -1161,26 +2336,31 @@ impl KeyboardHandler for App {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         _keyboard: &wl_keyboard::WlKeyboard,
-        _serial: u32,
+        serial: u32,
         event: SctkKeyEvent,
     ) {
+        self.last_serial = serial;
+        let Some(target) = self.keyboard_focus else {
+            return;
+        };
         let key_event = KeyEvent {
+            target,
             key: Key::from_keysym(event.keysym.raw()),
+            physical_key: Key::from_keycode(event.raw_code),
+            keycode: event.raw_code,
+            keysym: event.keysym.raw(),
             text: event.utf8.clone(),
             modifiers: self.current_modifiers,
             state: KeyState::Pressed,
+            is_repeat: false,
         };
-        self.key_events.push(key_event.clone());
-
-        // Start tracking for key repeat (only for non-modifier keys)
-        if !matches!(
-            key_event.key,
-            Key::Shift | Key::Control | Key::Alt | Key::Super
-        ) {
-            self.repeat_key = Some(key_event);
-            self.repeat_start = Some(std::time::Instant::now());
-            self.last_repeat = None;
+        self.key_repeat.press(&key_event, Instant::now());
+        self.schedule_key_repeat();
+        // xkb has no AltGr modifier of its own, so follow the level 3 shift key
+        if key_event.key == Key::AltGr {
+            self.current_modifiers.alt_gr = true;
         }
+        self.key_events.push(key_event);
     }
 
     fn release_key(
@@ -1191,23 +2371,43 @@ impl KeyboardHandler for App {
         _serial: u32,
         event: SctkKeyEvent,
     ) {
-        let key_event = KeyEvent {
-            key: Key::from_keysym(event.keysym.raw()),
+        let key = Key::from_keysym(event.keysym.raw());
+
+        // Stop repeat if releasing the repeated key
+        self.key_repeat.release(event.raw_code);
+        self.schedule_key_repeat();
+        if key == Key::AltGr {
+            self.current_modifiers.alt_gr = false;
+        }
+
+        let Some(target) = self.keyboard_focus else {
+            return;
+        };
+        self.key_events.push(KeyEvent {
+            target,
+            key,
+            physical_key: Key::from_keycode(event.raw_code),
+            keycode: event.raw_code,
+            keysym: event.keysym.raw(),
             text: event.utf8.clone(),
             modifiers: self.current_modifiers,
             state: KeyState::Released,
-        };
+            is_repeat: false,
+        });
+    }
 
-        // Stop repeat if releasing the repeated key
-        if let Some(ref repeat_key) = self.repeat_key
-            && repeat_key.key == key_event.key
-        {
-            self.repeat_key = None;
-            self.repeat_start = None;
-            self.last_repeat = None;
+    fn update_repeat_info(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        info: RepeatInfo,
+    ) {
+        match info {
+            RepeatInfo::Repeat { rate, delay } => self.key_repeat.set_info(rate.get(), delay),
+            RepeatInfo::Disable => self.key_repeat.set_info(0, 0),
         }
-
-        self.key_events.push(key_event);
+        self.schedule_key_repeat();
     }
 
     fn update_modifiers(
@@ -1224,6 +2424,9 @@ impl KeyboardHandler for App {
             ctrl: modifiers.ctrl,
             alt: modifiers.alt,
             super_: modifiers.logo,
+            alt_gr: self.current_modifiers.alt_gr,
+            caps_lock: modifiers.caps_lock,
+            num_lock: modifiers.num_lock,
         };
     }
 }
@@ -1236,139 +2439,246 @@ impl PointerHandler for App {
         _pointer: &wl_pointer::WlPointer,
         events: &[SctkPointerEvent],
     ) {
-        use crate::input::{PointerButton, PointerEvent, PointerEventKind};
+        use crate::input::{PointerButton, PointerEventKind, ScrollEvent};
         use smithay_client_toolkit::seat::pointer::PointerEventKind as SctkPointerEventKind;
 
         for event in events {
+            // Positions are already local to the surface the event was delivered to
             let (x, y) = event.position;
+            let Some(target) = self.windows.find_target_by_surface(&event.surface) else {
+                continue;
+            };
 
-            match &event.kind {
+            let kind = match &event.kind {
                 SctkPointerEventKind::Enter { .. } => {
-                    // Try to find which window this surface belongs to
-                    self.pointer_focus = self.windows.find_window_by_surface(&event.surface);
+                    self.pointer_focus = Some(target);
                     self.pointer_x = x;
                     self.pointer_y = y;
-                    self.pointer_events.push(PointerEvent {
-                        kind: PointerEventKind::Enter,
-                        x,
-                        y,
-                    });
+                    // The cursor must be set again on every enter
+                    self.apply_cursor(target);
+                    PointerEventKind::Enter
                 }
                 SctkPointerEventKind::Leave { .. } => {
-                    self.pointer_focus = None;
-                    self.pointer_events.push(PointerEvent {
-                        kind: PointerEventKind::Leave,
-                        x: self.pointer_x,
-                        y: self.pointer_y,
-                    });
+                    if self.pointer_focus == Some(target) {
+                        self.pointer_focus = None;
+                    }
+                    PointerEventKind::Leave
                 }
                 SctkPointerEventKind::Motion { .. } => {
                     self.pointer_x = x;
                     self.pointer_y = y;
-                    self.pointer_events.push(PointerEvent {
-                        kind: PointerEventKind::Motion,
-                        x,
-                        y,
-                    });
+                    PointerEventKind::Motion
                 }
                 SctkPointerEventKind::Press { button, serial, .. } => {
                     self.last_serial = *serial;
-                    let btn = match button {
-                        272 => PointerButton::Left,   // BTN_LEFT
-                        273 => PointerButton::Right,  // BTN_RIGHT
-                        274 => PointerButton::Middle, // BTN_MIDDLE
-                        other => PointerButton::Other(*other),
-                    };
-                    self.pointer_events.push(PointerEvent {
-                        kind: PointerEventKind::Press(btn),
-                        x: self.pointer_x,
-                        y: self.pointer_y,
-                    });
+                    PointerEventKind::Press(PointerButton::from_code(*button))
                 }
                 SctkPointerEventKind::Release { button, .. } => {
-                    let btn = match button {
-                        272 => PointerButton::Left,
-                        273 => PointerButton::Right,
-                        274 => PointerButton::Middle,
-                        other => PointerButton::Other(*other),
-                    };
-                    self.pointer_events.push(PointerEvent {
-                        kind: PointerEventKind::Release(btn),
-                        x: self.pointer_x,
-                        y: self.pointer_y,
-                    });
+                    PointerEventKind::Release(PointerButton::from_code(*button))
                 }
                 SctkPointerEventKind::Axis {
                     horizontal,
                     vertical,
+                    source,
                     ..
-                } => {
-                    // Convert discrete scroll amounts to deltas
-                    let dx = horizontal.discrete;
-                    let dy = vertical.discrete;
-                    if dx != 0 || dy != 0 {
-                        self.pointer_events.push(PointerEvent {
-                            kind: PointerEventKind::Scroll { dx, dy },
-                            x: self.pointer_x,
-                            y: self.pointer_y,
-                        });
-                    }
-                }
-            }
+                } => match ScrollEvent::from_axis(horizontal, vertical, *source) {
+                    Some(scroll) => PointerEventKind::Scroll(scroll),
+                    None => continue,
+                },
+            };
+
+            self.pointer_events.push(PointerEvent {
+                target,
+                kind,
+                x,
+                y,
+                modifiers: self.current_modifiers,
+            });
         }
     }
 }
 
-// Data device handlers for drag & drop support
-impl DataDeviceHandler for App {
-    fn enter(
+impl RelativePointerHandler for App {
+    fn relative_pointer_motion(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _data_device: &smithay_client_toolkit::reexports::client::protocol::wl_data_device::WlDataDevice,
-        _x: f64,
-        _y: f64,
-        _wl_surface: &wl_surface::WlSurface,
+        _relative_pointer: &ZwpRelativePointerV1,
+        _pointer: &wl_pointer::WlPointer,
+        event: RelativeMotionEvent,
     ) {
-        // A drag has entered our surface
+        use crate::input::PointerEventKind;
+
+        let Some(target) = self.pointer_focus else {
+            return;
+        };
+        let ((dx, dy), (dx_unaccel, dy_unaccel)) = (event.delta, event.delta_unaccel);
+        self.pointer_events.push(PointerEvent {
+            target,
+            kind: PointerEventKind::RelativeMotion {
+                dx,
+                dy,
+                dx_unaccel,
+                dy_unaccel,
+            },
+            x: self.pointer_x,
+            y: self.pointer_y,
+            modifiers: self.current_modifiers,
+        });
     }
+}
 
-    fn leave(
+impl PointerConstraintsHandler for App {
+    fn confined(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _confined_pointer: &ZwpConfinedPointerV1,
+        _surface: &wl_surface::WlSurface,
+        _pointer: &wl_pointer::WlPointer,
+    ) {
+        self.pointer_constrained = true;
+    }
+
+    fn unconfined(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _confined_pointer: &ZwpConfinedPointerV1,
+        _surface: &wl_surface::WlSurface,
+        _pointer: &wl_pointer::WlPointer,
+    ) {
+        // Oneshot constraints are dead once deactivated
+        self.release_pointer();
+    }
+
+    fn locked(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _locked_pointer: &ZwpLockedPointerV1,
+        _surface: &wl_surface::WlSurface,
+        _pointer: &wl_pointer::WlPointer,
+    ) {
+        self.pointer_constrained = true;
+    }
+
+    fn unlocked(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _data_device: &smithay_client_toolkit::reexports::client::protocol::wl_data_device::WlDataDevice,
+        _locked_pointer: &ZwpLockedPointerV1,
+        _surface: &wl_surface::WlSurface,
+        _pointer: &wl_pointer::WlPointer,
     ) {
-        // Drag left our surface
+        // Oneshot constraints are dead once deactivated
+        self.release_pointer();
+    }
+}
+
+// Data device handlers for drag & drop support
+impl DataDeviceHandler for App {
+    fn enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+        x: f64,
+        y: f64,
+        wl_surface: &wl_surface::WlSurface,
+    ) {
+        let Some(target) = self.windows.find_target_by_surface(wl_surface) else {
+            return;
+        };
+        self.drag_target = Some(target);
+        self.drag_accepted = None;
+
+        let mime_types = self.drag_mime_types();
+        let source_actions = self
+            .current_drag_offer()
+            .map(|offer| DragAction::list_from_wl(offer.source_actions))
+            .unwrap_or_default();
+
+        // File drops work out of the box; handlers can override this on Enter
+        if mime_types.iter().any(|m| m == MIME_URI_LIST) {
+            self.accept_drag(Some(MIME_URI_LIST));
+            self.set_drag_actions(&[DragAction::Copy], DragAction::Copy);
+        }
+
+        self.drag_events.push(DragEvent::Enter {
+            target,
+            x,
+            y,
+            mime_types,
+            source_actions,
+        });
+    }
+
+    fn leave(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _data_device: &WlDataDevice) {
+        if let Some(target) = self.drag_target.take() {
+            self.drag_events.push(DragEvent::Leave { target });
+        }
     }
 
     fn motion(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _data_device: &smithay_client_toolkit::reexports::client::protocol::wl_data_device::WlDataDevice,
-        _x: f64,
-        _y: f64,
+        _data_device: &WlDataDevice,
+        x: f64,
+        y: f64,
     ) {
-        // Drag is moving over our surface
+        if let Some(target) = self.drag_target {
+            self.drag_events.push(DragEvent::Motion { target, x, y });
+        }
     }
 
     fn selection(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _data_device: &smithay_client_toolkit::reexports::client::protocol::wl_data_device::WlDataDevice,
+        _data_device: &WlDataDevice,
     ) {
         // Selection (clipboard) changed
+        let mime_types = self.clipboard_mime_types();
+        self.clipboard_events
+            .push(ClipboardEvent::SelectionChanged {
+                selection: Selection::Clipboard,
+                mime_types,
+            });
     }
 
     fn drop_performed(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _data_device: &smithay_client_toolkit::reexports::client::protocol::wl_data_device::WlDataDevice,
+        _data_device: &WlDataDevice,
     ) {
-        // Drop was performed
+        let Some(offer) = self.current_drag_offer() else {
+            return;
+        };
+        let (Some(target), Some(mime)) = (self.drag_target, self.drag_accepted.clone()) else {
+            offer.destroy();
+            return;
+        };
+
+        // A previous drop that never finished reading is abandoned
+        if let Some(old) = self.dropped_offer.take() {
+            old.destroy();
+        }
+
+        match DragAction::from_wl(offer.selected_action) {
+            DragAction::Ask => {
+                self.drag_events.push(DragEvent::Ask {
+                    target,
+                    x: offer.x,
+                    y: offer.y,
+                    source_actions: DragAction::list_from_wl(offer.source_actions),
+                });
+                self.dropped_offer = Some(offer);
+            }
+            action => self.read_drop(offer, target, mime, action),
+        }
     }
 }
 
@@ -1377,8 +2687,8 @@ impl DataOfferHandler for App {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _offer: &mut smithay_client_toolkit::data_device_manager::data_offer::DragOffer,
-        _actions: smithay_client_toolkit::reexports::client::protocol::wl_data_device_manager::DndAction,
+        _offer: &mut DragOffer,
+        _actions: DndAction,
     ) {
     }
 
@@ -1386,8 +2696,8 @@ impl DataOfferHandler for App {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _offer: &mut smithay_client_toolkit::data_device_manager::data_offer::DragOffer,
-        _action: smithay_client_toolkit::reexports::client::protocol::wl_data_device_manager::DndAction,
+        _offer: &mut DragOffer,
+        _action: DndAction,
     ) {
     }
 }
@@ -1397,7 +2707,7 @@ impl DataSourceHandler for App {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _source: &smithay_client_toolkit::reexports::client::protocol::wl_data_source::WlDataSource,
+        _source: &WlDataSource,
         _mime: Option<String>,
     ) {
         // Destination accepted a MIME type
@@ -1407,55 +2717,129 @@ impl DataSourceHandler for App {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _source: &smithay_client_toolkit::reexports::client::protocol::wl_data_source::WlDataSource,
+        source: &WlDataSource,
         mime: String,
-        mut fd: WritePipe,
+        fd: WritePipe,
     ) {
-        // Receiver requested data - write to fd
-        if mime == "text/uri-list"
-            && let Some(ref data) = self.pending_drag_data
+        let content = if self
+            .clipboard_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
+        {
+            self.clipboard_content.as_ref()
+        } else if self
+            .drag_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
         {
-            use std::io::Write;
-            let _ = fd.write_all(data);
+            self.drag_content.as_ref()
+        } else {
+            None
+        };
+        if let Some(content) = content {
+            content.send(&mime, fd);
         }
-        // fd is automatically closed when dropped
     }
 
-    fn cancelled(
+    fn cancelled(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, source: &WlDataSource) {
+        // Another client took the clipboard
+        if self
+            .clipboard_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
+        {
+            self.clipboard_source = None;
+            self.clipboard_content = None;
+            return;
+        }
+
+        if self
+            .drag_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
+        {
+            self.end_outgoing_drag();
+            self.drag_events.push(DragEvent::SourceCancelled);
+        }
+    }
+
+    fn dnd_dropped(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _source: &WlDataSource) {
+    }
+
+    fn dnd_finished(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, source: &WlDataSource) {
+        if self
+            .drag_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
+        {
+            let action = self.drag_source_action;
+            self.end_outgoing_drag();
+            self.drag_events.push(DragEvent::SourceFinished { action });
+        }
+    }
+
+    fn action(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _source: &smithay_client_toolkit::reexports::client::protocol::wl_data_source::WlDataSource,
+        source: &WlDataSource,
+        action: DndAction,
     ) {
-        self.pending_drag_source = None;
-        self.pending_drag_data = None;
+        if self
+            .drag_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
+        {
+            self.drag_source_action = DragAction::from_wl(action);
+        }
     }
+}
 
-    fn dnd_dropped(
+impl PrimarySelectionDeviceHandler for App {
+    fn selection(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _source: &smithay_client_toolkit::reexports::client::protocol::wl_data_source::WlDataSource,
+        _primary_selection_device: &ZwpPrimarySelectionDeviceV1,
     ) {
+        let mime_types = self.primary_selection_mime_types();
+        self.clipboard_events
+            .push(ClipboardEvent::SelectionChanged {
+                selection: Selection::Primary,
+                mime_types,
+            });
     }
+}
 
-    fn dnd_finished(
+impl PrimarySelectionSourceHandler for App {
+    fn send_request(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _source: &smithay_client_toolkit::reexports::client::protocol::wl_data_source::WlDataSource,
+        _source: &ZwpPrimarySelectionSourceV1,
+        mime: String,
+        write_pipe: WritePipe,
     ) {
-        self.pending_drag_source = None;
-        self.pending_drag_data = None;
+        if let Some(ref content) = self.primary_selection_content {
+            content.send(&mime, write_pipe);
+        }
     }
 
-    fn action(
+    fn cancelled(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _source: &smithay_client_toolkit::reexports::client::protocol::wl_data_source::WlDataSource,
-        _action: smithay_client_toolkit::reexports::client::protocol::wl_data_device_manager::DndAction,
+        source: &ZwpPrimarySelectionSourceV1,
     ) {
+        // Another client took the primary selection
+        if self
+            .primary_selection_source
+            .as_ref()
+            .is_some_and(|s| s.inner() == source)
+        {
+            self.primary_selection_source = None;
+            self.primary_selection_content = None;
+        }
     }
 }
 
@@ -1490,6 +2874,9 @@ impl LayerShellHandler for App {
                 overlay.height = configure.new_size.1;
             }
             overlay.dirty = true;
+            if self.handler_active {
+                self.pending_configures.push(PendingConfigure::Overlay(id));
+            }
         }
     }
 }
@@ -1507,7 +2894,10 @@ smithay_client_toolkit::delegate_shm!(App);
 smithay_client_toolkit::delegate_seat!(App);
 smithay_client_toolkit::delegate_keyboard!(App);
 smithay_client_toolkit::delegate_pointer!(App);
+smithay_client_toolkit::delegate_pointer_constraints!(App);
+smithay_client_toolkit::delegate_relative_pointer!(App);
 smithay_client_toolkit::delegate_data_device!(App);
+smithay_client_toolkit::delegate_primary_selection!(App);
 smithay_client_toolkit::delegate_xdg_shell!(App);
 smithay_client_toolkit::delegate_xdg_window!(App);
 smithay_client_toolkit::delegate_xdg_popup!(App);
@@ -1542,6 +2932,627 @@ impl Dispatch<wl_subsurface::WlSubsurface, ()> for App {
     }
 }
 
+// Fractional scale manager has no events - it's a factory interface
+impl Dispatch<WpFractionalScaleManagerV1, ()> for App {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpFractionalScaleManagerV1,
+        _event: wp_fractional_scale_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+// Preferred fractional scale for a surface, in 120ths
+impl Dispatch<WpFractionalScaleV1, wl_surface::WlSurface> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        surface: &wl_surface::WlSurface,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event
+            && let Some(target) = state.windows.find_target_by_surface(surface)
+            && let Some(surface_scale) = state.windows.scale_of_mut(target)
+            && surface_scale.set_fractional(scale)
+        {
+            state.windows.mark_dirty(target);
+        }
+    }
+}
+
+/// The lock or confinement requested for the pointer
+enum PointerConstraint {
+    Locked(ZwpLockedPointerV1),
+    Confined(ZwpConfinedPointerV1),
+}
+
+impl PointerConstraint {
+    fn destroy(&self) {
+        match self {
+            PointerConstraint::Locked(locked) => locked.destroy(),
+            PointerConstraint::Confined(confined) => confined.destroy(),
+        }
+    }
+}
+
+/// Gesture objects created for our pointer
+struct PointerGestures {
+    swipe: ZwpPointerGestureSwipeV1,
+    pinch: ZwpPointerGesturePinchV1,
+    // Hold gestures need version 3 of the manager
+    hold: Option<ZwpPointerGestureHoldV1>,
+}
+
+impl PointerGestures {
+    fn new(
+        manager: &ZwpPointerGesturesV1,
+        pointer: &wl_pointer::WlPointer,
+        qh: &QueueHandle<App>,
+    ) -> Self {
+        Self {
+            swipe: manager.get_swipe_gesture(pointer, qh, ()),
+            pinch: manager.get_pinch_gesture(pointer, qh, ()),
+            hold: (manager.version() >= 3).then(|| manager.get_hold_gesture(pointer, qh, ())),
+        }
+    }
+}
+
+impl Drop for PointerGestures {
+    fn drop(&mut self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+        if let Some(ref hold) = self.hold {
+            hold.destroy();
+        }
+    }
+}
+
+// The pointer gestures manager has no events
+impl Dispatch<ZwpPointerGesturesV1, ()> for App {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpPointerGesturesV1,
+        _event: zwp_pointer_gestures_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, ()> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_pointer_gesture_swipe_v1::Event;
+        match event {
+            Event::Begin {
+                surface, fingers, ..
+            } => {
+                state.gesture_target = state.windows.find_target_by_surface(&surface);
+                state.push_gesture(GestureEventKind::SwipeBegin { fingers });
+            }
+            Event::Update { dx, dy, .. } => {
+                state.push_gesture(GestureEventKind::SwipeUpdate { dx, dy });
+            }
+            Event::End { cancelled, .. } => {
+                state.push_gesture(GestureEventKind::SwipeEnd {
+                    cancelled: cancelled != 0,
+                });
+                state.gesture_target = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerGesturePinchV1, ()> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_pointer_gesture_pinch_v1::Event;
+        match event {
+            Event::Begin {
+                surface, fingers, ..
+            } => {
+                state.gesture_target = state.windows.find_target_by_surface(&surface);
+                state.push_gesture(GestureEventKind::PinchBegin { fingers });
+            }
+            Event::Update {
+                dx,
+                dy,
+                scale,
+                rotation,
+                ..
+            } => {
+                state.push_gesture(GestureEventKind::PinchUpdate {
+                    dx,
+                    dy,
+                    scale,
+                    rotation,
+                });
+            }
+            Event::End { cancelled, .. } => {
+                state.push_gesture(GestureEventKind::PinchEnd {
+                    cancelled: cancelled != 0,
+                });
+                state.gesture_target = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerGestureHoldV1, ()> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureHoldV1,
+        event: zwp_pointer_gesture_hold_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_pointer_gesture_hold_v1::Event;
+        match event {
+            Event::Begin {
+                surface, fingers, ..
+            } => {
+                state.gesture_target = state.windows.find_target_by_surface(&surface);
+                state.push_gesture(GestureEventKind::HoldBegin { fingers });
+            }
+            Event::End { cancelled, .. } => {
+                state.push_gesture(GestureEventKind::HoldEnd {
+                    cancelled: cancelled != 0,
+                });
+                state.gesture_target = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+// The tablet manager has no events
+impl Dispatch<ZwpTabletManagerV2, ()> for App {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpTabletManagerV2,
+        _event: zwp_tablet_manager_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletSeatV2, ()> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Tablets and pads keep no state of their own until they send events
+        if let zwp_tablet_seat_v2::Event::ToolAdded { id } = event {
+            state
+                .tablet_tools
+                .insert(id.id(), TabletToolState::default());
+        }
+    }
+
+    event_created_child!(App, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, PadData::default()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletV2, ()> for App {
+    fn event(
+        _state: &mut Self,
+        proxy: &ZwpTabletV2,
+        event: zwp_tablet_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_v2::Event::Removed = event {
+            proxy.destroy();
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletToolV2, ()> for App {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_tablet_tool_v2::{ButtonState, Event};
+
+        if let Event::Removed = event {
+            state.tablet_tools.remove(&proxy.id());
+            proxy.destroy();
+            return;
+        }
+        let target = match &event {
+            Event::ProximityIn { surface, .. } => state.windows.find_target_by_surface(surface),
+            _ => None,
+        };
+        let tool = state.tablet_tools.entry(proxy.id()).or_default();
+
+        match event {
+            Event::Type {
+                tool_type: WEnum::Value(tool_type),
+            } => tool.tool.tool_type = TabletToolType::from_wl(tool_type),
+            Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                tool.tool.serial = (hardware_serial_hi as u64) << 32 | hardware_serial_lo as u64;
+            }
+            Event::Capability {
+                capability: WEnum::Value(capability),
+            } => tool.tool.capabilities.add(capability),
+            Event::ProximityIn { .. } => tool.proximity_in(target),
+            Event::ProximityOut => tool.proximity_out(),
+            Event::Down { .. } => tool.down(),
+            Event::Up => tool.up(),
+            Event::Motion { x, y } => tool.motion(x, y),
+            Event::Pressure { pressure } => tool.pressure(pressure),
+            Event::Distance { distance } => tool.distance(distance),
+            Event::Tilt { tilt_x, tilt_y } => tool.tilt(tilt_x, tilt_y),
+            Event::Rotation { degrees } => tool.rotation(degrees),
+            Event::Slider { position } => tool.slider(position),
+            Event::Wheel { degrees, clicks } => tool.wheel(degrees, clicks),
+            Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => tool.button(button, button_state == WEnum::Value(ButtonState::Pressed)),
+            Event::Frame { .. } => {
+                let events = tool.frame();
+                state.tablet_events.extend(events);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Counters for numbering a pad's groups, rings, strips and dials
+#[derive(Default)]
+struct PadData {
+    groups: AtomicU32,
+    rings: AtomicU32,
+    strips: AtomicU32,
+    dials: AtomicU32,
+}
+
+/// The pad a group, ring, strip or dial belongs to, and its index on that pad.
+/// Set by the parent's event that announces the object.
+type PadControlData = OnceLock<(ZwpTabletPadV2, u32)>;
+
+/// Record `pad` and the next index from `counter` on a new pad control
+fn register_pad_control<I: Proxy>(control: &I, pad: &ZwpTabletPadV2, counter: &AtomicU32) {
+    if let Some(data) = control.data::<PadControlData>() {
+        let _ = data.set((pad.clone(), counter.fetch_add(1, Ordering::Relaxed)));
+    }
+}
+
+impl Dispatch<ZwpTabletPadV2, PadData> for App {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        data: &PadData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_tablet_pad_v2::{ButtonState, Event};
+        match event {
+            Event::Group { pad_group } => register_pad_control(&pad_group, proxy, &data.groups),
+            Event::Enter { surface, .. } => {
+                if let Some(target) = state.windows.find_target_by_surface(&surface) {
+                    state.tablet_pad_focus.insert(proxy.id(), target);
+                }
+            }
+            Event::Leave { .. } => {
+                state.tablet_pad_focus.remove(&proxy.id());
+            }
+            Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                let pressed = button_state == WEnum::Value(ButtonState::Pressed);
+                state.push_pad_event(proxy, TabletEventKind::PadButton { button, pressed });
+            }
+            Event::Removed => {
+                state.tablet_pad_focus.remove(&proxy.id());
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(App, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, PadControlData::new()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, PadControlData> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadGroupV2,
+        event: zwp_tablet_pad_group_v2::Event,
+        data: &PadControlData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_tablet_pad_group_v2::Event;
+        let Some((pad, group)) = data.get() else {
+            return;
+        };
+        let Some(pad_data) = pad.data::<PadData>() else {
+            return;
+        };
+        match event {
+            Event::Ring { ring } => register_pad_control(&ring, pad, &pad_data.rings),
+            Event::Strip { strip } => register_pad_control(&strip, pad, &pad_data.strips),
+            Event::Dial { dial } => register_pad_control(&dial, pad, &pad_data.dials),
+            Event::ModeSwitch { mode, .. } => {
+                let group = *group;
+                state.push_pad_event(pad, TabletEventKind::PadMode { group, mode });
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(App, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, PadControlData::new()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, PadControlData::new()),
+        zwp_tablet_pad_group_v2::EVT_DIAL_OPCODE => (ZwpTabletPadDialV2, PadControlData::new()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadRingV2, PadControlData> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadRingV2,
+        event: zwp_tablet_pad_ring_v2::Event,
+        data: &PadControlData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_tablet_pad_ring_v2::Event;
+        let Some((pad, ring)) = data.get() else {
+            return;
+        };
+        let degrees = match event {
+            Event::Angle { degrees } => Some(degrees),
+            Event::Stop => None,
+            _ => return,
+        };
+        state.push_pad_event(
+            pad,
+            TabletEventKind::PadRing {
+                ring: *ring,
+                degrees,
+            },
+        );
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, PadControlData> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadStripV2,
+        event: zwp_tablet_pad_strip_v2::Event,
+        data: &PadControlData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_tablet_pad_strip_v2::Event;
+        let Some((pad, strip)) = data.get() else {
+            return;
+        };
+        let position = match event {
+            Event::Position { position } => Some(position as f64 / 65535.0),
+            Event::Stop => None,
+            _ => return,
+        };
+        state.push_pad_event(
+            pad,
+            TabletEventKind::PadStrip {
+                strip: *strip,
+                position,
+            },
+        );
+    }
+}
+
+impl Dispatch<ZwpTabletPadDialV2, PadControlData> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadDialV2,
+        event: zwp_tablet_pad_dial_v2::Event,
+        data: &PadControlData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_pad_dial_v2::Event::Delta { value120 } = event
+            && let Some((pad, dial)) = data.get()
+        {
+            state.push_pad_event(
+                pad,
+                TabletEventKind::PadDial {
+                    dial: *dial,
+                    value120,
+                },
+            );
+        }
+    }
+}
+
+// Touch is dispatched directly rather than through sctk so frames are visible
+impl Dispatch<wl_touch::WlTouch, ()> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_touch::Event::Down {
+                serial,
+                surface,
+                id,
+                x,
+                y,
+                ..
+            } => {
+                state.last_serial = serial;
+                if let Some(target) = state.windows.find_target_by_surface(&surface) {
+                    let event = state.touch_state.down(target, id, x, y);
+                    state.touch_events.push(event);
+                }
+            }
+            wl_touch::Event::Up { serial, id, .. } => {
+                state.last_serial = serial;
+                state.touch_events.extend(state.touch_state.up(id));
+                // Some compositors don't send a frame after the last point lifts
+                if state.touch_state.is_empty() {
+                    let frames = state.touch_state.frame();
+                    state.touch_events.extend(frames);
+                }
+            }
+            wl_touch::Event::Motion { id, x, y, .. } => {
+                state
+                    .touch_events
+                    .extend(state.touch_state.motion(id, x, y));
+            }
+            wl_touch::Event::Frame => {
+                let frames = state.touch_state.frame();
+                state.touch_events.extend(frames);
+            }
+            wl_touch::Event::Cancel => {
+                let cancels = state.touch_state.cancel();
+                state.touch_events.extend(cancels);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The text input manager has no events
+impl Dispatch<ZwpTextInputManagerV3, ()> for App {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpTextInputManagerV3,
+        _event: zwp_text_input_manager_v3::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTextInputV3, ()> for App {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTextInputV3,
+        event: zwp_text_input_v3::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwp_text_input_v3::Event;
+        match event {
+            Event::Enter { surface } => {
+                state.ime.focus = state.windows.find_target_by_surface(&surface);
+                state.sync_ime();
+            }
+            Event::Leave { .. } => {
+                // The text input is implicitly disabled on leave
+                if state.ime.active {
+                    state.ime.active = false;
+                    if let Some(ref text_input) = state.text_input {
+                        text_input.disable();
+                    }
+                    state.commit_ime();
+                }
+                if let Some(target) = state.ime.focus.take()
+                    && let Some(event) = state.ime.clear_preedit(target)
+                {
+                    state.ime_events.push(event);
+                }
+            }
+            Event::PreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            } => state.ime.set_preedit(text, cursor_begin, cursor_end),
+            Event::CommitString { text } => state.ime.set_commit(text),
+            Event::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => state.ime.set_delete(before_length, after_length),
+            Event::Done { .. } => {
+                // Batches are applied even when their serial is stale
+                let events = state.ime.done();
+                state.ime_events.extend(events);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Viewporter and viewports have no events
+impl Dispatch<WpViewporter, ()> for App {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpViewporter,
+        _event: wp_viewporter::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WpViewport, ()> for App {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpViewport,
+        _event: wp_viewport::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
 // Attached surface handler implementation
 impl AttachedSurfaceHandler for App {
     fn configure(
@@ -1562,6 +3573,10 @@ impl AttachedSurfaceHandler for App {
             }
             attached.ack_configure(serial);
             attached.dirty = true;
+            if self.handler_active {
+                self.pending_configures
+                    .push(PendingConfigure::AttachedSurface(surface_id));
+            }
         }
     }
 
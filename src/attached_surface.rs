@@ -1,5 +1,8 @@
 use wayland_client::{Connection, QueueHandle, protocol::wl_surface::WlSurface};
 
+use crate::scale::SurfaceScale;
+use crate::window::FrameState;
+
 // Generate the protocol code using wayland-scanner macros
 // Path is relative to crate root
 pub mod protocol {
@@ -60,6 +63,8 @@ pub struct AttachedSurface {
     pub width: u32,
     pub height: u32,
     pub dirty: bool,
+    pub scale: SurfaceScale,
+    pub frame: FrameState,
     pub configured: bool,
     pub pending_configure: Option<(u32, u32, u32)>, // serial, width, height
 }
@@ -0,0 +1,213 @@
+//! Clipboard (wl_data_device selection) and primary selection
+//! (zwp_primary_selection_v1) types and pipe helpers.
+
+use std::io::{Read, Write};
+use std::sync::{Arc, mpsc::Sender};
+
+use smithay_client_toolkit::data_device_manager::{ReadPipe, WritePipe};
+use smithay_client_toolkit::reexports::calloop::ping::Ping;
+
+pub const MIME_TEXT_UTF8: &str = "text/plain;charset=utf-8";
+pub const MIME_UTF8_STRING: &str = "UTF8_STRING";
+pub const MIME_TEXT_PLAIN: &str = "text/plain";
+pub const MIME_IMAGE_PNG: &str = "image/png";
+
+/// Text MIME types in order of preference, covering both Wayland and X11 clients
+pub const TEXT_MIME_TYPES: &[&str] = &[
+    MIME_TEXT_UTF8,
+    MIME_UTF8_STRING,
+    MIME_TEXT_PLAIN,
+    "TEXT",
+    "STRING",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClipboardReadId(pub u64);
+
+/// Which selection an event or read refers to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// The explicit copy/paste clipboard
+    #[default]
+    Clipboard,
+    /// The select-to-copy, middle-click-to-paste selection
+    Primary,
+}
+
+#[derive(Debug)]
+pub enum ClipboardEvent {
+    /// Another client (or we) took the selection; these MIME types are on offer
+    SelectionChanged {
+        selection: Selection,
+        mime_types: Vec<String>,
+    },
+    /// Data requested with `App::read_clipboard` or `App::read_primary_selection`
+    /// finished reading
+    Data(ClipboardData),
+}
+
+#[derive(Debug)]
+pub struct ClipboardData {
+    pub id: ClipboardReadId,
+    pub selection: Selection,
+    pub mime_type: String,
+    pub data: std::io::Result<Vec<u8>>,
+}
+
+impl ClipboardData {
+    /// Decode the payload as text if it was read with a text MIME type
+    pub fn text(&self) -> Option<String> {
+        if !is_text_mime(&self.mime_type) {
+            return None;
+        }
+        let data = self.data.as_ref().ok()?;
+        Some(String::from_utf8_lossy(data).into_owned())
+    }
+}
+
+pub fn is_text_mime(mime: &str) -> bool {
+    TEXT_MIME_TYPES.contains(&mime) || mime.starts_with("text/")
+}
+
+/// Pick the first of `preferred` that is present in `offered`
+pub fn best_mime_type(offered: &[String], preferred: &[&str]) -> Option<String> {
+    preferred
+        .iter()
+        .find(|p| offered.iter().any(|o| o == *p))
+        .map(|p| p.to_string())
+}
+
+/// Data we are offering as a selection or drag source, per MIME type
+pub(crate) struct SelectionContent {
+    entries: Vec<(String, Arc<[u8]>)>,
+}
+
+impl SelectionContent {
+    /// Offer the same data under each of `mime_types`
+    pub fn new(mime_types: &[&str], data: &[u8]) -> Self {
+        let data: Arc<[u8]> = data.into();
+        Self {
+            entries: mime_types
+                .iter()
+                .map(|m| (m.to_string(), data.clone()))
+                .collect(),
+        }
+    }
+
+    /// Offer different data for each MIME type
+    pub fn from_payloads(payloads: &[(&str, &[u8])]) -> Self {
+        Self {
+            entries: payloads
+                .iter()
+                .map(|(mime, data)| (mime.to_string(), (*data).into()))
+                .collect(),
+        }
+    }
+
+    pub fn mime_types(&self) -> Vec<String> {
+        self.entries.iter().map(|(m, _)| m.clone()).collect()
+    }
+
+    pub fn offers(&self, mime: &str) -> bool {
+        self.data(mime).is_some()
+    }
+
+    pub fn data(&self, mime: &str) -> Option<&Arc<[u8]>> {
+        self.entries.iter().find(|(m, _)| m == mime).map(|(_, d)| d)
+    }
+
+    /// Write the data for `mime` to a requesting client.
+    /// Done on a thread so large payloads can't stall the event loop, and so
+    /// pasting our own selection can't deadlock.
+    pub fn send(&self, mime: &str, mut pipe: WritePipe) {
+        let Some(data) = self.data(mime).cloned() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            let _ = pipe.write_all(&data);
+        });
+    }
+}
+
+/// Read a pipe to completion on a thread, then hand the result back to the
+/// event loop wrapped by `wrap`
+pub(crate) fn read_pipe<T, F>(mut pipe: ReadPipe, results: Sender<T>, wake: Option<Ping>, wrap: F)
+where
+    T: Send + 'static,
+    F: FnOnce(std::io::Result<Vec<u8>>) -> T + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let data = pipe.read_to_end(&mut buf).map(|_| buf);
+        if results.send(wrap(data)).is_ok()
+            && let Some(wake) = wake
+        {
+            wake.ping();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_mime_type_prefers_order() {
+        let offered = vec!["STRING".to_string(), MIME_TEXT_UTF8.to_string()];
+        assert_eq!(
+            best_mime_type(&offered, TEXT_MIME_TYPES),
+            Some(MIME_TEXT_UTF8.to_string())
+        );
+    }
+
+    #[test]
+    fn test_best_mime_type_none() {
+        let offered = vec![MIME_IMAGE_PNG.to_string()];
+        assert_eq!(best_mime_type(&offered, TEXT_MIME_TYPES), None);
+    }
+
+    #[test]
+    fn test_is_text_mime() {
+        assert!(is_text_mime(MIME_UTF8_STRING));
+        assert!(is_text_mime("text/html"));
+        assert!(!is_text_mime(MIME_IMAGE_PNG));
+    }
+
+    #[test]
+    fn test_clipboard_data_text() {
+        let data = ClipboardData {
+            id: ClipboardReadId(1),
+            selection: Selection::Clipboard,
+            mime_type: MIME_TEXT_UTF8.to_string(),
+            data: Ok(b"hello".to_vec()),
+        };
+        assert_eq!(data.text(), Some("hello".to_string()));
+
+        let image = ClipboardData {
+            id: ClipboardReadId(2),
+            selection: Selection::Primary,
+            mime_type: MIME_IMAGE_PNG.to_string(),
+            data: Ok(vec![0x89, b'P', b'N', b'G']),
+        };
+        assert_eq!(image.text(), None);
+    }
+
+    #[test]
+    fn test_selection_content_offers() {
+        let content = SelectionContent::new(TEXT_MIME_TYPES, b"abc");
+        assert!(content.offers(MIME_UTF8_STRING));
+        assert!(!content.offers(MIME_IMAGE_PNG));
+    }
+
+    #[test]
+    fn test_selection_content_payloads() {
+        let content =
+            SelectionContent::from_payloads(&[(MIME_TEXT_UTF8, b"abc"), (MIME_IMAGE_PNG, b"png")]);
+        assert_eq!(content.mime_types(), vec![MIME_TEXT_UTF8, MIME_IMAGE_PNG]);
+        assert_eq!(
+            content.data(MIME_IMAGE_PNG).map(|d| &d[..]),
+            Some(&b"png"[..])
+        );
+        assert!(content.data(MIME_UTF8_STRING).is_none());
+    }
+}
@@ -0,0 +1,827 @@
+//! Stock interactive widgets: buttons, checkboxes, toggle switches and radio groups.
+//!
+//! Each reports what the user did through an optional callback and, for apps
+//! that poll, through a shared `WidgetEvents` queue.
+
+use cosmic_text::Color as TextColor;
+
+use crate::input::{Key, KeyEvent, KeyState, PointerButton, PointerEvent, PointerEventKind};
+use crate::render::Rgba;
+use crate::text::{FONT_SIZE, HAlign, VAlign};
+use crate::widget::{
+    Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetEvent, WidgetEvents,
+    WidgetId,
+};
+
+pub(crate) const TEXT: TextColor = TextColor::rgb(230, 230, 230);
+pub(crate) const TEXT_DISABLED: TextColor = TextColor::rgb(120, 120, 126);
+pub(crate) const CONTROL: Rgba = Rgba::rgb(60, 60, 66);
+const CONTROL_HOVER: Rgba = Rgba::rgb(74, 74, 82);
+const CONTROL_PRESSED: Rgba = Rgba::rgb(44, 44, 50);
+pub(crate) const CONTROL_DISABLED: Rgba = Rgba::rgb(48, 48, 52);
+pub(crate) const BORDER: Rgba = Rgba::rgb(140, 140, 148);
+pub(crate) const ACCENT: Rgba = Rgba::rgb(80, 150, 255);
+const KNOB: Rgba = Rgba::rgb(240, 240, 240);
+
+const BUTTON_PADDING: (u32, u32) = (12, 6);
+/// Side of a checkbox or radio indicator
+const MARK_SIZE: u32 = 16;
+const SWITCH_SIZE: Size = Size {
+    width: 34,
+    height: 18,
+};
+/// Space between an indicator and its label
+const LABEL_GAP: u32 = 8;
+
+fn text_color(enabled: bool) -> TextColor {
+    if enabled { TEXT } else { TEXT_DISABLED }
+}
+
+fn emit(events: &Option<WidgetEvents>, event: WidgetEvent) {
+    if let Some(events) = events {
+        events.push(event);
+    }
+}
+
+/// An unmodified, non-repeated press of one of `keys`
+fn is_activation(event: &KeyEvent, keys: &[Key]) -> bool {
+    let mods = event.modifiers;
+    event.state == KeyState::Pressed
+        && !event.is_repeat
+        && !(mods.ctrl || mods.alt || mods.super_)
+        && keys.contains(&event.key)
+}
+
+/// What a pointer event did to a control
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Press {
+    Ignored,
+    Handled,
+    /// A left press and release both landed on the control
+    Clicked,
+}
+
+/// Hover and press tracking shared by the controls
+#[derive(Default)]
+struct PressState {
+    hovered: bool,
+    pressed: bool,
+}
+
+impl PressState {
+    fn handle(&mut self, event: &PointerEvent, bounds: Rect, enabled: bool) -> Press {
+        let inside = bounds.contains(event.x as i32, event.y as i32);
+        match event.kind {
+            PointerEventKind::Enter | PointerEventKind::Motion => {
+                self.hovered = inside && enabled;
+                Press::Ignored
+            }
+            PointerEventKind::Leave => {
+                *self = Self::default();
+                Press::Ignored
+            }
+            PointerEventKind::Press(PointerButton::Left) if inside && enabled => {
+                self.pressed = true;
+                Press::Handled
+            }
+            PointerEventKind::Release(PointerButton::Left) if self.pressed => {
+                self.pressed = false;
+                if inside {
+                    Press::Clicked
+                } else {
+                    Press::Handled
+                }
+            }
+            _ => Press::Ignored,
+        }
+    }
+
+    fn fill(&self, enabled: bool) -> Rgba {
+        match (enabled, self.pressed, self.hovered) {
+            (false, _, _) => CONTROL_DISABLED,
+            (true, true, _) => CONTROL_PRESSED,
+            (true, false, true) => CONTROL_HOVER,
+            (true, false, false) => CONTROL,
+        }
+    }
+}
+
+/// Size of an indicator followed by a label
+fn indicator_layout(
+    indicator: Size,
+    label: &str,
+    constraints: Constraints,
+    ctx: &mut LayoutContext,
+) -> Size {
+    let (text_width, text_height) = if label.is_empty() {
+        (0, 0)
+    } else {
+        let (width, height) = ctx.text.measure_text(label, FONT_SIZE);
+        (LABEL_GAP + width.ceil() as u32, height.ceil() as u32)
+    };
+    Size::new(
+        (indicator.width + text_width).clamp(constraints.min_width, constraints.max_width),
+        indicator
+            .height
+            .max(text_height)
+            .clamp(constraints.min_height, constraints.max_height),
+    )
+}
+
+/// Draw a label after an indicator `indicator_width` wide
+fn render_label(
+    label: &str,
+    indicator_width: u32,
+    enabled: bool,
+    bounds: Rect,
+    ctx: &mut RenderContext,
+) {
+    if label.is_empty() {
+        return;
+    }
+    let offset = indicator_width + LABEL_GAP;
+    let rect = Rect::new(
+        bounds.x + offset as i32,
+        bounds.y,
+        bounds.width.saturating_sub(offset),
+        bounds.height,
+    );
+    ctx.text.draw_text_in_rect(
+        ctx.canvas,
+        label,
+        rect,
+        FONT_SIZE,
+        text_color(enabled),
+        HAlign::Left,
+        VAlign::Center,
+    );
+}
+
+/// A push button, activated by clicking it or with Enter or Space while focused
+pub struct Button {
+    id: WidgetId,
+    label: String,
+    enabled: bool,
+    press: PressState,
+    on_activate: Option<Box<dyn FnMut()>>,
+    events: Option<WidgetEvents>,
+}
+
+impl Button {
+    pub fn new(id: WidgetId, label: impl Into<String>) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            enabled: true,
+            press: PressState::default(),
+            on_activate: None,
+            events: None,
+        }
+    }
+
+    pub fn on_activate(mut self, f: impl FnMut() + 'static) -> Self {
+        self.on_activate = Some(Box::new(f));
+        self
+    }
+
+    /// Also report activations as `WidgetEvent::Activated` on `events`
+    pub fn events(mut self, events: &WidgetEvents) -> Self {
+        self.events = Some(events.clone());
+        self
+    }
+
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.press = PressState::default();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_hovered(&self) -> bool {
+        self.press.hovered
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.press.pressed
+    }
+
+    fn activate(&mut self) {
+        if let Some(f) = &mut self.on_activate {
+            f();
+        }
+        emit(&self.events, WidgetEvent::Activated(self.id));
+    }
+}
+
+impl Widget for Button {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let (width, height) = ctx.text.measure_text(&self.label, FONT_SIZE);
+        let (pad_x, pad_y) = BUTTON_PADDING;
+        Size::new(
+            (width.ceil() as u32 + pad_x * 2).clamp(constraints.min_width, constraints.max_width),
+            (height.ceil() as u32 + pad_y * 2)
+                .clamp(constraints.min_height, constraints.max_height),
+        )
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        ctx.canvas.fill_rounded_rect(
+            bounds.x as f32,
+            bounds.y as f32,
+            bounds.width as f32,
+            bounds.height as f32,
+            4.0,
+            self.press.fill(self.enabled).to_color(),
+        );
+        ctx.text.draw_text_in_rect(
+            ctx.canvas,
+            &self.label,
+            bounds,
+            FONT_SIZE,
+            text_color(self.enabled),
+            HAlign::Center,
+            VAlign::Center,
+        );
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        if self.enabled && is_activation(event, &[Key::Enter, Key::KpEnter, Key::Space]) {
+            self.activate();
+            return true;
+        }
+        false
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        match self.press.handle(event, bounds, self.enabled) {
+            Press::Ignored => false,
+            Press::Handled => true,
+            Press::Clicked => {
+                self.activate();
+                true
+            }
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// State shared by checkboxes and toggle switches
+struct CheckState {
+    id: WidgetId,
+    label: String,
+    checked: bool,
+    enabled: bool,
+    press: PressState,
+    on_toggle: Option<Box<dyn FnMut(bool)>>,
+    events: Option<WidgetEvents>,
+}
+
+impl CheckState {
+    fn new(id: WidgetId, label: String) -> Self {
+        Self {
+            id,
+            label,
+            checked: false,
+            enabled: true,
+            press: PressState::default(),
+            on_toggle: None,
+            events: None,
+        }
+    }
+
+    fn toggle(&mut self) {
+        self.checked = !self.checked;
+        if let Some(f) = &mut self.on_toggle {
+            f(self.checked);
+        }
+        let (id, checked) = (self.id, self.checked);
+        emit(&self.events, WidgetEvent::Toggled { id, checked });
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        if self.enabled && is_activation(event, &[Key::Space]) {
+            self.toggle();
+            return true;
+        }
+        false
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        match self.press.handle(event, bounds, self.enabled) {
+            Press::Ignored => false,
+            Press::Handled => true,
+            Press::Clicked => {
+                self.toggle();
+                true
+            }
+        }
+    }
+}
+
+/// Builder and accessor methods shared by `Checkbox` and `Toggle`
+macro_rules! check_methods {
+    () => {
+        pub fn new(id: WidgetId, label: impl Into<String>) -> Self {
+            Self {
+                state: CheckState::new(id, label.into()),
+            }
+        }
+
+        pub fn checked(mut self, checked: bool) -> Self {
+            self.state.checked = checked;
+            self
+        }
+
+        /// Called with the new state whenever the user changes it
+        pub fn on_toggle(mut self, f: impl FnMut(bool) + 'static) -> Self {
+            self.state.on_toggle = Some(Box::new(f));
+            self
+        }
+
+        /// Also report changes as `WidgetEvent::Toggled` on `events`
+        pub fn events(mut self, events: &WidgetEvents) -> Self {
+            self.state.events = Some(events.clone());
+            self
+        }
+
+        /// Set the state without reporting a change
+        pub fn set_checked(&mut self, checked: bool) {
+            self.state.checked = checked;
+        }
+
+        pub fn is_checked(&self) -> bool {
+            self.state.checked
+        }
+
+        pub fn set_label(&mut self, label: impl Into<String>) {
+            self.state.label = label.into();
+        }
+
+        pub fn set_enabled(&mut self, enabled: bool) {
+            self.state.enabled = enabled;
+            if !enabled {
+                self.state.press = PressState::default();
+            }
+        }
+
+        pub fn is_enabled(&self) -> bool {
+            self.state.enabled
+        }
+    };
+}
+
+/// `Widget` methods shared by `Checkbox` and `Toggle`
+macro_rules! check_widget {
+    () => {
+        fn id(&self) -> WidgetId {
+            self.state.id
+        }
+
+        fn handle_key(&mut self, event: &KeyEvent) -> bool {
+            self.state.handle_key(event)
+        }
+
+        fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+            self.state.handle_pointer(event, bounds)
+        }
+
+        fn is_focusable(&self) -> bool {
+            self.state.enabled
+        }
+    };
+}
+
+/// A labelled checkbox, toggled by clicking it or with Space while focused
+pub struct Checkbox {
+    state: CheckState,
+}
+
+impl Checkbox {
+    check_methods!();
+}
+
+impl Widget for Checkbox {
+    check_widget!();
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let mark = Size::new(MARK_SIZE, MARK_SIZE);
+        indicator_layout(mark, &self.state.label, constraints, ctx)
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        let state = &self.state;
+        let size = MARK_SIZE as f32;
+        let x = bounds.x as f32;
+        let y = bounds.y as f32 + (bounds.height as f32 - size) / 2.0;
+
+        if state.checked {
+            let fill = if state.enabled { ACCENT } else { BORDER };
+            ctx.canvas
+                .fill_rounded_rect(x, y, size, size, 3.0, fill.to_color());
+            let color = KNOB.to_color();
+            ctx.canvas
+                .draw_line(x + 4.0, y + 8.5, x + 7.0, y + 11.5, 2.0, color);
+            ctx.canvas
+                .draw_line(x + 7.0, y + 11.5, x + 12.0, y + 5.0, 2.0, color);
+        } else {
+            ctx.canvas
+                .fill_rounded_rect(x, y, size, size, 3.0, BORDER.to_color());
+            let fill = state.press.fill(state.enabled).to_color();
+            ctx.canvas
+                .fill_rounded_rect(x + 1.5, y + 1.5, size - 3.0, size - 3.0, 2.0, fill);
+        }
+        render_label(&state.label, MARK_SIZE, state.enabled, bounds, ctx);
+    }
+}
+
+/// A labelled on/off switch, toggled by clicking it or with Space while focused
+pub struct Toggle {
+    state: CheckState,
+}
+
+impl Toggle {
+    check_methods!();
+}
+
+impl Widget for Toggle {
+    check_widget!();
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        indicator_layout(SWITCH_SIZE, &self.state.label, constraints, ctx)
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        let state = &self.state;
+        let (width, height) = (SWITCH_SIZE.width as f32, SWITCH_SIZE.height as f32);
+        let x = bounds.x as f32;
+        let y = bounds.y as f32 + (bounds.height as f32 - height) / 2.0;
+        let radius = height / 2.0;
+
+        let track = match (state.checked, state.enabled) {
+            (true, true) => ACCENT,
+            (true, false) => BORDER,
+            (false, _) => state.press.fill(state.enabled),
+        };
+        ctx.canvas
+            .fill_rounded_rect(x, y, width, height, radius, track.to_color());
+        let knob_x = if state.checked {
+            x + width - radius
+        } else {
+            x + radius
+        };
+        ctx.canvas
+            .fill_circle(knob_x, y + radius, radius - 3.0, KNOB.to_color());
+        render_label(&state.label, SWITCH_SIZE.width, state.enabled, bounds, ctx);
+    }
+}
+
+/// A column of mutually exclusive options. Clicking an option selects it;
+/// while focused, the arrow keys move the selection.
+pub struct RadioGroup {
+    id: WidgetId,
+    options: Vec<String>,
+    selected: Option<usize>,
+    enabled: bool,
+    spacing: u32,
+    row_height: u32,
+    hovered: Option<usize>,
+    pressed: Option<usize>,
+    on_select: Option<Box<dyn FnMut(usize)>>,
+    events: Option<WidgetEvents>,
+}
+
+impl RadioGroup {
+    pub fn new<S: Into<String>>(id: WidgetId, options: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            id,
+            options: options.into_iter().map(Into::into).collect(),
+            selected: None,
+            enabled: true,
+            spacing: 6,
+            row_height: MARK_SIZE,
+            hovered: None,
+            pressed: None,
+            on_select: None,
+            events: None,
+        }
+    }
+
+    pub fn selected(mut self, index: usize) -> Self {
+        self.set_selected(Some(index));
+        self
+    }
+
+    /// Space between options
+    pub fn spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Called with the option's index whenever the user selects one
+    pub fn on_select(mut self, f: impl FnMut(usize) + 'static) -> Self {
+        self.on_select = Some(Box::new(f));
+        self
+    }
+
+    /// Also report selections as `WidgetEvent::Selected` on `events`
+    pub fn events(mut self, events: &WidgetEvents) -> Self {
+        self.events = Some(events.clone());
+        self
+    }
+
+    /// Set the selection without reporting a change
+    pub fn set_selected(&mut self, index: Option<usize>) {
+        self.selected = index.filter(|&i| i < self.options.len());
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.hovered = None;
+            self.pressed = None;
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.selected == Some(index) {
+            return;
+        }
+        self.selected = Some(index);
+        if let Some(f) = &mut self.on_select {
+            f(index);
+        }
+        emit(&self.events, WidgetEvent::Selected { id: self.id, index });
+    }
+
+    fn option_bounds(&self, bounds: Rect, index: usize) -> Rect {
+        let y = bounds.y + (index as u32 * (self.row_height + self.spacing)) as i32;
+        Rect::new(bounds.x, y, bounds.width, self.row_height)
+    }
+
+    fn option_at(&self, bounds: Rect, x: f64, y: f64) -> Option<usize> {
+        (0..self.options.len())
+            .find(|&i| self.option_bounds(bounds, i).contains(x as i32, y as i32))
+    }
+}
+
+impl Widget for RadioGroup {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let mut width = MARK_SIZE;
+        self.row_height = MARK_SIZE;
+        for option in &self.options {
+            let (w, h) = ctx.text.measure_text(option, FONT_SIZE);
+            width = width.max(MARK_SIZE + LABEL_GAP + w.ceil() as u32);
+            self.row_height = self.row_height.max(h.ceil() as u32);
+        }
+        let count = self.options.len() as u32;
+        let height = count * self.row_height + count.saturating_sub(1) * self.spacing;
+        Size::new(
+            width.clamp(constraints.min_width, constraints.max_width),
+            height.clamp(constraints.min_height, constraints.max_height),
+        )
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        let radius = MARK_SIZE as f32 / 2.0;
+        for (i, option) in self.options.iter().enumerate() {
+            let row = self.option_bounds(bounds, i);
+            let cx = row.x as f32 + radius;
+            let cy = row.y as f32 + row.height as f32 / 2.0;
+            let press = PressState {
+                hovered: self.hovered == Some(i),
+                pressed: self.pressed == Some(i),
+            };
+
+            if self.selected == Some(i) {
+                let ring = if self.enabled { ACCENT } else { BORDER };
+                ctx.canvas.fill_circle(cx, cy, radius, ring.to_color());
+                ctx.canvas
+                    .fill_circle(cx, cy, radius - 4.5, KNOB.to_color());
+            } else {
+                ctx.canvas.fill_circle(cx, cy, radius, BORDER.to_color());
+                let fill = press.fill(self.enabled).to_color();
+                ctx.canvas.fill_circle(cx, cy, radius - 1.5, fill);
+            }
+            render_label(option, MARK_SIZE, self.enabled, row, ctx);
+        }
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        let count = self.options.len();
+        if !self.enabled || count == 0 || event.state != KeyState::Pressed {
+            return false;
+        }
+        // Arrows wrap around, as in native radio groups
+        let index = match (event.key, self.selected) {
+            (Key::Down | Key::Right, Some(i)) => (i + 1) % count,
+            (Key::Up | Key::Left, Some(i)) => (i + count - 1) % count,
+            (Key::Up | Key::Left, None) => count - 1,
+            (Key::Down | Key::Right, None) => 0,
+            (Key::Space, None) if is_activation(event, &[Key::Space]) => 0,
+            _ => return false,
+        };
+        self.select(index);
+        true
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let hit = self.option_at(bounds, event.x, event.y);
+        match event.kind {
+            PointerEventKind::Enter | PointerEventKind::Motion => {
+                self.hovered = hit;
+                false
+            }
+            PointerEventKind::Leave => {
+                self.hovered = None;
+                self.pressed = None;
+                false
+            }
+            PointerEventKind::Press(PointerButton::Left) if hit.is_some() => {
+                self.pressed = hit;
+                true
+            }
+            PointerEventKind::Release(PointerButton::Left) if self.pressed.is_some() => {
+                if let Some(index) = self.pressed.take().filter(|&i| hit == Some(i)) {
+                    self.select(index);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.enabled && !self.options.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Modifiers, make_key_event, make_pointer_event};
+    use crate::text::TextRenderer;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn click(widget: &mut dyn Widget, bounds: Rect, x: f64, y: f64) {
+        let left = PointerButton::Left;
+        widget.handle_pointer(
+            &make_pointer_event(PointerEventKind::Press(left), x, y),
+            bounds,
+        );
+        widget.handle_pointer(
+            &make_pointer_event(PointerEventKind::Release(left), x, y),
+            bounds,
+        );
+    }
+
+    #[test]
+    fn test_button_activation() {
+        let count = Rc::new(Cell::new(0));
+        let events = WidgetEvents::new();
+        let counter = count.clone();
+        let mut button = Button::new(WidgetId(1), "OK")
+            .on_activate(move || counter.set(counter.get() + 1))
+            .events(&events);
+        let bounds = Rect::new(0, 0, 60, 30);
+
+        click(&mut button, bounds, 10.0, 10.0);
+        assert!(button.handle_key(&make_key_event(Key::Enter, None, Modifiers::default())));
+        assert!(!button.handle_key(&make_key_event(Key::A, None, Modifiers::default())));
+        assert_eq!(count.get(), 2);
+        assert_eq!(
+            events.poll(),
+            [
+                WidgetEvent::Activated(WidgetId(1)),
+                WidgetEvent::Activated(WidgetId(1))
+            ]
+        );
+
+        // Releasing outside the button cancels the click
+        let left = PointerButton::Left;
+        button.handle_pointer(
+            &make_pointer_event(PointerEventKind::Press(left), 10.0, 10.0),
+            bounds,
+        );
+        assert!(button.is_pressed());
+        button.handle_pointer(
+            &make_pointer_event(PointerEventKind::Release(left), 90.0, 10.0),
+            bounds,
+        );
+        assert!(!button.is_pressed());
+
+        button.handle_pointer(
+            &make_pointer_event(PointerEventKind::Motion, 10.0, 10.0),
+            bounds,
+        );
+        assert!(button.is_hovered());
+        button.set_enabled(false);
+        click(&mut button, bounds, 10.0, 10.0);
+        assert!(!button.handle_key(&make_key_event(Key::Space, None, Modifiers::default())));
+        assert!(!button.is_focusable());
+        assert_eq!(count.get(), 2);
+        assert!(events.poll().is_empty());
+    }
+
+    #[test]
+    fn test_checkbox_and_toggle() {
+        let events = WidgetEvents::new();
+        let mut checkbox = Checkbox::new(WidgetId(1), "Bold").events(&events);
+        let mut toggle = Toggle::new(WidgetId(2), "Wi-Fi")
+            .checked(true)
+            .events(&events);
+        let bounds = Rect::new(0, 0, 100, 20);
+
+        click(&mut checkbox, bounds, 5.0, 5.0);
+        assert!(checkbox.is_checked());
+        assert!(toggle.handle_key(&make_key_event(Key::Space, None, Modifiers::default())));
+        assert!(!toggle.is_checked());
+        assert!(!toggle.handle_key(&make_key_event(Key::Enter, None, Modifiers::default())));
+        checkbox.set_checked(false);
+
+        assert_eq!(
+            events.poll(),
+            [
+                WidgetEvent::Toggled {
+                    id: WidgetId(1),
+                    checked: true
+                },
+                WidgetEvent::Toggled {
+                    id: WidgetId(2),
+                    checked: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_radio_group() {
+        let selected = Rc::new(Cell::new(None));
+        let events = WidgetEvents::new();
+        let last = selected.clone();
+        let mut radio = RadioGroup::new(WidgetId(1), ["Small", "Medium", "Large"])
+            .spacing(4)
+            .on_select(move |i| last.set(Some(i)))
+            .events(&events);
+        let mut text = TextRenderer::new();
+        let size = radio.layout(
+            Constraints::loose(200, 200),
+            &mut LayoutContext { text: &mut text },
+        );
+        let bounds = Rect::new(0, 0, size.width, size.height);
+        let row = radio.row_height as f64 + 4.0;
+
+        click(&mut radio, bounds, 5.0, row * 2.0 + 1.0);
+        assert_eq!(radio.selected_index(), Some(2));
+        assert!(radio.handle_key(&make_key_event(Key::Down, None, Modifiers::default())));
+        assert_eq!(radio.selected_index(), Some(0));
+        assert!(radio.handle_key(&make_key_event(Key::Up, None, Modifiers::default())));
+        // Clicking the selected option again reports nothing
+        click(&mut radio, bounds, 5.0, row * 2.0 + 1.0);
+
+        assert_eq!(selected.get(), Some(2));
+        let indices: Vec<_> = events
+            .poll()
+            .into_iter()
+            .map(|event| match event {
+                WidgetEvent::Selected { index, .. } => index,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(indices, [2, 0, 2]);
+    }
+}
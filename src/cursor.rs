@@ -0,0 +1,108 @@
+//! Pointer cursor shapes.
+
+use smithay_client_toolkit::seat::pointer::CursorIcon;
+
+use crate::split::SplitDirection;
+
+/// A standard pointer cursor, set per surface with `App::set_cursor`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CursorShape {
+    #[default]
+    Default,
+    /// I-beam for editable text
+    Text,
+    /// Hand for links and clickable items
+    Pointer,
+    Grab,
+    Grabbing,
+    NotAllowed,
+    /// Busy; the app can't take input
+    Wait,
+    /// Busy in the background; the app still takes input
+    Progress,
+    Crosshair,
+    Move,
+    /// Resize a column, e.g. a vertical split divider
+    ColResize,
+    /// Resize a row, e.g. a horizontal split divider
+    RowResize,
+    NResize,
+    SResize,
+    EResize,
+    WResize,
+    NeResize,
+    NwResize,
+    SeResize,
+    SwResize,
+    EwResize,
+    NsResize,
+    /// No cursor at all
+    Hidden,
+}
+
+impl CursorShape {
+    /// The divider cursor for a split in `direction`
+    pub fn for_split(direction: SplitDirection) -> Self {
+        match direction {
+            // Left/right panes are resized by dragging sideways
+            SplitDirection::Vertical => CursorShape::ColResize,
+            SplitDirection::Horizontal => CursorShape::RowResize,
+        }
+    }
+
+    /// The icon to show, or None if the cursor is hidden
+    pub(crate) fn icon(self) -> Option<CursorIcon> {
+        Some(match self {
+            CursorShape::Default => CursorIcon::Default,
+            CursorShape::Text => CursorIcon::Text,
+            CursorShape::Pointer => CursorIcon::Pointer,
+            CursorShape::Grab => CursorIcon::Grab,
+            CursorShape::Grabbing => CursorIcon::Grabbing,
+            CursorShape::NotAllowed => CursorIcon::NotAllowed,
+            CursorShape::Wait => CursorIcon::Wait,
+            CursorShape::Progress => CursorIcon::Progress,
+            CursorShape::Crosshair => CursorIcon::Crosshair,
+            CursorShape::Move => CursorIcon::Move,
+            CursorShape::ColResize => CursorIcon::ColResize,
+            CursorShape::RowResize => CursorIcon::RowResize,
+            CursorShape::NResize => CursorIcon::NResize,
+            CursorShape::SResize => CursorIcon::SResize,
+            CursorShape::EResize => CursorIcon::EResize,
+            CursorShape::WResize => CursorIcon::WResize,
+            CursorShape::NeResize => CursorIcon::NeResize,
+            CursorShape::NwResize => CursorIcon::NwResize,
+            CursorShape::SeResize => CursorIcon::SeResize,
+            CursorShape::SwResize => CursorIcon::SwResize,
+            CursorShape::EwResize => CursorIcon::EwResize,
+            CursorShape::NsResize => CursorIcon::NsResize,
+            CursorShape::Hidden => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_split() {
+        assert_eq!(
+            CursorShape::for_split(SplitDirection::Vertical),
+            CursorShape::ColResize
+        );
+        assert_eq!(
+            CursorShape::for_split(SplitDirection::Horizontal),
+            CursorShape::RowResize
+        );
+    }
+
+    #[test]
+    fn test_icon_names() {
+        assert_eq!(CursorShape::Text.icon().map(|i| i.name()), Some("text"));
+        assert_eq!(
+            CursorShape::NotAllowed.icon().map(|i| i.name()),
+            Some("not-allowed")
+        );
+        assert_eq!(CursorShape::Hidden.icon(), None);
+    }
+}
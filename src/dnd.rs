@@ -0,0 +1,258 @@
+//! Drag-and-drop types: actions, drag events, dropped payloads and drag icons.
+
+use std::ffi::OsString;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use smithay_client_toolkit::reexports::client::protocol::wl_data_device_manager::DndAction;
+
+use crate::clipboard::is_text_mime;
+use crate::render::Canvas;
+use crate::window::SurfaceTarget;
+
+pub const MIME_URI_LIST: &str = "text/uri-list";
+
+/// What happens to the data when a drag is dropped
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DragAction {
+    #[default]
+    None,
+    Copy,
+    Move,
+    /// Let the destination ask the user, typically with a menu
+    Ask,
+}
+
+impl DragAction {
+    pub(crate) fn to_wl(self) -> DndAction {
+        match self {
+            DragAction::None => DndAction::empty(),
+            DragAction::Copy => DndAction::Copy,
+            DragAction::Move => DndAction::Move,
+            DragAction::Ask => DndAction::Ask,
+        }
+    }
+
+    /// The single action the compositor selected
+    pub(crate) fn from_wl(action: DndAction) -> Self {
+        if action.contains(DndAction::Copy) {
+            DragAction::Copy
+        } else if action.contains(DndAction::Move) {
+            DragAction::Move
+        } else if action.contains(DndAction::Ask) {
+            DragAction::Ask
+        } else {
+            DragAction::None
+        }
+    }
+
+    pub(crate) fn set_to_wl(actions: &[DragAction]) -> DndAction {
+        actions
+            .iter()
+            .fold(DndAction::empty(), |set, action| set | action.to_wl())
+    }
+
+    pub(crate) fn list_from_wl(actions: DndAction) -> Vec<DragAction> {
+        [DragAction::Copy, DragAction::Move, DragAction::Ask]
+            .into_iter()
+            .filter(|a| actions.contains(a.to_wl()))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum DragEvent {
+    /// A drag entered one of our surfaces. Call `App::accept_drag` and
+    /// `App::set_drag_actions` to say whether and how it can be dropped here.
+    Enter {
+        target: SurfaceTarget,
+        x: f64,
+        y: f64,
+        mime_types: Vec<String>,
+        source_actions: Vec<DragAction>,
+    },
+    Motion {
+        target: SurfaceTarget,
+        x: f64,
+        y: f64,
+    },
+    Leave {
+        target: SurfaceTarget,
+    },
+    /// The drop was made with the `Ask` action; call `App::resolve_drop` with
+    /// the action the user picked
+    Ask {
+        target: SurfaceTarget,
+        x: f64,
+        y: f64,
+        source_actions: Vec<DragAction>,
+    },
+    /// The accepted payload of a drop finished reading
+    Drop(DropData),
+    /// A drag we started was dropped and the destination is done with it.
+    /// For `DragAction::Move` the source should now delete the data.
+    SourceFinished {
+        action: DragAction,
+    },
+    /// A drag we started was cancelled or rejected
+    SourceCancelled,
+}
+
+#[derive(Debug)]
+pub struct DropData {
+    pub target: SurfaceTarget,
+    pub x: f64,
+    pub y: f64,
+    pub action: DragAction,
+    pub mime_type: String,
+    pub data: std::io::Result<Vec<u8>>,
+}
+
+impl DropData {
+    /// Decode the payload as text if it was read with a text MIME type
+    pub fn text(&self) -> Option<String> {
+        if !is_text_mime(&self.mime_type) {
+            return None;
+        }
+        let data = self.data.as_ref().ok()?;
+        Some(String::from_utf8_lossy(data).into_owned())
+    }
+
+    /// Local file paths, if the payload is a `text/uri-list`
+    pub fn files(&self) -> Vec<PathBuf> {
+        match &self.data {
+            Ok(data) if self.mime_type == MIME_URI_LIST => {
+                parse_uri_list(&String::from_utf8_lossy(data))
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// An image shown under the pointer while dragging
+pub struct DragIcon {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) hotspot: (i32, i32),
+    pub(crate) draw: Box<dyn FnOnce(&mut Canvas)>,
+}
+
+impl DragIcon {
+    /// An icon of the given logical size, drawn once when the drag starts
+    pub fn new(width: u32, height: u32, draw: impl FnOnce(&mut Canvas) + 'static) -> Self {
+        Self {
+            width,
+            height,
+            hotspot: (0, 0),
+            draw: Box::new(draw),
+        }
+    }
+
+    /// The point in the icon that sits under the pointer
+    pub fn with_hotspot(mut self, x: i32, y: i32) -> Self {
+        self.hotspot = (x, y);
+        self
+    }
+}
+
+/// Build a `text/uri-list` of `file://` URIs for the given paths
+pub fn files_to_uri_list(files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .filter_map(|p| p.canonicalize().ok())
+        .map(|p| format!("file://{}\r\n", percent_encode(&p)))
+        .collect()
+}
+
+/// Parse the local file paths out of a `text/uri-list`
+pub fn parse_uri_list(list: &str) -> Vec<PathBuf> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        .filter_map(|rest| {
+            // Skip the host part of file://host/path
+            let path = &rest[rest.find('/')?..];
+            Some(PathBuf::from(OsString::from_vec(percent_decode(path))))
+        })
+        .collect()
+}
+
+fn percent_encode(path: &Path) -> String {
+    let mut out = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::WindowId;
+
+    #[test]
+    fn test_parse_uri_list() {
+        let list = "# comment\r\nfile:///tmp/a%20b.txt\r\nhttps://example.com\r\nfile://localhost/etc/hosts\r\n";
+        assert_eq!(
+            parse_uri_list(list),
+            vec![PathBuf::from("/tmp/a b.txt"), PathBuf::from("/etc/hosts")]
+        );
+    }
+
+    #[test]
+    fn test_percent_encode_roundtrip() {
+        let path = Path::new("/tmp/dir with spaces/ü%.txt");
+        let encoded = percent_encode(path);
+        assert_eq!(encoded, "/tmp/dir%20with%20spaces/%C3%BC%25.txt");
+        assert_eq!(percent_decode(&encoded), path.as_os_str().as_bytes());
+    }
+
+    #[test]
+    fn test_drag_action_wl() {
+        let set = DragAction::set_to_wl(&[DragAction::Copy, DragAction::Ask]);
+        assert_eq!(
+            DragAction::list_from_wl(set),
+            vec![DragAction::Copy, DragAction::Ask]
+        );
+        assert_eq!(DragAction::from_wl(DndAction::Move), DragAction::Move);
+        assert_eq!(DragAction::from_wl(DndAction::empty()), DragAction::None);
+    }
+
+    #[test]
+    fn test_drop_data_files() {
+        let drop = DropData {
+            target: SurfaceTarget::Window(WindowId(1)),
+            x: 0.0,
+            y: 0.0,
+            action: DragAction::Copy,
+            mime_type: MIME_URI_LIST.to_string(),
+            data: Ok(b"file:///tmp/x\r\n".to_vec()),
+        };
+        assert_eq!(drop.files(), vec![PathBuf::from("/tmp/x")]);
+        assert_eq!(drop.text(), Some("file:///tmp/x\r\n".to_string()));
+    }
+}
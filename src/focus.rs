@@ -0,0 +1,317 @@
+//! Keyboard focus for a widget tree.
+//!
+//! `FocusManager` tracks which widget has focus, routes key events to it,
+//! moves focus with Tab/Shift-Tab and on click, and tells the render pass
+//! where to draw the focus ring.
+
+use crate::input::{Key, KeyEvent, KeyState, PointerEvent, PointerEventKind};
+use crate::widget::{Rect, RenderContext, Widget, WidgetId, with_widget_mut};
+
+#[derive(Default)]
+pub struct FocusManager {
+    focused: Option<WidgetId>,
+}
+
+impl FocusManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focused
+    }
+
+    pub fn is_focused(&self, id: WidgetId) -> bool {
+        self.focused == Some(id)
+    }
+
+    /// Focus the widget `id` in `root`. Returns false, leaving focus alone,
+    /// if there is no focusable widget with that id.
+    pub fn focus(&mut self, root: &mut dyn Widget, id: WidgetId) -> bool {
+        if !focusable_ids(root).contains(&id) {
+            return false;
+        }
+        self.set_focus(root, Some(id));
+        true
+    }
+
+    pub fn clear_focus(&mut self, root: &mut dyn Widget) {
+        self.set_focus(root, None);
+    }
+
+    /// Move focus to the next focusable widget in tree order, wrapping around
+    pub fn focus_next(&mut self, root: &mut dyn Widget) -> bool {
+        self.step(root, true)
+    }
+
+    /// Move focus to the previous focusable widget in tree order, wrapping around
+    pub fn focus_prev(&mut self, root: &mut dyn Widget) -> bool {
+        self.step(root, false)
+    }
+
+    /// Route a key event to the focused widget, then to its ancestors up to
+    /// `root`, until one handles it. Unhandled Tab and Shift-Tab presses move
+    /// focus. With nothing focused, `root` gets the event.
+    pub fn handle_key(&mut self, root: &mut dyn Widget, event: &KeyEvent) -> bool {
+        let path = self
+            .focused
+            .and_then(|id| path_to(root, id))
+            .unwrap_or_else(|| vec![root.id()]);
+
+        for &id in path.iter().rev() {
+            let mut handled = false;
+            with_widget_mut(root, id, &mut |widget| handled = widget.handle_key(event));
+            if handled {
+                return true;
+            }
+        }
+
+        let mods = event.modifiers;
+        if event.key == Key::Tab
+            && event.state == KeyState::Pressed
+            && !(mods.ctrl || mods.alt || mods.super_)
+        {
+            return self.step(root, !mods.shift);
+        }
+        false
+    }
+
+    /// Focus the focusable widget under a button press (or clear focus if
+    /// there is none), then pass the event to `root`
+    pub fn handle_pointer(
+        &mut self,
+        root: &mut dyn Widget,
+        event: &PointerEvent,
+        bounds: Rect,
+    ) -> bool {
+        let mut focus_changed = false;
+        if let PointerEventKind::Press(_) = event.kind {
+            let hit = focusable_at(&*root, bounds, event.x as i32, event.y as i32);
+            focus_changed = hit != self.focused;
+            self.set_focus(root, hit);
+        }
+        root.handle_pointer(event, bounds) || focus_changed
+    }
+
+    /// Render `root` into `bounds`, drawing the focus ring around the focused widget
+    pub fn render(&self, root: &dyn Widget, bounds: Rect, ctx: &mut RenderContext) {
+        ctx.focused = self.focused;
+        ctx.render_child(root, bounds);
+    }
+
+    fn step(&mut self, root: &mut dyn Widget, forward: bool) -> bool {
+        let ids = focusable_ids(root);
+        if ids.is_empty() {
+            return false;
+        }
+        let current = self
+            .focused
+            .and_then(|id| ids.iter().position(|&other| other == id));
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % ids.len(),
+            (Some(i), false) => (i + ids.len() - 1) % ids.len(),
+            (None, true) => 0,
+            (None, false) => ids.len() - 1,
+        };
+        self.set_focus(root, Some(ids[next]));
+        true
+    }
+
+    fn set_focus(&mut self, root: &mut dyn Widget, id: Option<WidgetId>) {
+        if self.focused == id {
+            return;
+        }
+        if let Some(old) = self.focused {
+            with_widget_mut(root, old, &mut |widget| widget.focus_changed(false));
+        }
+        self.focused = id;
+        if let Some(new) = id {
+            with_widget_mut(root, new, &mut |widget| widget.focus_changed(true));
+        }
+    }
+}
+
+/// Focusable widgets in tree order, which is Tab order
+fn focusable_ids(root: &dyn Widget) -> Vec<WidgetId> {
+    fn collect(widget: &dyn Widget, ids: &mut Vec<WidgetId>) {
+        if widget.is_focusable() {
+            ids.push(widget.id());
+        }
+        widget.visit_children(Rect::default(), &mut |child, _| collect(child, ids));
+    }
+
+    let mut ids = Vec::new();
+    collect(root, &mut ids);
+    ids
+}
+
+/// The innermost focusable widget containing (x, y)
+fn focusable_at(widget: &dyn Widget, bounds: Rect, x: i32, y: i32) -> Option<WidgetId> {
+    if !bounds.contains(x, y) {
+        return None;
+    }
+    let mut hit = None;
+    widget.visit_children(bounds, &mut |child, child_bounds| {
+        if hit.is_none() {
+            hit = focusable_at(child, child_bounds, x, y);
+        }
+    });
+    hit.or_else(|| widget.is_focusable().then(|| widget.id()))
+}
+
+/// The ids from `root` down to the widget `id`, inclusive
+fn path_to(root: &dyn Widget, id: WidgetId) -> Option<Vec<WidgetId>> {
+    if root.id() == id {
+        return Some(vec![id]);
+    }
+    let mut path = None;
+    root.visit_children(Rect::default(), &mut |child, _| {
+        if path.is_none() {
+            path = path_to(child, id);
+        }
+    });
+    path.map(|mut path| {
+        path.insert(0, root.id());
+        path
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Modifiers, PointerButton, make_key_event, make_pointer_event};
+    use crate::layout::VStack;
+    use crate::widget::{Constraints, LayoutContext, Size};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Field {
+        id: WidgetId,
+        focusable: bool,
+        log: Log,
+    }
+
+    impl Widget for Field {
+        fn id(&self) -> WidgetId {
+            self.id
+        }
+
+        fn layout(&mut self, _constraints: Constraints, _ctx: &mut LayoutContext) -> Size {
+            Size::new(100, 20)
+        }
+
+        fn render(&self, _bounds: Rect, _ctx: &mut RenderContext) {}
+
+        fn handle_key(&mut self, event: &KeyEvent) -> bool {
+            self.log.borrow_mut().push(format!("key {}", self.id.0));
+            event.key == Key::Enter
+        }
+
+        fn is_focusable(&self) -> bool {
+            self.focusable
+        }
+
+        fn focus_changed(&mut self, focused: bool) {
+            let change = if focused { "in" } else { "out" };
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", change, self.id.0));
+        }
+    }
+
+    fn form(log: &Log) -> VStack {
+        let field = |id, focusable| Field {
+            id: WidgetId(id),
+            focusable,
+            log: log.clone(),
+        };
+        VStack::new(WidgetId(0))
+            .child(field(1, true))
+            .child(field(2, false))
+            .child(VStack::new(WidgetId(10)).child(field(3, true)))
+    }
+
+    fn key(key: Key, shift: bool) -> KeyEvent {
+        let modifiers = Modifiers {
+            shift,
+            ..Default::default()
+        };
+        make_key_event(key, None, modifiers)
+    }
+
+    #[test]
+    fn test_tab_traversal() {
+        let log = Log::default();
+        let mut root = form(&log);
+        let mut focus = FocusManager::new();
+
+        assert!(focus.handle_key(&mut root, &key(Key::Tab, false)));
+        assert_eq!(focus.focused(), Some(WidgetId(1)));
+        focus.handle_key(&mut root, &key(Key::Tab, false));
+        assert_eq!(focus.focused(), Some(WidgetId(3)));
+        focus.handle_key(&mut root, &key(Key::Tab, false));
+        assert_eq!(focus.focused(), Some(WidgetId(1)));
+        focus.handle_key(&mut root, &key(Key::Tab, true));
+        assert_eq!(focus.focused(), Some(WidgetId(3)));
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "in 1", "key 1", "out 1", "in 3", "key 3", "out 3", "in 1", "key 1", "out 1",
+                "in 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keys_go_to_focused_widget() {
+        let log = Log::default();
+        let mut root = form(&log);
+        let mut focus = FocusManager::new();
+
+        assert!(!focus.focus(&mut root, WidgetId(2)));
+        assert!(focus.focus(&mut root, WidgetId(3)));
+        log.borrow_mut().clear();
+
+        assert!(focus.handle_key(&mut root, &key(Key::Enter, false)));
+        assert!(!focus.handle_key(&mut root, &key(Key::A, false)));
+        assert_eq!(*log.borrow(), ["key 3", "key 3"]);
+
+        focus.clear_focus(&mut root);
+        assert_eq!(focus.focused(), None);
+    }
+
+    #[test]
+    fn test_containers_keep_unfocused_keys_from_children() {
+        let log = Log::default();
+        let mut root = form(&log);
+        let mut focus = FocusManager::new();
+
+        assert!(!focus.handle_key(&mut root, &key(Key::Enter, false)));
+        assert!(!root.handle_key(&key(Key::Enter, false)));
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_click_to_focus() {
+        let log = Log::default();
+        let mut root = form(&log);
+        let mut text = crate::text::TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        root.layout(Constraints::loose(200, 200), &mut ctx);
+
+        let mut focus = FocusManager::new();
+        let bounds = Rect::new(0, 0, 200, 200);
+        let click = |y| make_pointer_event(PointerEventKind::Press(PointerButton::Left), 10.0, y);
+
+        assert!(focus.handle_pointer(&mut root, &click(45.0), bounds));
+        assert_eq!(focus.focused(), Some(WidgetId(3)));
+        // The second field isn't focusable, so clicking it clears focus
+        focus.handle_pointer(&mut root, &click(25.0), bounds);
+        assert_eq!(focus.focused(), None);
+        focus.handle_pointer(&mut root, &click(5.0), bounds);
+        assert_eq!(focus.focused(), Some(WidgetId(1)));
+    }
+}
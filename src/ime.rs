@@ -0,0 +1,234 @@
+//! Input method (zwp_text_input_v3) events and state.
+//!
+//! The compositor sends preedit, commit and delete-surrounding requests as a
+//! batch terminated by `done`; [`ImeState`] collects a batch and turns it into
+//! [`ImeEvent`]s in the order the protocol says they must be applied.
+
+use std::collections::HashSet;
+
+use crate::window::SurfaceTarget;
+
+/// Surrounding text is limited by the protocol's maximum request size
+const MAX_SURROUNDING_BYTES: usize = 4000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImeEvent {
+    /// Replace the current preedit (composing) text. An empty `text` clears it.
+    /// `cursor` is a byte range within `text`, or None to hide the cursor.
+    Preedit {
+        target: SurfaceTarget,
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    /// Insert `text` at the cursor, replacing any selection
+    Commit { target: SurfaceTarget, text: String },
+    /// Delete `before` bytes before and `after` bytes after the cursor
+    DeleteSurrounding {
+        target: SurfaceTarget,
+        before: usize,
+        after: usize,
+    },
+}
+
+/// What kind of text a field holds, so the input method can adapt
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ImePurpose {
+    #[default]
+    Normal,
+    Digits,
+    Number,
+    Phone,
+    Url,
+    Email,
+    Name,
+    /// Hidden, sensitive text; input methods shouldn't learn or show it
+    Password,
+    Pin,
+    Terminal,
+}
+
+#[derive(Default)]
+struct PendingBatch {
+    preedit: Option<(String, Option<(usize, usize)>)>,
+    commit: Option<String>,
+    delete: Option<(usize, usize)>,
+}
+
+#[derive(Default)]
+pub(crate) struct ImeState {
+    /// The surface the text input is currently focused on
+    pub focus: Option<SurfaceTarget>,
+    /// Surfaces that asked for the input method with `App::enable_ime`
+    pub wanted: HashSet<SurfaceTarget>,
+    /// Whether we have sent `enable` for the focused surface
+    pub active: bool,
+    /// Number of `commit` requests sent, echoed back in `done`
+    pub commits: u32,
+    pub cursor_rect: Option<(i32, i32, i32, i32)>,
+    pub surrounding: Option<(String, i32, i32)>,
+    pub purpose: ImePurpose,
+    preedit: String,
+    pending: PendingBatch,
+}
+
+impl ImeState {
+    pub fn set_preedit(&mut self, text: Option<String>, begin: i32, end: i32) {
+        let cursor = (begin >= 0 && end >= 0).then_some((begin as usize, end as usize));
+        self.pending.preedit = Some((text.unwrap_or_default(), cursor));
+    }
+
+    pub fn set_commit(&mut self, text: Option<String>) {
+        self.pending.commit = text;
+    }
+
+    pub fn set_delete(&mut self, before: u32, after: u32) {
+        self.pending.delete = Some((before as usize, after as usize));
+    }
+
+    /// Apply a `done` batch, yielding events in protocol order:
+    /// delete surrounding text, insert the commit, then show the new preedit
+    pub fn done(&mut self) -> Vec<ImeEvent> {
+        let pending = std::mem::take(&mut self.pending);
+        let Some(target) = self.focus else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        if let Some((before, after)) = pending.delete {
+            events.push(ImeEvent::DeleteSurrounding {
+                target,
+                before,
+                after,
+            });
+        }
+        if let Some(text) = pending.commit {
+            events.push(ImeEvent::Commit { target, text });
+        }
+
+        // Every batch replaces the old preedit, even when it sends no new one
+        let (text, cursor) = pending.preedit.unwrap_or_default();
+        if text != self.preedit || !text.is_empty() {
+            self.preedit = text.clone();
+            events.push(ImeEvent::Preedit {
+                target,
+                text,
+                cursor,
+            });
+        }
+        events
+    }
+
+    /// Clear the preedit when the input method goes away from `target`
+    pub fn clear_preedit(&mut self, target: SurfaceTarget) -> Option<ImeEvent> {
+        self.pending = PendingBatch::default();
+        if self.preedit.is_empty() {
+            return None;
+        }
+        self.preedit.clear();
+        Some(ImeEvent::Preedit {
+            target,
+            text: String::new(),
+            cursor: None,
+        })
+    }
+}
+
+/// Trim `text` to the protocol limit, keeping the part around the cursor.
+/// Returns the trimmed text and the cursor and anchor adjusted to it.
+pub(crate) fn clamp_surrounding(text: &str, cursor: usize, anchor: usize) -> (String, i32, i32) {
+    if text.len() <= MAX_SURROUNDING_BYTES {
+        return (text.to_string(), cursor as i32, anchor as i32);
+    }
+
+    let lo = cursor.min(anchor);
+    let hi = cursor.max(anchor);
+    let slack = MAX_SURROUNDING_BYTES.saturating_sub(hi - lo) / 2;
+    let mut start = lo.saturating_sub(slack);
+    let mut end = (start + MAX_SURROUNDING_BYTES).min(text.len());
+    start = end.saturating_sub(MAX_SURROUNDING_BYTES).min(start);
+
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let clamp = |offset: usize| (offset.clamp(start, end) - start) as i32;
+    (text[start..end].to_string(), clamp(cursor), clamp(anchor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::WindowId;
+
+    fn focused() -> ImeState {
+        ImeState {
+            focus: Some(SurfaceTarget::Window(WindowId(1))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_done_orders_events() {
+        let mut ime = focused();
+        let target = SurfaceTarget::Window(WindowId(1));
+        ime.set_preedit(Some("かな".to_string()), 3, 3);
+        ime.set_commit(Some("日本".to_string()));
+        ime.set_delete(1, 0);
+
+        assert_eq!(
+            ime.done(),
+            vec![
+                ImeEvent::DeleteSurrounding {
+                    target,
+                    before: 1,
+                    after: 0
+                },
+                ImeEvent::Commit {
+                    target,
+                    text: "日本".to_string()
+                },
+                ImeEvent::Preedit {
+                    target,
+                    text: "かな".to_string(),
+                    cursor: Some((3, 3))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_done_clears_preedit_once() {
+        let mut ime = focused();
+        ime.set_preedit(Some("a".to_string()), -1, -1);
+        assert_eq!(ime.done().len(), 1);
+
+        // An empty batch replaces the preedit with nothing
+        let events = ime.done();
+        assert!(
+            matches!(&events[..], [ImeEvent::Preedit { text, cursor: None, .. }] if text.is_empty())
+        );
+        assert!(ime.done().is_empty());
+    }
+
+    #[test]
+    fn test_done_without_focus() {
+        let mut ime = ImeState::default();
+        ime.set_commit(Some("x".to_string()));
+        assert!(ime.done().is_empty());
+    }
+
+    #[test]
+    fn test_clamp_surrounding() {
+        let short = clamp_surrounding("hello", 5, 0);
+        assert_eq!(short, ("hello".to_string(), 5, 0));
+
+        let text = "é".repeat(3000);
+        let (trimmed, cursor, anchor) = clamp_surrounding(&text, 3000, 3000);
+        assert!(trimmed.len() <= MAX_SURROUNDING_BYTES);
+        assert_eq!(cursor, anchor);
+        assert!(trimmed.is_char_boundary(cursor as usize));
+    }
+}
@@ -1,18 +1,50 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use smithay_client_toolkit::reexports::client::protocol::wl_pointer;
+use smithay_client_toolkit::seat::keyboard::Keysym;
+use smithay_client_toolkit::seat::pointer::AxisScroll;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2;
+
+use crate::window::SurfaceTarget;
+
 #[derive(Clone, Debug)]
 pub struct KeyEvent {
+    /// The surface holding keyboard focus when the key was pressed
+    pub target: SurfaceTarget,
+    /// The logical key the active layout produced, e.g. `Key::Z` for the key
+    /// labelled Z on a German keyboard
     pub key: Key,
+    /// The key at this physical position on a US QWERTY keyboard, whatever the
+    /// layout. Use it for shortcuts that should stay in place across layouts.
+    pub physical_key: Key,
+    /// Raw evdev scancode, e.g. 30 for the key left of S
+    pub keycode: u32,
+    /// The xkb keysym the layout produced
+    pub keysym: u32,
     pub text: Option<String>,
     pub modifiers: Modifiers,
     pub state: KeyState,
+    /// Synthesised by key repeat rather than a physical press
+    pub is_repeat: bool,
 }
 
 impl KeyEvent {
     /// Convert the key event to a string representation suitable for keybinding matching.
     /// Returns None for keys that don't produce meaningful input (like bare modifier presses).
+    /// Alt and Super are encoded as `M-` and `s-`, after any `C-`.
     pub fn to_key_string(&self) -> Option<String> {
+        let mut meta = String::new();
+        if self.modifiers.alt {
+            meta.push_str("M-");
+        }
+        if self.modifiers.super_ {
+            meta.push_str("s-");
+        }
+
         // Handle Ctrl combinations
         if self.modifiers.ctrl {
-            return self.key.to_base_char().map(|c| format!("C-{}", c));
+            return self.key.to_base_char().map(|c| format!("C-{}{}", meta, c));
         }
 
         // If we have UTF-8 text and it's printable, use it directly
@@ -22,12 +54,14 @@ impl KeyEvent {
         {
             let c = t.chars().next().unwrap();
             if !c.is_control() {
-                return Some(t.clone());
+                return Some(format!("{}{}", meta, t));
             }
         }
 
         // Fallback: use key's character representation
-        self.key.to_string_with_shift(self.modifiers.shift)
+        self.key
+            .to_string_with_shift(self.modifiers.shift)
+            .map(|s| meta + &s)
     }
 }
 
@@ -37,15 +71,19 @@ pub enum KeyState {
     Released,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Modifiers {
     pub shift: bool,
     pub ctrl: bool,
     pub alt: bool,
     pub super_: bool,
+    /// The third-level shift (right Alt on most European layouts)
+    pub alt_gr: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Key {
     // Letters
     A,
@@ -100,6 +138,18 @@ pub enum Key {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
 
     // Navigation
     Up,
@@ -124,9 +174,19 @@ pub enum Key {
     Control,
     Alt,
     Super,
+    AltGr,
+
+    // Locks
+    CapsLock,
+    NumLock,
+    ScrollLock,
 
     // Special
     Escape,
+    PrintScreen,
+    Pause,
+    /// The context menu key
+    Menu,
     Colon,
     Semicolon,
     Period,
@@ -140,6 +200,87 @@ pub enum Key {
     Quote,
     Grave,
 
+    // Shifted symbols, as produced by US layouts
+    Exclam,
+    At,
+    Hash,
+    Dollar,
+    Percent,
+    Caret,
+    Ampersand,
+    Asterisk,
+    ParenLeft,
+    ParenRight,
+    Underscore,
+    Plus,
+    BraceLeft,
+    BraceRight,
+    Pipe,
+    DoubleQuote,
+    Tilde,
+    Less,
+    Greater,
+    Question,
+
+    // Keypad; with num lock off the layout reports navigation keys instead
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    KpDecimal,
+    KpAdd,
+    KpSubtract,
+    KpMultiply,
+    KpDivide,
+    KpEnter,
+    KpEquals,
+
+    // Media and hardware keys
+    MediaPlay,
+    MediaPause,
+    MediaStop,
+    MediaNext,
+    MediaPrevious,
+    MediaRecord,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    MicMute,
+    BrightnessUp,
+    BrightnessDown,
+    Eject,
+    Power,
+
+    // International keys
+    /// The extra key next to left Shift on ISO keyboards
+    IntlBackslash,
+    /// The key left of right Shift on Japanese keyboards
+    IntlRo,
+    /// The key left of Backspace on Japanese keyboards
+    IntlYen,
+    /// Henkan on Japanese keyboards
+    Convert,
+    /// Muhenkan on Japanese keyboards
+    NonConvert,
+    KanaMode,
+    Hiragana,
+    Katakana,
+    ZenkakuHankaku,
+    HangulMode,
+    Hanja,
+
+    /// A printable character from a layout key that has no named variant,
+    /// e.g. `ä` on a German layout
+    Char(char),
+
+    /// A keysym (for `KeyEvent::key`) or scancode (for `physical_key`) with
+    /// no named key
     Unknown(u32),
 }
 
@@ -196,6 +337,45 @@ const KEY_CHARS: &[(Key, (&str, &str))] = &[
     (Key::BracketRight, ("]", "}")),
     (Key::Quote, ("'", "\"")),
     (Key::Grave, ("`", "~")),
+    // Symbols already include the shift
+    (Key::Exclam, ("!", "!")),
+    (Key::At, ("@", "@")),
+    (Key::Hash, ("#", "#")),
+    (Key::Dollar, ("$", "$")),
+    (Key::Percent, ("%", "%")),
+    (Key::Caret, ("^", "^")),
+    (Key::Ampersand, ("&", "&")),
+    (Key::Asterisk, ("*", "*")),
+    (Key::ParenLeft, ("(", "(")),
+    (Key::ParenRight, (")", ")")),
+    (Key::Underscore, ("_", "_")),
+    (Key::Plus, ("+", "+")),
+    (Key::BraceLeft, ("{", "{")),
+    (Key::BraceRight, ("}", "}")),
+    (Key::Pipe, ("|", "|")),
+    (Key::DoubleQuote, ("\"", "\"")),
+    (Key::Tilde, ("~", "~")),
+    (Key::Less, ("<", "<")),
+    (Key::Greater, (">", ">")),
+    (Key::Question, ("?", "?")),
+    // Keypad
+    (Key::Kp0, ("0", "0")),
+    (Key::Kp1, ("1", "1")),
+    (Key::Kp2, ("2", "2")),
+    (Key::Kp3, ("3", "3")),
+    (Key::Kp4, ("4", "4")),
+    (Key::Kp5, ("5", "5")),
+    (Key::Kp6, ("6", "6")),
+    (Key::Kp7, ("7", "7")),
+    (Key::Kp8, ("8", "8")),
+    (Key::Kp9, ("9", "9")),
+    (Key::KpDecimal, (".", ".")),
+    (Key::KpAdd, ("+", "+")),
+    (Key::KpSubtract, ("-", "-")),
+    (Key::KpMultiply, ("*", "*")),
+    (Key::KpDivide, ("/", "/")),
+    (Key::KpEquals, ("=", "=")),
+    (Key::KpEnter, ("\n", "\n")),
     // Special keys
     (Key::Enter, ("\n", "\n")),
     (Key::Escape, ("\x1b", "\x1b")),
@@ -204,9 +384,385 @@ const KEY_CHARS: &[(Key, (&str, &str))] = &[
     (Key::Space, (" ", " ")),
 ];
 
+const LETTERS: [Key; 26] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+];
+
+const DIGITS: [Key; 10] = [
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+const KEYPAD_DIGITS: [Key; 10] = [
+    Key::Kp0,
+    Key::Kp1,
+    Key::Kp2,
+    Key::Kp3,
+    Key::Kp4,
+    Key::Kp5,
+    Key::Kp6,
+    Key::Kp7,
+    Key::Kp8,
+    Key::Kp9,
+];
+
+const FUNCTION_KEYS: [Key; 24] = [
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+    Key::F21,
+    Key::F22,
+    Key::F23,
+    Key::F24,
+];
+
+/// Keysyms that aren't part of a contiguous range
+const KEYSYM_KEYS: &[(Keysym, Key)] = &[
+    // Navigation, including the keypad's with num lock off
+    (Keysym::Up, Key::Up),
+    (Keysym::Down, Key::Down),
+    (Keysym::Left, Key::Left),
+    (Keysym::Right, Key::Right),
+    (Keysym::Home, Key::Home),
+    (Keysym::End, Key::End),
+    (Keysym::Page_Up, Key::PageUp),
+    (Keysym::Page_Down, Key::PageDown),
+    (Keysym::KP_Up, Key::Up),
+    (Keysym::KP_Down, Key::Down),
+    (Keysym::KP_Left, Key::Left),
+    (Keysym::KP_Right, Key::Right),
+    (Keysym::KP_Home, Key::Home),
+    (Keysym::KP_End, Key::End),
+    (Keysym::KP_Page_Up, Key::PageUp),
+    (Keysym::KP_Page_Down, Key::PageDown),
+    // Editing
+    (Keysym::BackSpace, Key::Backspace),
+    (Keysym::Delete, Key::Delete),
+    (Keysym::Insert, Key::Insert),
+    (Keysym::KP_Delete, Key::Delete),
+    (Keysym::KP_Insert, Key::Insert),
+    (Keysym::Return, Key::Enter),
+    (Keysym::Tab, Key::Tab),
+    (Keysym::ISO_Left_Tab, Key::Tab),
+    (Keysym::KP_Tab, Key::Tab),
+    (Keysym::space, Key::Space),
+    (Keysym::KP_Space, Key::Space),
+    // Modifiers and locks
+    (Keysym::Shift_L, Key::Shift),
+    (Keysym::Shift_R, Key::Shift),
+    (Keysym::Control_L, Key::Control),
+    (Keysym::Control_R, Key::Control),
+    (Keysym::Alt_L, Key::Alt),
+    (Keysym::Alt_R, Key::Alt),
+    (Keysym::Super_L, Key::Super),
+    (Keysym::Super_R, Key::Super),
+    (Keysym::ISO_Level3_Shift, Key::AltGr),
+    (Keysym::Mode_switch, Key::AltGr),
+    (Keysym::Caps_Lock, Key::CapsLock),
+    (Keysym::Num_Lock, Key::NumLock),
+    (Keysym::Scroll_Lock, Key::ScrollLock),
+    // Special
+    (Keysym::Escape, Key::Escape),
+    (Keysym::Print, Key::PrintScreen),
+    (Keysym::Sys_Req, Key::PrintScreen),
+    (Keysym::Pause, Key::Pause),
+    (Keysym::Break, Key::Pause),
+    (Keysym::Menu, Key::Menu),
+    // Punctuation
+    (Keysym::colon, Key::Colon),
+    (Keysym::semicolon, Key::Semicolon),
+    (Keysym::period, Key::Period),
+    (Keysym::comma, Key::Comma),
+    (Keysym::slash, Key::Slash),
+    (Keysym::backslash, Key::Backslash),
+    (Keysym::minus, Key::Minus),
+    (Keysym::equal, Key::Equals),
+    (Keysym::bracketleft, Key::BracketLeft),
+    (Keysym::bracketright, Key::BracketRight),
+    (Keysym::apostrophe, Key::Quote),
+    (Keysym::grave, Key::Grave),
+    (Keysym::exclam, Key::Exclam),
+    (Keysym::at, Key::At),
+    (Keysym::numbersign, Key::Hash),
+    (Keysym::dollar, Key::Dollar),
+    (Keysym::percent, Key::Percent),
+    (Keysym::asciicircum, Key::Caret),
+    (Keysym::ampersand, Key::Ampersand),
+    (Keysym::asterisk, Key::Asterisk),
+    (Keysym::parenleft, Key::ParenLeft),
+    (Keysym::parenright, Key::ParenRight),
+    (Keysym::underscore, Key::Underscore),
+    (Keysym::plus, Key::Plus),
+    (Keysym::braceleft, Key::BraceLeft),
+    (Keysym::braceright, Key::BraceRight),
+    (Keysym::bar, Key::Pipe),
+    (Keysym::quotedbl, Key::DoubleQuote),
+    (Keysym::asciitilde, Key::Tilde),
+    (Keysym::less, Key::Less),
+    (Keysym::greater, Key::Greater),
+    (Keysym::question, Key::Question),
+    // Keypad operators
+    (Keysym::KP_Decimal, Key::KpDecimal),
+    (Keysym::KP_Separator, Key::KpDecimal),
+    (Keysym::KP_Add, Key::KpAdd),
+    (Keysym::KP_Subtract, Key::KpSubtract),
+    (Keysym::KP_Multiply, Key::KpMultiply),
+    (Keysym::KP_Divide, Key::KpDivide),
+    (Keysym::KP_Enter, Key::KpEnter),
+    (Keysym::KP_Equal, Key::KpEquals),
+    // Media and hardware
+    (Keysym::XF86_AudioPlay, Key::MediaPlay),
+    (Keysym::XF86_AudioPause, Key::MediaPause),
+    (Keysym::XF86_AudioStop, Key::MediaStop),
+    (Keysym::XF86_AudioNext, Key::MediaNext),
+    (Keysym::XF86_AudioPrev, Key::MediaPrevious),
+    (Keysym::XF86_AudioRecord, Key::MediaRecord),
+    (Keysym::XF86_AudioRaiseVolume, Key::VolumeUp),
+    (Keysym::XF86_AudioLowerVolume, Key::VolumeDown),
+    (Keysym::XF86_AudioMute, Key::VolumeMute),
+    (Keysym::XF86_AudioMicMute, Key::MicMute),
+    (Keysym::XF86_MonBrightnessUp, Key::BrightnessUp),
+    (Keysym::XF86_MonBrightnessDown, Key::BrightnessDown),
+    (Keysym::XF86_Eject, Key::Eject),
+    (Keysym::XF86_PowerOff, Key::Power),
+    // International
+    (Keysym::Henkan_Mode, Key::Convert),
+    (Keysym::Muhenkan, Key::NonConvert),
+    (Keysym::Hiragana_Katakana, Key::KanaMode),
+    (Keysym::Hiragana, Key::Hiragana),
+    (Keysym::Katakana, Key::Katakana),
+    (Keysym::Zenkaku_Hankaku, Key::ZenkakuHankaku),
+    (Keysym::Hangul, Key::HangulMode),
+    (Keysym::Hangul_Hanja, Key::Hanja),
+];
+
+/// Linux evdev scancodes and the key at that position on a US QWERTY keyboard
+const KEYCODE_KEYS: &[(u32, Key)] = &[
+    (1, Key::Escape),
+    (2, Key::Num1),
+    (3, Key::Num2),
+    (4, Key::Num3),
+    (5, Key::Num4),
+    (6, Key::Num5),
+    (7, Key::Num6),
+    (8, Key::Num7),
+    (9, Key::Num8),
+    (10, Key::Num9),
+    (11, Key::Num0),
+    (12, Key::Minus),
+    (13, Key::Equals),
+    (14, Key::Backspace),
+    (15, Key::Tab),
+    (16, Key::Q),
+    (17, Key::W),
+    (18, Key::E),
+    (19, Key::R),
+    (20, Key::T),
+    (21, Key::Y),
+    (22, Key::U),
+    (23, Key::I),
+    (24, Key::O),
+    (25, Key::P),
+    (26, Key::BracketLeft),
+    (27, Key::BracketRight),
+    (28, Key::Enter),
+    (29, Key::Control),
+    (30, Key::A),
+    (31, Key::S),
+    (32, Key::D),
+    (33, Key::F),
+    (34, Key::G),
+    (35, Key::H),
+    (36, Key::J),
+    (37, Key::K),
+    (38, Key::L),
+    (39, Key::Semicolon),
+    (40, Key::Quote),
+    (41, Key::Grave),
+    (42, Key::Shift),
+    (43, Key::Backslash),
+    (44, Key::Z),
+    (45, Key::X),
+    (46, Key::C),
+    (47, Key::V),
+    (48, Key::B),
+    (49, Key::N),
+    (50, Key::M),
+    (51, Key::Comma),
+    (52, Key::Period),
+    (53, Key::Slash),
+    (54, Key::Shift),
+    (55, Key::KpMultiply),
+    (56, Key::Alt),
+    (57, Key::Space),
+    (58, Key::CapsLock),
+    (59, Key::F1),
+    (60, Key::F2),
+    (61, Key::F3),
+    (62, Key::F4),
+    (63, Key::F5),
+    (64, Key::F6),
+    (65, Key::F7),
+    (66, Key::F8),
+    (67, Key::F9),
+    (68, Key::F10),
+    (69, Key::NumLock),
+    (70, Key::ScrollLock),
+    (71, Key::Kp7),
+    (72, Key::Kp8),
+    (73, Key::Kp9),
+    (74, Key::KpSubtract),
+    (75, Key::Kp4),
+    (76, Key::Kp5),
+    (77, Key::Kp6),
+    (78, Key::KpAdd),
+    (79, Key::Kp1),
+    (80, Key::Kp2),
+    (81, Key::Kp3),
+    (82, Key::Kp0),
+    (83, Key::KpDecimal),
+    (85, Key::ZenkakuHankaku),
+    (86, Key::IntlBackslash),
+    (87, Key::F11),
+    (88, Key::F12),
+    (89, Key::IntlRo),
+    (90, Key::Katakana),
+    (91, Key::Hiragana),
+    (92, Key::Convert),
+    (93, Key::KanaMode),
+    (94, Key::NonConvert),
+    (96, Key::KpEnter),
+    (97, Key::Control),
+    (98, Key::KpDivide),
+    (99, Key::PrintScreen),
+    (100, Key::Alt),
+    (102, Key::Home),
+    (103, Key::Up),
+    (104, Key::PageUp),
+    (105, Key::Left),
+    (106, Key::Right),
+    (107, Key::End),
+    (108, Key::Down),
+    (109, Key::PageDown),
+    (110, Key::Insert),
+    (111, Key::Delete),
+    (113, Key::VolumeMute),
+    (114, Key::VolumeDown),
+    (115, Key::VolumeUp),
+    (116, Key::Power),
+    (117, Key::KpEquals),
+    (119, Key::Pause),
+    (121, Key::KpDecimal),
+    (122, Key::HangulMode),
+    (123, Key::Hanja),
+    (124, Key::IntlYen),
+    (125, Key::Super),
+    (126, Key::Super),
+    (127, Key::Menu),
+    (161, Key::Eject),
+    (163, Key::MediaNext),
+    (164, Key::MediaPlay),
+    (165, Key::MediaPrevious),
+    (166, Key::MediaStop),
+    (167, Key::MediaRecord),
+    (183, Key::F13),
+    (184, Key::F14),
+    (185, Key::F15),
+    (186, Key::F16),
+    (187, Key::F17),
+    (188, Key::F18),
+    (189, Key::F19),
+    (190, Key::F20),
+    (191, Key::F21),
+    (192, Key::F22),
+    (193, Key::F23),
+    (194, Key::F24),
+    (200, Key::MediaPlay),
+    (201, Key::MediaPause),
+    (224, Key::BrightnessDown),
+    (225, Key::BrightnessUp),
+    (248, Key::MicMute),
+];
+
+/// Every key with a name of its own, i.e. all but `Char` and `Unknown`
+pub(crate) fn named_keys() -> impl Iterator<Item = Key> {
+    FUNCTION_KEYS
+        .into_iter()
+        .chain(KEYSYM_KEYS.iter().map(|(_, key)| *key))
+        .chain(KEYCODE_KEYS.iter().map(|(_, key)| *key))
+}
+
 impl Key {
+    /// Modifier and lock keys: Shift, Control, Alt, AltGr, Super and the locks
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self,
+            Key::Shift
+                | Key::Control
+                | Key::Alt
+                | Key::AltGr
+                | Key::Super
+                | Key::CapsLock
+                | Key::NumLock
+                | Key::ScrollLock
+        )
+    }
+
     /// Get the base lowercase character for this key (for Ctrl combinations)
     pub fn to_base_char(&self) -> Option<char> {
+        if let Key::Char(c) = self {
+            return c.to_lowercase().next().filter(|c| c.is_alphabetic());
+        }
         KEY_CHARS
             .iter()
             .find(|(k, _)| k == self)
@@ -216,112 +772,694 @@ impl Key {
 
     /// Get the string representation of this key, considering shift state
     pub fn to_string_with_shift(&self, shifted: bool) -> Option<String> {
+        if let Key::Char(c) = self {
+            return Some(c.to_string());
+        }
         KEY_CHARS
             .iter()
             .find(|(k, _)| k == self)
             .map(|(_, (base, shift))| if shifted { *shift } else { *base }.to_string())
     }
 
+    /// The logical key for an xkb keysym. Letters map to the same key in
+    /// either case; other printable keysyms become `Key::Char`.
     pub fn from_keysym(keysym: u32) -> Self {
-        use smithay_client_toolkit::seat::keyboard::Keysym;
-
+        let index = |first: Keysym| keysym.wrapping_sub(first.raw()) as usize;
         match keysym {
-            x if x == Keysym::a.raw() => Key::A,
-            x if x == Keysym::b.raw() => Key::B,
-            x if x == Keysym::c.raw() => Key::C,
-            x if x == Keysym::d.raw() => Key::D,
-            x if x == Keysym::e.raw() => Key::E,
-            x if x == Keysym::f.raw() => Key::F,
-            x if x == Keysym::g.raw() => Key::G,
-            x if x == Keysym::h.raw() => Key::H,
-            x if x == Keysym::i.raw() => Key::I,
-            x if x == Keysym::j.raw() => Key::J,
-            x if x == Keysym::k.raw() => Key::K,
-            x if x == Keysym::l.raw() => Key::L,
-            x if x == Keysym::m.raw() => Key::M,
-            x if x == Keysym::n.raw() => Key::N,
-            x if x == Keysym::o.raw() => Key::O,
-            x if x == Keysym::p.raw() => Key::P,
-            x if x == Keysym::q.raw() => Key::Q,
-            x if x == Keysym::r.raw() => Key::R,
-            x if x == Keysym::s.raw() => Key::S,
-            x if x == Keysym::t.raw() => Key::T,
-            x if x == Keysym::u.raw() => Key::U,
-            x if x == Keysym::v.raw() => Key::V,
-            x if x == Keysym::w.raw() => Key::W,
-            x if x == Keysym::x.raw() => Key::X,
-            x if x == Keysym::y.raw() => Key::Y,
-            x if x == Keysym::z.raw() => Key::Z,
-
-            x if x == Keysym::_0.raw() => Key::Num0,
-            x if x == Keysym::_1.raw() => Key::Num1,
-            x if x == Keysym::_2.raw() => Key::Num2,
-            x if x == Keysym::_3.raw() => Key::Num3,
-            x if x == Keysym::_4.raw() => Key::Num4,
-            x if x == Keysym::_5.raw() => Key::Num5,
-            x if x == Keysym::_6.raw() => Key::Num6,
-            x if x == Keysym::_7.raw() => Key::Num7,
-            x if x == Keysym::_8.raw() => Key::Num8,
-            x if x == Keysym::_9.raw() => Key::Num9,
-
-            x if x == Keysym::Up.raw() => Key::Up,
-            x if x == Keysym::Down.raw() => Key::Down,
-            x if x == Keysym::Left.raw() => Key::Left,
-            x if x == Keysym::Right.raw() => Key::Right,
-
-            x if x == Keysym::Return.raw() => Key::Enter,
-            x if x == Keysym::Escape.raw() => Key::Escape,
-            x if x == Keysym::BackSpace.raw() => Key::Backspace,
-            x if x == Keysym::Tab.raw() => Key::Tab,
-            x if x == Keysym::space.raw() => Key::Space,
-
-            x if x == Keysym::colon.raw() => Key::Colon,
-            x if x == Keysym::semicolon.raw() => Key::Semicolon,
-            x if x == Keysym::period.raw() => Key::Period,
-            x if x == Keysym::comma.raw() => Key::Comma,
-
-            _ => Key::Unknown(keysym),
+            x if (Keysym::a.raw()..=Keysym::z.raw()).contains(&x) => LETTERS[index(Keysym::a)],
+            x if (Keysym::A.raw()..=Keysym::Z.raw()).contains(&x) => LETTERS[index(Keysym::A)],
+            x if (Keysym::_0.raw()..=Keysym::_9.raw()).contains(&x) => DIGITS[index(Keysym::_0)],
+            x if (Keysym::KP_0.raw()..=Keysym::KP_9.raw()).contains(&x) => {
+                KEYPAD_DIGITS[index(Keysym::KP_0)]
+            }
+            x if (Keysym::F1.raw()..=Keysym::F24.raw()).contains(&x) => {
+                FUNCTION_KEYS[index(Keysym::F1)]
+            }
+            _ => KEYSYM_KEYS
+                .iter()
+                .find(|(sym, _)| sym.raw() == keysym)
+                .map(|(_, key)| *key)
+                .or_else(|| {
+                    Keysym::new(keysym)
+                        .key_char()
+                        .filter(|c| !c.is_control())
+                        .map(Key::Char)
+                })
+                .unwrap_or(Key::Unknown(keysym)),
         }
     }
+
+    /// The key at an evdev scancode's position on a US QWERTY keyboard
+    pub fn from_keycode(keycode: u32) -> Self {
+        KEYCODE_KEYS
+            .iter()
+            .find(|(code, _)| *code == keycode)
+            .map(|(_, key)| *key)
+            .unwrap_or(Key::Unknown(keycode))
+    }
+}
+
+/// Key repeat driven by `wl_keyboard.repeat_info`
+pub(crate) struct KeyRepeat {
+    delay: Duration,
+    /// None when the compositor disabled repeat (rate 0)
+    interval: Option<Duration>,
+    key: Option<KeyEvent>,
+    next: Option<Instant>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        // Used until the compositor sends its repeat info
+        Self {
+            delay: Duration::from_millis(400),
+            interval: Some(Duration::from_millis(33)),
+            key: None,
+            next: None,
+        }
+    }
+}
+
+impl KeyRepeat {
+    /// Apply the compositor's repeat info: `rate` repeats per second after `delay_ms`
+    pub fn set_info(&mut self, rate: u32, delay_ms: u32) {
+        self.delay = Duration::from_millis(delay_ms as u64);
+        self.interval = (rate > 0).then(|| Duration::from_secs(1) / rate);
+        if self.interval.is_none() {
+            self.cancel();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    /// Start repeating `key`; modifier keys never repeat
+    pub fn press(&mut self, key: &KeyEvent, now: Instant) {
+        if !self.is_enabled() || key.key.is_modifier() {
+            return;
+        }
+        self.key = Some(KeyEvent {
+            is_repeat: true,
+            ..key.clone()
+        });
+        self.next = Some(now + self.delay);
+    }
+
+    /// Stop repeating if the key at `keycode` is the one being repeated.
+    /// Compared by scancode since the keysym can change with modifiers.
+    pub fn release(&mut self, keycode: u32) {
+        if self.key.as_ref().is_some_and(|k| k.keycode == keycode) {
+            self.cancel();
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.key = None;
+        self.next = None;
+    }
+
+    /// When the next repeat is due
+    pub fn deadline(&self) -> Option<Instant> {
+        self.next
+    }
+
+    /// The repeat event if one is due at `now`
+    pub fn due(&mut self, now: Instant) -> Option<KeyEvent> {
+        let (next, interval) = (self.next?, self.interval?);
+        if now < next {
+            return None;
+        }
+        // Skip missed repeats rather than bursting after a stall
+        self.next = Some((next + interval).max(now));
+        self.key.clone()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct PointerEvent {
+    /// The surface under the pointer; `x`/`y` are local to this surface
+    pub target: SurfaceTarget,
     pub kind: PointerEventKind,
     pub x: f64,
     pub y: f64,
+    /// Keyboard modifiers held at the time, for Shift-click and Ctrl-click
+    pub modifiers: Modifiers,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+impl PointerEvent {
+    /// An event with no modifiers held
+    pub fn new(target: SurfaceTarget, kind: PointerEventKind, x: f64, y: f64) -> Self {
+        Self {
+            target,
+            kind,
+            x,
+            y,
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PointerEventKind {
     Enter,
     Leave,
     Motion,
     Press(PointerButton),
     Release(PointerButton),
-    Scroll { dx: i32, dy: i32 },
+    Scroll(ScrollEvent),
+    /// Raw pointer motion, reported even while the pointer is locked or stopped
+    /// by a screen edge. `dx`/`dy` include pointer acceleration; the
+    /// `_unaccel` deltas are the device's own motion.
+    RelativeMotion {
+        dx: f64,
+        dy: f64,
+        dx_unaccel: f64,
+        dy_unaccel: f64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct TouchEvent {
+    /// The surface the touch point went down on; positions are local to it
+    pub target: SurfaceTarget,
+    pub kind: TouchEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TouchEventKind {
+    /// A new touch point `id`; ids are reused after `Up`
+    Down {
+        id: i32,
+        x: f64,
+        y: f64,
+    },
+    /// The touch point lifted at its last known position
+    Up {
+        id: i32,
+        x: f64,
+        y: f64,
+    },
+    Motion {
+        id: i32,
+        x: f64,
+        y: f64,
+    },
+    /// The compositor took over the touch point (e.g. for a gesture);
+    /// undo whatever it started
+    Cancel {
+        id: i32,
+    },
+    /// The events since the previous frame belong together, e.g. several
+    /// fingers moving at once
+    Frame,
+}
+
+/// Tracks active touch points so events that only carry an id can be routed
+/// to the surface the point went down on
+#[derive(Default)]
+pub(crate) struct TouchState {
+    points: HashMap<i32, (SurfaceTarget, f64, f64)>,
+    /// Surfaces that received events since the last frame
+    framed: Vec<SurfaceTarget>,
+}
+
+impl TouchState {
+    pub fn down(&mut self, target: SurfaceTarget, id: i32, x: f64, y: f64) -> TouchEvent {
+        self.points.insert(id, (target, x, y));
+        self.touched(target);
+        TouchEvent {
+            target,
+            kind: TouchEventKind::Down { id, x, y },
+        }
+    }
+
+    pub fn motion(&mut self, id: i32, x: f64, y: f64) -> Option<TouchEvent> {
+        let point = self.points.get_mut(&id)?;
+        point.1 = x;
+        point.2 = y;
+        let target = point.0;
+        self.touched(target);
+        Some(TouchEvent {
+            target,
+            kind: TouchEventKind::Motion { id, x, y },
+        })
+    }
+
+    pub fn up(&mut self, id: i32) -> Option<TouchEvent> {
+        let (target, x, y) = self.points.remove(&id)?;
+        self.touched(target);
+        Some(TouchEvent {
+            target,
+            kind: TouchEventKind::Up { id, x, y },
+        })
+    }
+
+    /// One frame event per surface that saw activity
+    pub fn frame(&mut self) -> Vec<TouchEvent> {
+        self.framed
+            .drain(..)
+            .map(|target| TouchEvent {
+                target,
+                kind: TouchEventKind::Frame,
+            })
+            .collect()
+    }
+
+    /// Cancel every active point
+    pub fn cancel(&mut self) -> Vec<TouchEvent> {
+        self.framed.clear();
+        let mut points: Vec<_> = self.points.drain().collect();
+        points.sort_by_key(|(id, _)| *id);
+        points
+            .into_iter()
+            .map(|(id, (target, _, _))| TouchEvent {
+                target,
+                kind: TouchEventKind::Cancel { id },
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    fn touched(&mut self, target: SurfaceTarget) {
+        if !self.framed.contains(&target) {
+            self.framed.push(target);
+        }
+    }
+}
+
+/// A touchpad gesture, delivered to the surface under the pointer when it began
+#[derive(Clone, Debug)]
+pub struct GestureEvent {
+    pub target: SurfaceTarget,
+    pub kind: GestureEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GestureEventKind {
+    SwipeBegin {
+        fingers: u32,
+    },
+    /// Motion since the last update, in surface-local logical pixels
+    SwipeUpdate {
+        dx: f64,
+        dy: f64,
+    },
+    SwipeEnd {
+        cancelled: bool,
+    },
+    PinchBegin {
+        fingers: u32,
+    },
+    /// `scale` is relative to the start of the pinch (1.0 = unchanged);
+    /// `rotation` is in degrees clockwise since the last update
+    PinchUpdate {
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+    },
+    PinchEnd {
+        cancelled: bool,
+    },
+    /// Fingers resting on the touchpad, e.g. to stop kinetic scrolling
+    HoldBegin {
+        fingers: u32,
+    },
+    HoldEnd {
+        cancelled: bool,
+    },
+}
+
+/// Drawing tablet input. Tool events go to the surface the tool is over,
+/// pad events to the surface the pad is focused on.
+#[derive(Clone, Debug)]
+pub struct TabletEvent {
+    pub target: SurfaceTarget,
+    pub kind: TabletEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TabletEventKind {
+    /// A tool came into range over the surface
+    ProximityIn { tool: TabletTool },
+    /// The tool left the surface or went out of range
+    ProximityOut { tool: TabletTool },
+    /// The tip touched the tablet
+    Down { tool: TabletTool },
+    /// The tip lifted off the tablet
+    Up { tool: TabletTool },
+    /// The tool's position and axes after a frame of changes
+    Motion { tool: TabletTool, axes: TabletAxes },
+    /// The tool's wheel turned, e.g. the finger wheel on an airbrush
+    Wheel {
+        tool: TabletTool,
+        degrees: f64,
+        clicks: i32,
+    },
+    /// A button on the tool; `button` is an evdev code such as `BTN_STYLUS` (0x14b)
+    Button {
+        tool: TabletTool,
+        button: u32,
+        pressed: bool,
+    },
+    /// A button on the pad; `button` is its index on the pad
+    PadButton { button: u32, pressed: bool },
+    /// A pad ring turned to `degrees` clockwise from its top, or None when
+    /// the finger lifted
+    PadRing { ring: u32, degrees: Option<f64> },
+    /// A pad strip moved to `position` from 0.0 (top or left) to 1.0, or None
+    /// when the finger lifted
+    PadStrip { strip: u32, position: Option<f64> },
+    /// A pad dial turned, where 120 is one detent
+    PadDial { dial: u32, value120: i32 },
+    /// A pad group switched mode; apps may remap its buttons, rings and strips
+    PadMode { group: u32, mode: u32 },
+}
+
+/// A physical tablet tool, e.g. a pen or the eraser end of one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TabletTool {
+    pub tool_type: TabletToolType,
+    /// Serial number of the tool, or 0 if the tablet can't tell tools apart
+    pub serial: u64,
+    pub capabilities: TabletToolCapabilities,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TabletToolType {
+    #[default]
+    Pen,
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+    Finger,
+    Mouse,
+    Lens,
+}
+
+impl TabletToolType {
+    pub(crate) fn from_wl(tool_type: zwp_tablet_tool_v2::Type) -> Self {
+        use zwp_tablet_tool_v2::Type;
+        match tool_type {
+            Type::Eraser => TabletToolType::Eraser,
+            Type::Brush => TabletToolType::Brush,
+            Type::Pencil => TabletToolType::Pencil,
+            Type::Airbrush => TabletToolType::Airbrush,
+            Type::Finger => TabletToolType::Finger,
+            Type::Mouse => TabletToolType::Mouse,
+            Type::Lens => TabletToolType::Lens,
+            _ => TabletToolType::Pen,
+        }
+    }
+}
+
+/// The axes a tool reports besides its position
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TabletToolCapabilities {
+    pub pressure: bool,
+    pub distance: bool,
+    pub tilt: bool,
+    pub rotation: bool,
+    pub slider: bool,
+    pub wheel: bool,
+}
+
+impl TabletToolCapabilities {
+    pub(crate) fn add(&mut self, capability: zwp_tablet_tool_v2::Capability) {
+        use zwp_tablet_tool_v2::Capability;
+        match capability {
+            Capability::Pressure => self.pressure = true,
+            Capability::Distance => self.distance = true,
+            Capability::Tilt => self.tilt = true,
+            Capability::Rotation => self.rotation = true,
+            Capability::Slider => self.slider = true,
+            Capability::Wheel => self.wheel = true,
+            _ => {}
+        }
+    }
+}
+
+/// A tool's current axis values; axes the tool doesn't have stay at zero
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TabletAxes {
+    /// Surface-local position in logical pixels
+    pub x: f64,
+    pub y: f64,
+    /// Tip pressure from 0.0 to 1.0
+    pub pressure: f64,
+    /// Hover distance from 0.0 to 1.0
+    pub distance: f64,
+    /// Tilt away from upright in degrees, positive towards +x and +y
+    pub tilt_x: f64,
+    pub tilt_y: f64,
+    /// Rotation around the tool's own axis, in degrees clockwise
+    pub rotation: f64,
+    /// Slider position from -1.0 to 1.0, e.g. an airbrush's
+    pub slider: f64,
+}
+
+/// Normalized tablet axes are sent in 0..=65535
+const TABLET_AXIS_MAX: f64 = 65535.0;
+
+/// Collects a tool's events until the compositor's frame, so axes that
+/// changed together arrive as a single `Motion`
+#[derive(Default)]
+pub(crate) struct TabletToolState {
+    pub tool: TabletTool,
+    focus: Option<SurfaceTarget>,
+    axes: TabletAxes,
+    pending: Vec<TabletEventKind>,
+    /// Where in `pending` this frame's motion goes
+    motion_at: Option<usize>,
+}
+
+impl TabletToolState {
+    /// `target` is None when the tool is over a surface that isn't ours
+    pub fn proximity_in(&mut self, target: Option<SurfaceTarget>) {
+        self.focus = target;
+        self.push(|tool| TabletEventKind::ProximityIn { tool });
+    }
+
+    pub fn proximity_out(&mut self) {
+        self.push(|tool| TabletEventKind::ProximityOut { tool });
+    }
+
+    pub fn down(&mut self) {
+        self.push(|tool| TabletEventKind::Down { tool });
+    }
+
+    pub fn up(&mut self) {
+        self.push(|tool| TabletEventKind::Up { tool });
+    }
+
+    pub fn button(&mut self, button: u32, pressed: bool) {
+        self.push(|tool| TabletEventKind::Button {
+            tool,
+            button,
+            pressed,
+        });
+    }
+
+    pub fn wheel(&mut self, degrees: f64, clicks: i32) {
+        self.push(|tool| TabletEventKind::Wheel {
+            tool,
+            degrees,
+            clicks,
+        });
+    }
+
+    pub fn motion(&mut self, x: f64, y: f64) {
+        let axes = self.axes_mut();
+        axes.x = x;
+        axes.y = y;
+    }
+
+    pub fn pressure(&mut self, raw: u32) {
+        self.axes_mut().pressure = raw as f64 / TABLET_AXIS_MAX;
+    }
+
+    pub fn distance(&mut self, raw: u32) {
+        self.axes_mut().distance = raw as f64 / TABLET_AXIS_MAX;
+    }
+
+    pub fn tilt(&mut self, x: f64, y: f64) {
+        let axes = self.axes_mut();
+        axes.tilt_x = x;
+        axes.tilt_y = y;
+    }
+
+    pub fn rotation(&mut self, degrees: f64) {
+        self.axes_mut().rotation = degrees;
+    }
+
+    pub fn slider(&mut self, raw: i32) {
+        self.axes_mut().slider = raw as f64 / TABLET_AXIS_MAX;
+    }
+
+    /// The events of the finished frame, in the order they were sent
+    pub fn frame(&mut self) -> Vec<TabletEvent> {
+        let mut kinds = std::mem::take(&mut self.pending);
+        if let Some(at) = self.motion_at.take() {
+            let (tool, axes) = (self.tool, self.axes);
+            kinds.insert(at, TabletEventKind::Motion { tool, axes });
+        }
+        let Some(target) = self.focus else {
+            return Vec::new();
+        };
+
+        if kinds
+            .iter()
+            .any(|kind| matches!(kind, TabletEventKind::ProximityOut { .. }))
+        {
+            self.focus = None;
+            self.axes = TabletAxes::default();
+        }
+        kinds
+            .into_iter()
+            .map(|kind| TabletEvent { target, kind })
+            .collect()
+    }
+
+    fn push(&mut self, kind: impl FnOnce(TabletTool) -> TabletEventKind) {
+        self.pending.push(kind(self.tool));
+    }
+
+    fn axes_mut(&mut self) -> &mut TabletAxes {
+        self.motion_at.get_or_insert(self.pending.len());
+        &mut self.axes
+    }
+}
+
+/// What produced a scroll
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScrollSource {
+    #[default]
+    Wheel,
+    /// A touchpad; expect a `stop` when the fingers lift
+    Finger,
+    /// Continuous motion without a terminating stop, e.g. a trackpoint
+    Continuous,
+    /// Side-to-side tilt of a wheel
+    WheelTilt,
+}
+
+/// One pointer frame's worth of scrolling on both axes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScrollEvent {
+    /// Pixel-precise scroll distance in surface-local logical pixels
+    pub dx: f64,
+    pub dy: f64,
+    /// Whole wheel detents. sctk binds `wl_seat` at v7 at most, so the
+    /// pointer never sends high-resolution (value120) wheel data, nor the
+    /// natural-scroll direction.
+    pub discrete_x: i32,
+    pub discrete_y: i32,
+    pub source: ScrollSource,
+    /// Scrolling on an axis ended (fingers lifted); kinetic scrolling can start
+    pub stop_x: bool,
+    pub stop_y: bool,
+}
+
+impl ScrollEvent {
+    /// Whole wheel detents on each axis, for code that scrolls by lines
+    pub fn steps(&self) -> (i32, i32) {
+        (self.discrete_x, self.discrete_y)
+    }
+
+    /// Build from sctk's merged axis data; None if the frame carried nothing
+    pub(crate) fn from_axis(
+        horizontal: &AxisScroll,
+        vertical: &AxisScroll,
+        source: Option<wl_pointer::AxisSource>,
+    ) -> Option<Self> {
+        if horizontal.is_none() && vertical.is_none() {
+            return None;
+        }
+        let source = match source {
+            Some(wl_pointer::AxisSource::Finger) => ScrollSource::Finger,
+            Some(wl_pointer::AxisSource::Continuous) => ScrollSource::Continuous,
+            Some(wl_pointer::AxisSource::WheelTilt) => ScrollSource::WheelTilt,
+            _ => ScrollSource::Wheel,
+        };
+        Some(Self {
+            dx: horizontal.absolute,
+            dy: vertical.absolute,
+            discrete_x: horizontal.discrete,
+            discrete_y: vertical.discrete,
+            source,
+            stop_x: horizontal.stop,
+            stop_y: vertical.stop,
+        })
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PointerButton {
     Left,
     Right,
+    /// By convention a middle click pastes the primary selection
+    /// (see `App::read_primary_selection_text`)
     Middle,
     Other(u32),
 }
 
+impl PointerButton {
+    /// Map a linux evdev button code to a pointer button
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            272 => PointerButton::Left,   // BTN_LEFT
+            273 => PointerButton::Right,  // BTN_RIGHT
+            274 => PointerButton::Middle, // BTN_MIDDLE
+            other => PointerButton::Other(other),
+        }
+    }
+}
+
+/// A key press on a test window
+#[cfg(test)]
+pub(crate) fn make_key_event(key: Key, text: Option<&str>, modifiers: Modifiers) -> KeyEvent {
+    let keycode = KEYCODE_KEYS
+        .iter()
+        .find(|(_, k)| *k == key)
+        .map_or(0, |(code, _)| *code);
+    KeyEvent {
+        target: SurfaceTarget::Window(crate::window::WindowId(1)),
+        key,
+        physical_key: key,
+        keycode,
+        keysym: 0,
+        text: text.map(|s| s.to_string()),
+        modifiers,
+        state: KeyState::Pressed,
+        is_repeat: false,
+    }
+}
+
+/// A pointer event on a test window
+#[cfg(test)]
+pub(crate) fn make_pointer_event(kind: PointerEventKind, x: f64, y: f64) -> PointerEvent {
+    PointerEvent::new(
+        SurfaceTarget::Window(crate::window::WindowId(1)),
+        kind,
+        x,
+        y,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn make_key_event(key: Key, text: Option<&str>, modifiers: Modifiers) -> KeyEvent {
-        KeyEvent {
-            key,
-            text: text.map(|s| s.to_string()),
-            modifiers,
-            state: KeyState::Pressed,
-        }
-    }
+    use crate::window::WindowId;
 
     #[test]
     fn test_key_event_simple_letters() {
@@ -356,6 +1494,25 @@ mod tests {
         assert_eq!(event.to_key_string(), Some("C-c".to_string()));
     }
 
+    #[test]
+    fn test_key_event_meta_and_super() {
+        let mods = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let event = make_key_event(Key::X, Some("x"), mods);
+        assert_eq!(event.to_key_string(), Some("M-x".to_string()));
+
+        let mods = Modifiers {
+            ctrl: true,
+            alt: true,
+            super_: true,
+            ..Default::default()
+        };
+        let event = make_key_event(Key::S, None, mods);
+        assert_eq!(event.to_key_string(), Some("C-M-s-s".to_string()));
+    }
+
     #[test]
     fn test_key_event_text_takes_precedence() {
         // If text is provided, it should be used directly
@@ -426,6 +1583,46 @@ mod tests {
         assert_eq!(event.to_key_string(), None);
     }
 
+    #[test]
+    fn test_key_from_keysym() {
+        assert_eq!(Key::from_keysym(Keysym::q.raw()), Key::Q);
+        assert_eq!(Key::from_keysym(Keysym::Q.raw()), Key::Q);
+        assert_eq!(Key::from_keysym(Keysym::_7.raw()), Key::Num7);
+        assert_eq!(Key::from_keysym(Keysym::F13.raw()), Key::F13);
+        assert_eq!(Key::from_keysym(Keysym::KP_3.raw()), Key::Kp3);
+        assert_eq!(Key::from_keysym(Keysym::KP_Home.raw()), Key::Home);
+        assert_eq!(Key::from_keysym(Keysym::ISO_Left_Tab.raw()), Key::Tab);
+        assert_eq!(Key::from_keysym(Keysym::exclam.raw()), Key::Exclam);
+        assert_eq!(
+            Key::from_keysym(Keysym::XF86_AudioRaiseVolume.raw()),
+            Key::VolumeUp
+        );
+        assert_eq!(Key::from_keysym(Keysym::ISO_Level3_Shift.raw()), Key::AltGr);
+        assert_eq!(Key::from_keysym(0xffffff), Key::Unknown(0xffffff));
+    }
+
+    #[test]
+    fn test_key_from_keysym_non_latin() {
+        // ä on a German layout, я on a Russian one
+        assert_eq!(Key::from_keysym(Keysym::adiaeresis.raw()), Key::Char('ä'));
+        assert_eq!(Key::from_keysym(Keysym::Cyrillic_ya.raw()), Key::Char('я'));
+        assert_eq!(Key::Char('Ä').to_base_char(), Some('ä'));
+        assert_eq!(
+            Key::Char('я').to_string_with_shift(true),
+            Some("я".to_string())
+        );
+    }
+
+    #[test]
+    fn test_key_from_keycode() {
+        assert_eq!(Key::from_keycode(30), Key::A);
+        assert_eq!(Key::from_keycode(44), Key::Z);
+        assert_eq!(Key::from_keycode(86), Key::IntlBackslash);
+        assert_eq!(Key::from_keycode(183), Key::F13);
+        assert_eq!(Key::from_keycode(100), Key::Alt);
+        assert_eq!(Key::from_keycode(9999), Key::Unknown(9999));
+    }
+
     #[test]
     fn test_modifiers_default() {
         let mods = Modifiers::default();
@@ -433,6 +1630,9 @@ mod tests {
         assert!(!mods.ctrl);
         assert!(!mods.alt);
         assert!(!mods.super_);
+        assert!(!mods.alt_gr);
+        assert!(!mods.caps_lock);
+        assert!(!mods.num_lock);
     }
 
     #[test]
@@ -449,15 +1649,209 @@ mod tests {
         assert_ne!(PointerButton::Left, PointerButton::Right);
     }
 
+    #[test]
+    fn test_pointer_button_from_code() {
+        assert_eq!(PointerButton::from_code(272), PointerButton::Left);
+        assert_eq!(PointerButton::from_code(273), PointerButton::Right);
+        assert_eq!(PointerButton::from_code(274), PointerButton::Middle);
+        assert_eq!(PointerButton::from_code(275), PointerButton::Other(275));
+    }
+
     #[test]
     fn test_pointer_event_kind_scroll() {
-        let scroll = PointerEventKind::Scroll { dx: 10, dy: -5 };
+        let scroll = PointerEventKind::Scroll(ScrollEvent {
+            dx: 10.0,
+            dy: -5.0,
+            ..Default::default()
+        });
         match scroll {
-            PointerEventKind::Scroll { dx, dy } => {
-                assert_eq!(dx, 10);
-                assert_eq!(dy, -5);
+            PointerEventKind::Scroll(event) => {
+                assert_eq!(event.dx, 10.0);
+                assert_eq!(event.dy, -5.0);
             }
             _ => panic!("expected Scroll"),
         }
     }
+
+    #[test]
+    fn test_scroll_from_wheel() {
+        let vertical = AxisScroll {
+            absolute: 15.0,
+            discrete: 1,
+            stop: false,
+        };
+        let event = ScrollEvent::from_axis(
+            &AxisScroll::default(),
+            &vertical,
+            Some(wl_pointer::AxisSource::Wheel),
+        )
+        .unwrap();
+        assert_eq!(event.source, ScrollSource::Wheel);
+        assert_eq!(event.steps(), (0, 1));
+        assert_eq!(event.dy, 15.0);
+    }
+
+    #[test]
+    fn test_scroll_from_touchpad() {
+        let stop = AxisScroll {
+            absolute: 0.0,
+            discrete: 0,
+            stop: true,
+        };
+        let event = ScrollEvent::from_axis(
+            &stop,
+            &AxisScroll::default(),
+            Some(wl_pointer::AxisSource::Finger),
+        )
+        .unwrap();
+        assert_eq!(event.source, ScrollSource::Finger);
+        assert!(event.stop_x && !event.stop_y);
+        assert_eq!(event.steps(), (0, 0));
+
+        assert!(
+            ScrollEvent::from_axis(&AxisScroll::default(), &AxisScroll::default(), None).is_none()
+        );
+    }
+
+    #[test]
+    fn test_key_repeat_timing() {
+        let mut repeat = KeyRepeat::default();
+        repeat.set_info(10, 200);
+        let start = Instant::now();
+        repeat.press(
+            &make_key_event(Key::A, Some("a"), Modifiers::default()),
+            start,
+        );
+
+        assert!(repeat.due(start + Duration::from_millis(199)).is_none());
+        let event = repeat.due(start + Duration::from_millis(200)).unwrap();
+        assert!(event.is_repeat);
+        assert_eq!(event.key, Key::A);
+        assert_eq!(repeat.deadline(), Some(start + Duration::from_millis(300)));
+
+        repeat.release(48); // B
+        assert!(repeat.deadline().is_some());
+        repeat.release(30); // A
+        assert!(repeat.deadline().is_none());
+    }
+
+    #[test]
+    fn test_key_repeat_disabled() {
+        let mut repeat = KeyRepeat::default();
+        let start = Instant::now();
+        repeat.press(&make_key_event(Key::A, None, Modifiers::default()), start);
+        repeat.set_info(0, 200);
+        assert!(repeat.deadline().is_none());
+
+        repeat.press(&make_key_event(Key::A, None, Modifiers::default()), start);
+        assert!(repeat.due(start + Duration::from_secs(1)).is_none());
+    }
+
+    #[test]
+    fn test_key_repeat_skips_modifiers() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(
+            &make_key_event(Key::Shift, None, Modifiers::default()),
+            Instant::now(),
+        );
+        assert!(repeat.deadline().is_none());
+    }
+
+    #[test]
+    fn test_touch_routes_by_id() {
+        let mut touch = TouchState::default();
+        let a = SurfaceTarget::Window(WindowId(1));
+        let b = SurfaceTarget::Window(WindowId(2));
+        touch.down(a, 0, 1.0, 2.0);
+        touch.down(b, 1, 5.0, 5.0);
+
+        let motion = touch.motion(0, 3.0, 4.0).unwrap();
+        assert_eq!(motion.target, a);
+        assert_eq!(touch.frame().len(), 2);
+        assert!(touch.frame().is_empty());
+
+        let up = touch.up(0).unwrap();
+        assert_eq!(
+            up.kind,
+            TouchEventKind::Up {
+                id: 0,
+                x: 3.0,
+                y: 4.0
+            }
+        );
+        assert!(touch.up(0).is_none());
+        assert!(touch.motion(7, 0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_touch_cancel() {
+        let mut touch = TouchState::default();
+        let a = SurfaceTarget::Window(WindowId(1));
+        touch.down(a, 3, 0.0, 0.0);
+        touch.down(a, 1, 0.0, 0.0);
+
+        let kinds: Vec<_> = touch.cancel().into_iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TouchEventKind::Cancel { id: 1 },
+                TouchEventKind::Cancel { id: 3 }
+            ]
+        );
+        assert!(touch.is_empty());
+        assert!(touch.frame().is_empty());
+    }
+
+    #[test]
+    fn test_tablet_frame_order() {
+        let mut tool = TabletToolState::default();
+        let a = SurfaceTarget::Window(WindowId(1));
+        tool.proximity_in(Some(a));
+        tool.motion(10.0, 20.0);
+        tool.down();
+        tool.pressure(65535);
+        tool.tilt(30.0, -15.0);
+
+        let kinds: Vec<_> = tool.frame().into_iter().map(|e| e.kind).collect();
+        let axes = TabletAxes {
+            x: 10.0,
+            y: 20.0,
+            pressure: 1.0,
+            tilt_x: 30.0,
+            tilt_y: -15.0,
+            ..Default::default()
+        };
+        let tool_info = TabletTool::default();
+        assert_eq!(
+            kinds,
+            vec![
+                TabletEventKind::ProximityIn { tool: tool_info },
+                TabletEventKind::Motion {
+                    tool: tool_info,
+                    axes
+                },
+                TabletEventKind::Down { tool: tool_info },
+            ]
+        );
+        assert!(tool.frame().is_empty());
+    }
+
+    #[test]
+    fn test_tablet_proximity_out_clears_focus() {
+        let mut tool = TabletToolState::default();
+        tool.proximity_in(Some(SurfaceTarget::Window(WindowId(1))));
+        tool.frame();
+
+        tool.slider(-65535);
+        tool.proximity_out();
+        let events = tool.frame();
+        assert_eq!(events.len(), 2);
+        assert!(
+            matches!(events[0].kind, TabletEventKind::Motion { axes, .. } if axes.slider == -1.0)
+        );
+
+        // Events for a tool that isn't over one of our surfaces are dropped
+        tool.motion(1.0, 1.0);
+        assert!(tool.frame().is_empty());
+    }
 }
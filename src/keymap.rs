@@ -0,0 +1,637 @@
+//! Emacs-style key bindings.
+//!
+//! Bindings are written the way `KeyEvent::to_key_string` prints keys:
+//! `C-x C-s`, `M-<Left>`, `s-RET`. A [`Keymap`] stores them as a tree of
+//! prefixes, and a [`KeyMatcher`] feeds key events through the global keymap
+//! and any active mode keymaps, tracking the pending prefix of a sequence.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use smithay_client_toolkit::seat::keyboard::Keysym;
+
+use crate::input::{Key, KeyEvent, KeyState, named_keys};
+
+/// How long a prefix like `C-x` waits for the rest of its sequence
+const DEFAULT_SEQUENCE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The key part of a stroke
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StrokeKey {
+    /// A printable character with Shift already applied, e.g. `A` or `:`
+    Char(char),
+    /// A key that doesn't type a character, e.g. `<Left>` or `RET`
+    Named(Key),
+}
+
+/// One key press and its modifiers, e.g. `C-x` or `M-<Left>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyStroke {
+    pub key: StrokeKey,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub super_: bool,
+    /// Only set for named keys; a character already includes Shift
+    pub shift: bool,
+}
+
+impl KeyStroke {
+    pub fn new(key: StrokeKey) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            alt: false,
+            super_: false,
+            shift: false,
+        }
+    }
+
+    /// The stroke for a key press, or None for releases and bare modifiers
+    pub fn from_event(event: &KeyEvent) -> Option<Self> {
+        if event.state != KeyState::Pressed || event.key.is_modifier() {
+            return None;
+        }
+        let mods = event.modifiers;
+        let mut ch = Keysym::new(event.keysym)
+            .key_char()
+            .filter(|c| !c.is_control())
+            .or_else(|| key_char(event.key, mods.shift));
+        // Caps lock shouldn't turn C-a into C-A
+        if mods.caps_lock && !mods.shift {
+            ch = ch.map(|c| c.to_lowercase().next().unwrap_or(c));
+        }
+        Some(
+            Self {
+                key: ch.map_or(StrokeKey::Named(event.key), StrokeKey::Char),
+                ctrl: mods.ctrl,
+                alt: mods.alt,
+                super_: mods.super_,
+                shift: mods.shift,
+            }
+            .normalize(),
+        )
+    }
+
+    /// The stroke for the key's physical position, used when the layout
+    /// produced a character no binding can name (e.g. Cyrillic)
+    fn physical(event: &KeyEvent, logical: &Self) -> Option<Self> {
+        match logical.key {
+            StrokeKey::Char(c) if !c.is_ascii() => {}
+            _ => return None,
+        }
+        let key = key_char(event.physical_key, event.modifiers.shift)
+            .map_or(StrokeKey::Named(event.physical_key), StrokeKey::Char);
+        Some(
+            Self {
+                key,
+                shift: event.modifiers.shift,
+                ..*logical
+            }
+            .normalize(),
+        )
+    }
+
+    /// Fold Shift into characters so `S-a`, `A` and a shifted press all agree
+    fn normalize(mut self) -> Self {
+        if let StrokeKey::Char(c) = self.key {
+            if self.shift {
+                self.key = StrokeKey::Char(c.to_uppercase().next().unwrap_or(c));
+            }
+            self.shift = false;
+        }
+        self
+    }
+}
+
+/// The character a key types, if it's a single printable one
+fn key_char(key: Key, shift: bool) -> Option<char> {
+    let text = key.to_string_with_shift(shift)?;
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() => Some(c),
+        _ => None,
+    }
+}
+
+fn parse_key(name: &str) -> Option<StrokeKey> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(StrokeKey::Char(c));
+    }
+
+    let name = name
+        .strip_prefix('<')
+        .and_then(|n| n.strip_suffix('>'))
+        .unwrap_or(name);
+    let key = match name {
+        "RET" | "Return" => Key::Enter,
+        "TAB" => Key::Tab,
+        "ESC" => Key::Escape,
+        "SPC" => Key::Space,
+        "DEL" => Key::Backspace,
+        "prior" => Key::PageUp,
+        "next" => Key::PageDown,
+        _ => named_keys().find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))?,
+    };
+    Some(key_char(key, false).map_or(StrokeKey::Named(key), StrokeKey::Char))
+}
+
+impl FromStr for KeyStroke {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        let mut stroke = KeyStroke::new(StrokeKey::Char(' '));
+        let mut rest = spec;
+        // A lone or trailing `-` is the minus key, as in `C--`
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match rest.as_bytes()[0] {
+                b'C' => stroke.ctrl = true,
+                b'M' => stroke.alt = true,
+                b's' => stroke.super_ = true,
+                b'S' => stroke.shift = true,
+                _ => break,
+            }
+            rest = &rest[2..];
+        }
+        stroke.key =
+            parse_key(rest).ok_or_else(|| format!("unknown key `{}` in `{}`", rest, spec))?;
+        Ok(stroke.normalize())
+    }
+}
+
+impl fmt::Display for KeyStroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (on, prefix) in [
+            (self.ctrl, "C-"),
+            (self.alt, "M-"),
+            (self.super_, "s-"),
+            (self.shift, "S-"),
+        ] {
+            if on {
+                f.write_str(prefix)?;
+            }
+        }
+        match self.key {
+            StrokeKey::Char(' ') => f.write_str("SPC"),
+            StrokeKey::Char(c) => write!(f, "{}", c),
+            StrokeKey::Named(Key::Enter) => f.write_str("RET"),
+            StrokeKey::Named(Key::Tab) => f.write_str("TAB"),
+            StrokeKey::Named(Key::Escape) => f.write_str("ESC"),
+            StrokeKey::Named(key) => write!(f, "<{:?}>", key),
+        }
+    }
+}
+
+/// Parse a space-separated sequence of strokes, e.g. `C-x C-s`
+pub fn parse_key_sequence(spec: &str) -> Result<Vec<KeyStroke>, String> {
+    let strokes: Vec<KeyStroke> = spec
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()?;
+    if strokes.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(strokes)
+}
+
+/// Format strokes the way `parse_key_sequence` reads them
+pub fn format_key_sequence(strokes: &[KeyStroke]) -> String {
+    strokes
+        .iter()
+        .map(KeyStroke::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+enum Binding<A> {
+    Action(A),
+    Prefix(Keymap<A>),
+}
+
+/// Key sequences bound to actions of type `A`
+pub struct Keymap<A> {
+    bindings: HashMap<KeyStroke, Binding<A>>,
+}
+
+impl<A> Default for Keymap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+}
+
+/// The result of looking up a sequence in the keymaps
+enum Found<A> {
+    Action(A),
+    Prefix,
+    None,
+}
+
+impl<A> Keymap<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind `spec` (e.g. `"C-x C-s"`) to `action`, replacing whatever was
+    /// bound to it or to any prefix of it
+    pub fn bind(&mut self, spec: &str, action: A) -> Result<(), String> {
+        let strokes = parse_key_sequence(spec)?;
+        self.insert(&strokes, Binding::Action(action));
+        Ok(())
+    }
+
+    /// Put all of `keymap`'s bindings under the prefix `spec`, e.g. a `C-c`
+    /// keymap for one mode
+    pub fn bind_keymap(&mut self, spec: &str, keymap: Keymap<A>) -> Result<(), String> {
+        let strokes = parse_key_sequence(spec)?;
+        self.insert(&strokes, Binding::Prefix(keymap));
+        Ok(())
+    }
+
+    /// Remove the binding or prefix at `spec`; returns whether there was one
+    pub fn unbind(&mut self, spec: &str) -> Result<bool, String> {
+        let strokes = parse_key_sequence(spec)?;
+        let (last, prefix) = strokes.split_last().unwrap();
+        let mut map = self;
+        for stroke in prefix {
+            match map.bindings.get_mut(stroke) {
+                Some(Binding::Prefix(inner)) => map = inner,
+                _ => return Ok(false),
+            }
+        }
+        Ok(map.bindings.remove(last).is_some())
+    }
+
+    /// The action bound to exactly `strokes`
+    pub fn get(&self, strokes: &[KeyStroke]) -> Option<&A> {
+        let (last, prefix) = strokes.split_last()?;
+        let mut map = self;
+        for stroke in prefix {
+            match map.bindings.get(stroke)? {
+                Binding::Prefix(inner) => map = inner,
+                Binding::Action(_) => return None,
+            }
+        }
+        match map.bindings.get(last)? {
+            Binding::Action(action) => Some(action),
+            Binding::Prefix(_) => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    fn insert(&mut self, strokes: &[KeyStroke], binding: Binding<A>) {
+        let (last, prefix) = strokes.split_last().unwrap();
+        let mut map = self;
+        for stroke in prefix {
+            let entry = map
+                .bindings
+                .entry(*stroke)
+                .or_insert_with(|| Binding::Prefix(Keymap::new()));
+            if let Binding::Action(_) = entry {
+                *entry = Binding::Prefix(Keymap::new());
+            }
+            let Binding::Prefix(inner) = entry else {
+                unreachable!()
+            };
+            map = inner;
+        }
+        map.bindings.insert(*last, binding);
+    }
+
+    fn find(&self, strokes: &[KeyStroke]) -> Found<&A> {
+        let mut map = self;
+        for (i, stroke) in strokes.iter().enumerate() {
+            match map.bindings.get(stroke) {
+                Some(Binding::Prefix(inner)) => map = inner,
+                Some(Binding::Action(action)) if i == strokes.len() - 1 => {
+                    return Found::Action(action);
+                }
+                _ => return Found::None,
+            }
+        }
+        Found::Prefix
+    }
+}
+
+/// What a key event did to the sequence being matched
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyMatch<A> {
+    /// The event completed a bound sequence
+    Action(A),
+    /// The event started or extended a prefix; see `KeyMatcher::pending_hint`
+    Prefix,
+    /// A prefix was pending and the event doesn't continue it. The whole
+    /// sequence, shown in Emacs as "C-x q is undefined", is swallowed.
+    Undefined(Vec<KeyStroke>),
+    /// Nothing is bound to this key; handle the event normally
+    Unbound,
+    /// A release or bare modifier press, which never affects matching
+    Ignored,
+}
+
+/// Matches key events against a global keymap and stacked mode keymaps
+pub struct KeyMatcher<A> {
+    global: Keymap<A>,
+    /// Active mode keymaps; later ones take precedence
+    modes: Vec<(String, Keymap<A>)>,
+    pending: Vec<KeyStroke>,
+    deadline: Option<Instant>,
+    timeout: Option<Duration>,
+}
+
+impl<A: Clone> KeyMatcher<A> {
+    pub fn new(global: Keymap<A>) -> Self {
+        Self {
+            global,
+            modes: Vec::new(),
+            pending: Vec::new(),
+            deadline: None,
+            timeout: Some(DEFAULT_SEQUENCE_TIMEOUT),
+        }
+    }
+
+    /// How long a prefix waits for its next stroke; None waits forever
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn global(&self) -> &Keymap<A> {
+        &self.global
+    }
+
+    pub fn global_mut(&mut self) -> &mut Keymap<A> {
+        &mut self.global
+    }
+
+    /// Activate a mode keymap. It takes precedence over the global keymap
+    /// and modes pushed before it; prefixes are shared between them.
+    pub fn push_mode(&mut self, name: impl Into<String>, keymap: Keymap<A>) {
+        self.modes.push((name.into(), keymap));
+    }
+
+    /// Deactivate the mode called `name`, returning its keymap
+    pub fn remove_mode(&mut self, name: &str) -> Option<Keymap<A>> {
+        let index = self.modes.iter().position(|(n, _)| n == name)?;
+        self.cancel();
+        Some(self.modes.remove(index).1)
+    }
+
+    pub fn has_mode(&self, name: &str) -> bool {
+        self.modes.iter().any(|(n, _)| n == name)
+    }
+
+    /// Match a key event, timing out a stale prefix against the current time
+    pub fn feed(&mut self, event: &KeyEvent) -> KeyMatch<A> {
+        self.feed_at(event, Instant::now())
+    }
+
+    pub fn feed_at(&mut self, event: &KeyEvent, now: Instant) -> KeyMatch<A> {
+        let Some(stroke) = KeyStroke::from_event(event) else {
+            return KeyMatch::Ignored;
+        };
+        self.expire(now);
+
+        let mut sequence = self.pending.clone();
+        sequence.push(stroke);
+        let mut found = self.find(&sequence);
+        if let Found::None = found
+            && let Some(physical) = KeyStroke::physical(event, &stroke)
+        {
+            *sequence.last_mut().unwrap() = physical;
+            found = self.find(&sequence);
+        }
+
+        match found {
+            Found::Action(action) => {
+                self.cancel();
+                KeyMatch::Action(action)
+            }
+            Found::Prefix => {
+                self.pending = sequence;
+                self.deadline = self.timeout.map(|timeout| now + timeout);
+                KeyMatch::Prefix
+            }
+            Found::None if self.pending.is_empty() => KeyMatch::Unbound,
+            Found::None => {
+                let mut undefined = std::mem::take(&mut self.pending);
+                undefined.push(stroke);
+                self.cancel();
+                KeyMatch::Undefined(undefined)
+            }
+        }
+    }
+
+    /// The strokes of the prefix typed so far
+    pub fn pending(&self) -> &[KeyStroke] {
+        &self.pending
+    }
+
+    /// A hint for the pending prefix like `C-x-`, or None if nothing is pending
+    pub fn pending_hint(&self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| format!("{}-", format_key_sequence(&self.pending)))
+    }
+
+    /// When the pending prefix times out, e.g. to clear a hint on time
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Drop the pending prefix if it has timed out; returns whether it did
+    pub fn expire(&mut self, now: Instant) -> bool {
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            self.cancel();
+            return true;
+        }
+        false
+    }
+
+    /// Abandon the pending prefix, e.g. on `C-g` or focus loss
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+        self.deadline = None;
+    }
+
+    fn find(&self, strokes: &[KeyStroke]) -> Found<A> {
+        let mut prefix = false;
+        let keymaps = self.modes.iter().rev().map(|(_, keymap)| keymap);
+        for keymap in keymaps.chain([&self.global]) {
+            match keymap.find(strokes) {
+                Found::Action(action) => return Found::Action(action.clone()),
+                Found::Prefix => prefix = true,
+                Found::None => {}
+            }
+        }
+        if prefix { Found::Prefix } else { Found::None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Modifiers, make_key_event};
+
+    fn press(key: Key, keysym: Keysym, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent {
+            keysym: keysym.raw(),
+            ..make_key_event(key, None, modifiers)
+        }
+    }
+
+    fn ctrl() -> Modifiers {
+        Modifiers {
+            ctrl: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        for spec in [
+            "C-x C-s", "M-<Left>", "s-RET", "C--", "SPC", "C-M-<F5>", "S-TAB", "A",
+        ] {
+            let strokes = parse_key_sequence(spec).unwrap();
+            assert_eq!(format_key_sequence(&strokes), spec);
+        }
+        assert_eq!(parse_key_sequence("s-Return"), parse_key_sequence("s-RET"));
+        assert_eq!(parse_key_sequence("S-a"), parse_key_sequence("A"));
+        assert_eq!(parse_key_sequence("<space>"), parse_key_sequence("SPC"));
+        assert!(parse_key_sequence("C-<Nope>").is_err());
+        assert!(parse_key_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn test_stroke_from_event() {
+        let event = press(Key::X, Keysym::x, ctrl());
+        assert_eq!(KeyStroke::from_event(&event), "C-x".parse().ok());
+
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let event = press(Key::Colon, Keysym::colon, shift);
+        assert_eq!(KeyStroke::from_event(&event), ":".parse().ok());
+        let event = press(Key::Tab, Keysym::ISO_Left_Tab, shift);
+        assert_eq!(KeyStroke::from_event(&event), "S-TAB".parse().ok());
+
+        let caps = Modifiers {
+            ctrl: true,
+            caps_lock: true,
+            ..Default::default()
+        };
+        let event = press(Key::A, Keysym::A, caps);
+        assert_eq!(KeyStroke::from_event(&event), "C-a".parse().ok());
+
+        let event = press(Key::Control, Keysym::Control_L, Modifiers::default());
+        assert_eq!(KeyStroke::from_event(&event), None);
+    }
+
+    #[test]
+    fn test_sequence_matching() {
+        let mut keymap = Keymap::new();
+        keymap.bind("C-x C-s", "save").unwrap();
+        keymap.bind("C-x k", "kill").unwrap();
+        keymap.bind("M-<Left>", "back").unwrap();
+        let mut matcher = KeyMatcher::new(keymap);
+
+        assert_eq!(
+            matcher.feed(&press(Key::X, Keysym::x, ctrl())),
+            KeyMatch::Prefix
+        );
+        assert_eq!(matcher.pending_hint(), Some("C-x-".to_string()));
+        let shift = press(Key::Shift, Keysym::Shift_L, Modifiers::default());
+        assert_eq!(matcher.feed(&shift), KeyMatch::Ignored);
+        assert_eq!(
+            matcher.feed(&press(Key::S, Keysym::s, ctrl())),
+            KeyMatch::Action("save")
+        );
+        assert_eq!(matcher.pending_hint(), None);
+
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let event = press(Key::Left, Keysym::Left, alt);
+        assert_eq!(matcher.feed(&event), KeyMatch::Action("back"));
+
+        matcher.feed(&press(Key::X, Keysym::x, ctrl()));
+        let event = press(Key::Q, Keysym::q, Modifiers::default());
+        assert_eq!(
+            matcher.feed(&event),
+            KeyMatch::Undefined(parse_key_sequence("C-x q").unwrap())
+        );
+        assert_eq!(matcher.feed(&event), KeyMatch::Unbound);
+    }
+
+    #[test]
+    fn test_sequence_timeout() {
+        let mut keymap = Keymap::new();
+        keymap.bind("C-x C-s", 1).unwrap();
+        let mut matcher = KeyMatcher::new(keymap).with_timeout(Some(Duration::from_secs(1)));
+
+        let start = Instant::now();
+        matcher.feed_at(&press(Key::X, Keysym::x, ctrl()), start);
+        assert_eq!(matcher.deadline(), Some(start + Duration::from_secs(1)));
+
+        // The prefix expired, so C-s starts a new sequence
+        let later = start + Duration::from_secs(2);
+        let event = press(Key::S, Keysym::s, ctrl());
+        assert_eq!(matcher.feed_at(&event, later), KeyMatch::Unbound);
+    }
+
+    #[test]
+    fn test_modes() {
+        let mut global = Keymap::new();
+        global.bind("C-c a", "global").unwrap();
+        global.bind("C-c b", "global-b").unwrap();
+        let mut mode = Keymap::new();
+        mode.bind("a", "mode").unwrap();
+
+        let mut matcher = KeyMatcher::new(global);
+        let mut nested = Keymap::new();
+        nested.bind_keymap("C-c", mode).unwrap();
+        matcher.push_mode("edit", nested);
+
+        let c = press(Key::C, Keysym::c, ctrl());
+        let a = press(Key::A, Keysym::a, Modifiers::default());
+        let b = press(Key::B, Keysym::b, Modifiers::default());
+        assert_eq!(matcher.feed(&c), KeyMatch::Prefix);
+        assert_eq!(matcher.feed(&a), KeyMatch::Action("mode"));
+        // Prefixes are shared, so global bindings under C-c still work
+        matcher.feed(&c);
+        assert_eq!(matcher.feed(&b), KeyMatch::Action("global-b"));
+
+        assert!(matcher.remove_mode("edit").is_some());
+        matcher.feed(&c);
+        assert_eq!(matcher.feed(&a), KeyMatch::Action("global"));
+    }
+
+    #[test]
+    fn test_physical_fallback() {
+        let mut keymap = Keymap::new();
+        keymap.bind("C-z", "undo").unwrap();
+        let mut matcher = KeyMatcher::new(keymap);
+
+        // C-я on a Russian layout is the key where Z sits on US keyboards
+        let mut event = press(Key::Char('я'), Keysym::Cyrillic_ya, ctrl());
+        event.physical_key = Key::Z;
+        assert_eq!(matcher.feed(&event), KeyMatch::Action("undo"));
+    }
+
+    #[test]
+    fn test_unbind() {
+        let mut keymap = Keymap::new();
+        keymap.bind("C-x C-s", 1).unwrap();
+        let strokes = parse_key_sequence("C-x C-s").unwrap();
+        assert_eq!(keymap.get(&strokes), Some(&1));
+        assert!(keymap.unbind("C-x C-s").unwrap());
+        assert!(!keymap.unbind("C-x C-s").unwrap());
+        assert_eq!(keymap.get(&strokes), None);
+    }
+}
@@ -0,0 +1,1185 @@
+//! Layout containers: flexbox-style rows and columns, and grids.
+//!
+//! `VStack` and `HStack` are flex layouts whose children keep their natural
+//! size. Every container lays its children out within the `Constraints` it is
+//! given and caches their rects for rendering and hit testing. Containers
+//! pass pointer events on to their children but not keys, which reach the
+//! focused widget through `FocusManager`.
+
+use crate::input::{PointerEvent, PointerEventKind};
+use crate::widget::{Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetId};
+
+/// Spacing around the inside or outside of a widget
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Insets {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl Insets {
+    pub const fn all(value: u32) -> Self {
+        Self {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        }
+    }
+
+    pub const fn symmetric(horizontal: u32, vertical: u32) -> Self {
+        Self {
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+            left: horizontal,
+        }
+    }
+
+    pub fn horizontal(&self) -> u32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> u32 {
+        self.top + self.bottom
+    }
+}
+
+/// The direction a container lays its children out in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Axis {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    fn main(self, size: Size) -> u32 {
+        match self {
+            Axis::Horizontal => size.width,
+            Axis::Vertical => size.height,
+        }
+    }
+
+    fn cross(self, size: Size) -> u32 {
+        match self {
+            Axis::Horizontal => size.height,
+            Axis::Vertical => size.width,
+        }
+    }
+
+    fn size(self, main: u32, cross: u32) -> Size {
+        match self {
+            Axis::Horizontal => Size::new(main, cross),
+            Axis::Vertical => Size::new(cross, main),
+        }
+    }
+
+    fn rect(self, main_pos: i32, cross_pos: i32, main: u32, cross: u32) -> Rect {
+        match self {
+            Axis::Horizontal => Rect::new(main_pos, cross_pos, main, cross),
+            Axis::Vertical => Rect::new(cross_pos, main_pos, cross, main),
+        }
+    }
+
+    /// Constraints as (min_main, max_main, min_cross, max_cross)
+    fn split(self, c: Constraints) -> (u32, u32, u32, u32) {
+        match self {
+            Axis::Horizontal => (c.min_width, c.max_width, c.min_height, c.max_height),
+            Axis::Vertical => (c.min_height, c.max_height, c.min_width, c.max_width),
+        }
+    }
+
+    fn constraints(
+        self,
+        min_main: u32,
+        max_main: u32,
+        min_cross: u32,
+        max_cross: u32,
+    ) -> Constraints {
+        let (min_width, max_width, min_height, max_height) = match self {
+            Axis::Horizontal => (min_main, max_main, min_cross, max_cross),
+            Axis::Vertical => (min_cross, max_cross, min_main, max_main),
+        };
+        Constraints {
+            min_width,
+            max_width,
+            min_height,
+            max_height,
+        }
+    }
+
+    /// The (start, end) insets along the main axis
+    fn main_insets(self, insets: Insets) -> (u32, u32) {
+        match self {
+            Axis::Horizontal => (insets.left, insets.right),
+            Axis::Vertical => (insets.top, insets.bottom),
+        }
+    }
+
+    fn cross_insets(self, insets: Insets) -> (u32, u32) {
+        match self {
+            Axis::Horizontal => (insets.top, insets.bottom),
+            Axis::Vertical => (insets.left, insets.right),
+        }
+    }
+}
+
+/// Where children sit across a container, or within a grid cell
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Fill the available space
+    Stretch,
+}
+
+impl Align {
+    /// Offset of a `size` item placed in `space`
+    fn offset(self, space: u32, size: u32) -> i32 {
+        let free = space.saturating_sub(size) as i32;
+        match self {
+            Align::Start | Align::Stretch => 0,
+            Align::Center => free / 2,
+            Align::End => free,
+        }
+    }
+}
+
+/// How leftover space along a container's main axis is shared out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Equal space between children, none at the edges
+    SpaceBetween,
+    /// Equal space around each child, so half as much at the edges
+    SpaceAround,
+    /// Equal space between children and at the edges
+    SpaceEvenly,
+}
+
+/// Shrink constraints by `insets`, e.g. to get the room inside padding
+fn deflate(c: Constraints, insets: Insets) -> Constraints {
+    let (h, v) = (insets.horizontal(), insets.vertical());
+    Constraints {
+        min_width: c.min_width.saturating_sub(h),
+        max_width: c.max_width.saturating_sub(h),
+        min_height: c.min_height.saturating_sub(v),
+        max_height: c.max_height.saturating_sub(v),
+    }
+}
+
+fn clamp_size(size: Size, c: Constraints) -> Size {
+    Size::new(
+        size.width.clamp(c.min_width, c.max_width.max(c.min_width)),
+        size.height
+            .clamp(c.min_height, c.max_height.max(c.min_height)),
+    )
+}
+
+/// A container's children and the rects they were last laid out into,
+/// relative to the container's origin
+#[derive(Default)]
+pub(crate) struct Children {
+    widgets: Vec<Box<dyn Widget>>,
+    rects: Vec<Rect>,
+}
+
+impl Children {
+    pub(crate) fn push(&mut self, widget: impl Widget + 'static) {
+        self.widgets.push(Box::new(widget));
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.widgets.len()
+    }
+
+    /// Child bounds within `bounds`; children that haven't been laid out are empty
+    fn bounds(&self, bounds: Rect) -> impl Iterator<Item = Rect> + '_ {
+        (0..self.widgets.len()).map(move |i| {
+            let rect = self.rects.get(i).copied().unwrap_or_default();
+            Rect::new(
+                bounds.x + rect.x,
+                bounds.y + rect.y,
+                rect.width,
+                rect.height,
+            )
+        })
+    }
+
+    pub(crate) fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        for (child, child_bounds) in self.widgets.iter().zip(self.bounds(bounds)) {
+            ctx.render_child(child.as_ref(), child_bounds);
+        }
+    }
+
+    pub(crate) fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        let child_bounds: Vec<Rect> = self.bounds(bounds).collect();
+
+        // Everyone sees motion, releases and leaves so hover and press
+        // states can end when the pointer moves off a child
+        if let PointerEventKind::Enter
+        | PointerEventKind::Leave
+        | PointerEventKind::Motion
+        | PointerEventKind::Release(_) = event.kind
+        {
+            let mut handled = false;
+            for (child, child_bounds) in self.widgets.iter_mut().zip(child_bounds) {
+                handled |= child.handle_pointer(event, child_bounds);
+            }
+            return handled;
+        }
+
+        for (child, child_bounds) in self.widgets.iter_mut().zip(child_bounds) {
+            if child_bounds.contains(event.x as i32, event.y as i32)
+                && child.handle_pointer(event, child_bounds)
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn visit(&self, bounds: Rect, f: &mut dyn FnMut(&dyn Widget, Rect)) {
+        for (child, child_bounds) in self.widgets.iter().zip(self.bounds(bounds)) {
+            f(child.as_ref(), child_bounds);
+        }
+    }
+
+    pub(crate) fn visit_mut(&mut self, f: &mut dyn FnMut(&mut dyn Widget)) {
+        for child in &mut self.widgets {
+            f(child.as_mut());
+        }
+    }
+}
+
+/// How a flex child grows, shrinks and aligns
+#[derive(Clone, Copy, Debug)]
+struct FlexParams {
+    grow: f32,
+    shrink: f32,
+    basis: Option<u32>,
+    margin: Insets,
+    min: Size,
+    max: Size,
+    align: Option<Align>,
+}
+
+impl FlexParams {
+    /// Children of stacks keep their natural size
+    const FIXED: Self = Self {
+        grow: 0.0,
+        shrink: 0.0,
+        basis: None,
+        margin: Insets::all(0),
+        min: Size {
+            width: 0,
+            height: 0,
+        },
+        max: Size {
+            width: u32::MAX,
+            height: u32::MAX,
+        },
+        align: None,
+    };
+}
+
+impl Default for FlexParams {
+    fn default() -> Self {
+        Self {
+            shrink: 1.0,
+            ..Self::FIXED
+        }
+    }
+}
+
+/// A `Flex` child with its sizing options
+pub struct FlexItem {
+    widget: Box<dyn Widget>,
+    params: FlexParams,
+}
+
+impl FlexItem {
+    pub fn new(widget: impl Widget + 'static) -> Self {
+        Self {
+            widget: Box::new(widget),
+            params: FlexParams::default(),
+        }
+    }
+
+    /// Share of leftover main-axis space this child takes, relative to its siblings
+    pub fn grow(mut self, grow: f32) -> Self {
+        self.params.grow = grow.max(0.0);
+        self
+    }
+
+    /// How much this child gives up when the children overflow, relative to
+    /// its siblings and weighted by its size. Defaults to 1; 0 never shrinks.
+    pub fn shrink(mut self, shrink: f32) -> Self {
+        self.params.shrink = shrink.max(0.0);
+        self
+    }
+
+    /// Main-axis size to start from instead of the child's natural size
+    pub fn basis(mut self, basis: u32) -> Self {
+        self.params.basis = Some(basis);
+        self
+    }
+
+    pub fn margin(mut self, margin: Insets) -> Self {
+        self.params.margin = margin;
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.params.min = Size::new(width, height);
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.params.max = Size::new(width, height);
+        self
+    }
+
+    /// Cross-axis alignment for this child, overriding the container's
+    pub fn align_self(mut self, align: Align) -> Self {
+        self.params.align = Some(align);
+        self
+    }
+}
+
+/// Options shared by flex containers and stacks
+#[derive(Clone, Copy, Debug, Default)]
+struct FlexStyle {
+    axis: Axis,
+    gap: u32,
+    padding: Insets,
+    justify: Justify,
+    align: Align,
+}
+
+/// Grow or shrink `sizes` by `free` in proportion to `weights`, stopping
+/// each at its `limit`. Space a clamped size can't take goes to the others.
+fn distribute(sizes: &mut [f32], weights: &[f32], limits: &[f32], free: f32) {
+    let mut frozen: Vec<bool> = weights.iter().map(|&w| w <= 0.0).collect();
+    let mut remaining = free;
+    loop {
+        let total: f32 = (0..sizes.len())
+            .filter(|&i| !frozen[i])
+            .map(|i| weights[i])
+            .sum();
+        if total <= 0.0 || remaining.abs() < 0.5 {
+            break;
+        }
+        let mut clamped = false;
+        let mut given = 0.0;
+        for i in 0..sizes.len() {
+            if frozen[i] {
+                continue;
+            }
+            let mut delta = remaining * weights[i] / total;
+            let target = sizes[i] + delta;
+            if (free > 0.0 && target > limits[i]) || (free < 0.0 && target < limits[i]) {
+                delta = limits[i] - sizes[i];
+                frozen[i] = true;
+                clamped = true;
+            }
+            sizes[i] += delta;
+            given += delta;
+        }
+        remaining -= given;
+        if !clamped {
+            break;
+        }
+    }
+}
+
+/// Lay `children` out in a row or column, caching their rects
+fn layout_flex(
+    children: &mut Children,
+    params: &[FlexParams],
+    style: FlexStyle,
+    constraints: Constraints,
+    ctx: &mut LayoutContext,
+) -> Size {
+    let axis = style.axis;
+    let (min_main, max_main, min_cross, max_cross) =
+        axis.split(deflate(constraints, style.padding));
+    let count = children.len();
+    let param = |i: usize| params.get(i).copied().unwrap_or(FlexParams::FIXED);
+    let align = |i: usize| param(i).align.unwrap_or(style.align);
+    let margin_main = |i: usize| {
+        let (start, end) = axis.main_insets(param(i).margin);
+        start + end
+    };
+    let margin_cross = |i: usize| {
+        let (start, end) = axis.cross_insets(param(i).margin);
+        start + end
+    };
+    let limits = |i: usize| {
+        let p = param(i);
+        (
+            axis.main(p.min),
+            axis.main(p.max),
+            axis.cross(p.min),
+            axis.cross(p.max),
+        )
+    };
+    let cross_room = |i: usize| {
+        let (_, _, min, max) = limits(i);
+        (
+            min,
+            max_cross
+                .saturating_sub(margin_cross(i))
+                .clamp(min, max.max(min)),
+        )
+    };
+
+    // Measure each child at its basis or natural size
+    let mut bases = Vec::with_capacity(count);
+    for (i, child) in children.widgets.iter_mut().enumerate() {
+        let (min, max, _, _) = limits(i);
+        let (cross_min, cross_max) = cross_room(i);
+        let child_constraints = match param(i).basis {
+            Some(basis) => {
+                let basis = basis.clamp(min, max.max(min));
+                axis.constraints(basis, basis, cross_min, cross_max)
+            }
+            None => axis.constraints(min, max_main.min(max).max(min), cross_min, cross_max),
+        };
+        let size = child.layout(child_constraints, ctx);
+        let base = param(i).basis.unwrap_or(axis.main(size));
+        bases.push(base.clamp(min, max.max(min)) as f32);
+    }
+
+    // Grow into leftover space or shrink to fit
+    let gaps = style.gap.saturating_mul(count.saturating_sub(1) as u32);
+    let margins: u32 = (0..count).map(margin_main).sum();
+    let used = bases.iter().sum::<f32>() + (gaps + margins) as f32;
+    let grows = (0..count).any(|i| param(i).grow > 0.0);
+    let target = if grows && max_main != u32::MAX {
+        max_main as f32
+    } else {
+        used.clamp(min_main as f32, max_main as f32)
+    };
+    let mut sizes = bases.clone();
+    if target > used {
+        let weights: Vec<f32> = (0..count).map(|i| param(i).grow).collect();
+        let maxes: Vec<f32> = (0..count).map(|i| limits(i).1 as f32).collect();
+        distribute(&mut sizes, &weights, &maxes, target - used);
+    } else if target < used {
+        let weights: Vec<f32> = (0..count).map(|i| param(i).shrink * bases[i]).collect();
+        let mins: Vec<f32> = (0..count).map(|i| limits(i).0 as f32).collect();
+        distribute(&mut sizes, &weights, &mins, target - used);
+    }
+    let sizes: Vec<u32> = sizes.iter().map(|size| size.round() as u32).collect();
+
+    // Lay children out at their final main size; stretched children wait
+    // until the line's cross size is known
+    let mut crosses = vec![0; count];
+    let mut line_cross = 0;
+    for (i, child) in children.widgets.iter_mut().enumerate() {
+        let (cross_min, cross_max) = cross_room(i);
+        let size = child.layout(
+            axis.constraints(sizes[i], sizes[i], cross_min, cross_max),
+            ctx,
+        );
+        crosses[i] = axis.cross(size);
+        line_cross = line_cross.max(crosses[i] + margin_cross(i));
+    }
+    let line_cross = line_cross.clamp(min_cross, max_cross.max(min_cross));
+    for (i, child) in children.widgets.iter_mut().enumerate() {
+        if align(i) == Align::Stretch {
+            let (_, _, min, max) = limits(i);
+            let cross = line_cross
+                .saturating_sub(margin_cross(i))
+                .clamp(min, max.max(min));
+            child.layout(axis.constraints(sizes[i], sizes[i], cross, cross), ctx);
+            crosses[i] = cross;
+        }
+    }
+
+    // Place children along the main axis per `justify`
+    let used: u32 = sizes.iter().sum::<u32>() + gaps + margins;
+    let line_main = used.clamp(min_main, max_main.max(min_main));
+    let free = line_main.saturating_sub(used) as f32;
+    let n = count as f32;
+    let (lead, between) = match style.justify {
+        _ if count == 0 => (0.0, 0.0),
+        Justify::Start => (0.0, 0.0),
+        Justify::Center => (free / 2.0, 0.0),
+        Justify::End => (free, 0.0),
+        Justify::SpaceBetween if count == 1 => (0.0, 0.0),
+        Justify::SpaceBetween => (0.0, free / (n - 1.0)),
+        Justify::SpaceAround => (free / n / 2.0, free / n),
+        Justify::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+    };
+
+    let (pad_main, _) = axis.main_insets(style.padding);
+    let (pad_cross, _) = axis.cross_insets(style.padding);
+    let mut pos = pad_main as f32 + lead;
+    children.rects.clear();
+    for i in 0..count {
+        let (margin_start, margin_end) = axis.main_insets(param(i).margin);
+        let (cross_start, _) = axis.cross_insets(param(i).margin);
+        let cross_space = line_cross.saturating_sub(margin_cross(i));
+        let cross_pos = pad_cross + cross_start + align(i).offset(cross_space, crosses[i]) as u32;
+        pos += margin_start as f32;
+        children
+            .rects
+            .push(axis.rect(pos.round() as i32, cross_pos as i32, sizes[i], crosses[i]));
+        pos += (sizes[i] + margin_end + style.gap) as f32 + between;
+    }
+
+    let padding = style.padding;
+    let (pad_main_total, pad_cross_total) = match axis {
+        Axis::Horizontal => (padding.horizontal(), padding.vertical()),
+        Axis::Vertical => (padding.vertical(), padding.horizontal()),
+    };
+    clamp_size(
+        axis.size(line_main + pad_main_total, line_cross + pad_cross_total),
+        constraints,
+    )
+}
+
+macro_rules! container_builders {
+    () => {
+        /// Space between children
+        pub fn spacing(mut self, spacing: u32) -> Self {
+            self.style.gap = spacing;
+            self
+        }
+
+        /// Space between the container's edges and its children
+        pub fn padding(mut self, padding: Insets) -> Self {
+            self.style.padding = padding;
+            self
+        }
+
+        /// Where children sit across the container
+        pub fn align(mut self, align: Align) -> Self {
+            self.style.align = align;
+            self
+        }
+    };
+}
+
+macro_rules! container_widget {
+    () => {
+        fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+            self.children.render(bounds, ctx);
+        }
+
+        fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+            self.children.handle_pointer(event, bounds)
+        }
+
+        fn visit_children(&self, bounds: Rect, f: &mut dyn FnMut(&dyn Widget, Rect)) {
+            self.children.visit(bounds, f);
+        }
+
+        fn visit_children_mut(&mut self, f: &mut dyn FnMut(&mut dyn Widget)) {
+            self.children.visit_mut(f);
+        }
+    };
+}
+
+/// Stacks children top to bottom at their natural height
+pub struct VStack {
+    id: WidgetId,
+    children: Children,
+    style: FlexStyle,
+}
+
+impl VStack {
+    pub fn new(id: WidgetId) -> Self {
+        Self {
+            id,
+            children: Children::default(),
+            style: FlexStyle {
+                axis: Axis::Vertical,
+                ..Default::default()
+            },
+        }
+    }
+
+    container_builders!();
+
+    pub fn child(mut self, widget: impl Widget + 'static) -> Self {
+        self.children.push(widget);
+        self
+    }
+
+    pub fn add_child(&mut self, widget: impl Widget + 'static) {
+        self.children.push(widget);
+    }
+}
+
+impl Widget for VStack {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        layout_flex(&mut self.children, &[], self.style, constraints, ctx)
+    }
+
+    container_widget!();
+}
+
+/// Stacks children left to right at their natural width
+pub struct HStack {
+    id: WidgetId,
+    children: Children,
+    style: FlexStyle,
+}
+
+impl HStack {
+    pub fn new(id: WidgetId) -> Self {
+        Self {
+            id,
+            children: Children::default(),
+            style: FlexStyle::default(),
+        }
+    }
+
+    container_builders!();
+
+    pub fn child(mut self, widget: impl Widget + 'static) -> Self {
+        self.children.push(widget);
+        self
+    }
+
+    pub fn add_child(&mut self, widget: impl Widget + 'static) {
+        self.children.push(widget);
+    }
+}
+
+impl Widget for HStack {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        layout_flex(&mut self.children, &[], self.style, constraints, ctx)
+    }
+
+    container_widget!();
+}
+
+/// A flexbox-style row or column whose children can grow, shrink and align
+pub struct Flex {
+    id: WidgetId,
+    children: Children,
+    params: Vec<FlexParams>,
+    style: FlexStyle,
+}
+
+impl Flex {
+    pub fn new(id: WidgetId, axis: Axis) -> Self {
+        Self {
+            id,
+            children: Children::default(),
+            params: Vec::new(),
+            style: FlexStyle {
+                axis,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn row(id: WidgetId) -> Self {
+        Self::new(id, Axis::Horizontal)
+    }
+
+    pub fn column(id: WidgetId) -> Self {
+        Self::new(id, Axis::Vertical)
+    }
+
+    container_builders!();
+
+    /// How leftover main-axis space is shared out when no child grows
+    pub fn justify(mut self, justify: Justify) -> Self {
+        self.style.justify = justify;
+        self
+    }
+
+    /// Add a child that keeps its natural size but may shrink
+    pub fn child(self, widget: impl Widget + 'static) -> Self {
+        self.item(FlexItem::new(widget))
+    }
+
+    pub fn item(mut self, item: FlexItem) -> Self {
+        self.add_item(item);
+        self
+    }
+
+    pub fn add_item(&mut self, item: FlexItem) {
+        self.children.widgets.push(item.widget);
+        self.params.push(item.params);
+    }
+}
+
+impl Widget for Flex {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        layout_flex(
+            &mut self.children,
+            &self.params,
+            self.style,
+            constraints,
+            ctx,
+        )
+    }
+
+    container_widget!();
+}
+
+/// How a grid row or column is sized
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GridTrack {
+    /// As large as the largest child in it
+    #[default]
+    Auto,
+    Fixed(u32),
+    /// A share of the space left after the other tracks, relative to the
+    /// other fractions. Acts like `Auto` when the grid is unbounded.
+    Fraction(f32),
+}
+
+/// Where a grid child sits: its first row and column and how many it spans
+#[derive(Clone, Copy, Debug)]
+struct GridCell {
+    row: usize,
+    column: usize,
+    row_span: usize,
+    column_span: usize,
+}
+
+/// Children placed in rows and columns, optionally spanning several
+pub struct Grid {
+    id: WidgetId,
+    children: Children,
+    cells: Vec<GridCell>,
+    rows: Vec<GridTrack>,
+    columns: Vec<GridTrack>,
+    row_gap: u32,
+    column_gap: u32,
+    padding: Insets,
+    align: Align,
+}
+
+impl Grid {
+    pub fn new(id: WidgetId) -> Self {
+        Self {
+            id,
+            children: Children::default(),
+            cells: Vec::new(),
+            rows: Vec::new(),
+            columns: Vec::new(),
+            row_gap: 0,
+            column_gap: 0,
+            padding: Insets::default(),
+            align: Align::Stretch,
+        }
+    }
+
+    /// Row sizing; rows beyond these are `Auto`
+    pub fn rows(mut self, rows: Vec<GridTrack>) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    /// Column sizing; columns beyond these are `Auto`
+    pub fn columns(mut self, columns: Vec<GridTrack>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn gap(mut self, gap: u32) -> Self {
+        self.row_gap = gap;
+        self.column_gap = gap;
+        self
+    }
+
+    pub fn row_gap(mut self, gap: u32) -> Self {
+        self.row_gap = gap;
+        self
+    }
+
+    pub fn column_gap(mut self, gap: u32) -> Self {
+        self.column_gap = gap;
+        self
+    }
+
+    pub fn padding(mut self, padding: Insets) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Where children sit within their cells; they fill them by default
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn cell(self, row: usize, column: usize, widget: impl Widget + 'static) -> Self {
+        self.cell_span(row, column, 1, 1, widget)
+    }
+
+    pub fn cell_span(
+        mut self,
+        row: usize,
+        column: usize,
+        row_span: usize,
+        column_span: usize,
+        widget: impl Widget + 'static,
+    ) -> Self {
+        self.add_cell(row, column, row_span, column_span, widget);
+        self
+    }
+
+    pub fn add_cell(
+        &mut self,
+        row: usize,
+        column: usize,
+        row_span: usize,
+        column_span: usize,
+        widget: impl Widget + 'static,
+    ) {
+        self.children.push(widget);
+        self.cells.push(GridCell {
+            row,
+            column,
+            row_span: row_span.max(1),
+            column_span: column_span.max(1),
+        });
+    }
+}
+
+/// Size grid tracks to fit `items`, given as (first track, span, size)
+fn resolve_tracks(
+    tracks: &[GridTrack],
+    count: usize,
+    items: &[(usize, usize, u32)],
+    gap: u32,
+    available: u32,
+) -> Vec<u32> {
+    let track = |i: usize| tracks.get(i).copied().unwrap_or_default();
+    let bounded = available != u32::MAX;
+    let is_auto = |i: usize| match track(i) {
+        GridTrack::Auto => true,
+        GridTrack::Fixed(_) => false,
+        GridTrack::Fraction(_) => !bounded,
+    };
+
+    let mut sizes: Vec<u32> = (0..count)
+        .map(|i| match track(i) {
+            GridTrack::Fixed(size) => size,
+            _ => 0,
+        })
+        .collect();
+    for &(start, span, size) in items {
+        if span == 1 && is_auto(start) {
+            sizes[start] = sizes[start].max(size);
+        }
+    }
+
+    // Spanning children widen the auto tracks they cover, narrowest spans first
+    let mut spanning: Vec<_> = items.iter().filter(|(_, span, _)| *span > 1).collect();
+    spanning.sort_by_key(|(_, span, _)| *span);
+    for &&(start, span, size) in &spanning {
+        let range = start..start + span;
+        let current: u32 = sizes[range.clone()].iter().sum::<u32>() + gap * (span as u32 - 1);
+        let autos: Vec<usize> = range.filter(|&i| is_auto(i)).collect();
+        if size > current && !autos.is_empty() {
+            let extra = size - current;
+            let share = extra / autos.len() as u32;
+            for (n, &i) in autos.iter().enumerate() {
+                // The first tracks take the remainder
+                sizes[i] += share + u32::from((n as u32) < extra % autos.len() as u32);
+            }
+        }
+    }
+
+    if bounded {
+        let fractions: f32 = (0..count)
+            .filter_map(|i| match track(i) {
+                GridTrack::Fraction(fr) => Some(fr),
+                _ => None,
+            })
+            .sum();
+        if fractions > 0.0 {
+            let gaps = gap.saturating_mul(count.saturating_sub(1) as u32);
+            let used: u32 = sizes.iter().sum::<u32>() + gaps;
+            let left = available.saturating_sub(used) as f32;
+            for (i, size) in sizes.iter_mut().enumerate() {
+                if let GridTrack::Fraction(fr) = track(i) {
+                    *size = (left * fr / fractions) as u32;
+                }
+            }
+        }
+    }
+    sizes
+}
+
+/// Offset of each track, starting at `start`
+fn track_offsets(sizes: &[u32], start: u32, gap: u32) -> Vec<u32> {
+    let mut pos = start;
+    sizes
+        .iter()
+        .map(|size| {
+            let offset = pos;
+            pos += size + gap;
+            offset
+        })
+        .collect()
+}
+
+/// Total size of `span` tracks from `start`, including the gaps between them
+fn span_size(sizes: &[u32], start: usize, span: usize, gap: u32) -> u32 {
+    sizes[start..start + span].iter().sum::<u32>() + gap * (span as u32 - 1)
+}
+
+impl Widget for Grid {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let inner = deflate(constraints, self.padding);
+        let row_count = self
+            .cells
+            .iter()
+            .map(|cell| cell.row + cell.row_span)
+            .max()
+            .unwrap_or(0)
+            .max(self.rows.len());
+        let column_count = self
+            .cells
+            .iter()
+            .map(|cell| cell.column + cell.column_span)
+            .max()
+            .unwrap_or(0)
+            .max(self.columns.len());
+
+        let loose = Constraints::loose(inner.max_width, inner.max_height);
+        let natural: Vec<Size> = self
+            .children
+            .widgets
+            .iter_mut()
+            .map(|child| child.layout(loose, ctx))
+            .collect();
+
+        let column_items: Vec<_> = self
+            .cells
+            .iter()
+            .zip(&natural)
+            .map(|(cell, size)| (cell.column, cell.column_span, size.width))
+            .collect();
+        let row_items: Vec<_> = self
+            .cells
+            .iter()
+            .zip(&natural)
+            .map(|(cell, size)| (cell.row, cell.row_span, size.height))
+            .collect();
+        let widths = resolve_tracks(
+            &self.columns,
+            column_count,
+            &column_items,
+            self.column_gap,
+            inner.max_width,
+        );
+        let heights = resolve_tracks(
+            &self.rows,
+            row_count,
+            &row_items,
+            self.row_gap,
+            inner.max_height,
+        );
+
+        let xs = track_offsets(&widths, self.padding.left, self.column_gap);
+        let ys = track_offsets(&heights, self.padding.top, self.row_gap);
+        self.children.rects.clear();
+        for (cell, child) in self.cells.iter().zip(self.children.widgets.iter_mut()) {
+            let width = span_size(&widths, cell.column, cell.column_span, self.column_gap);
+            let height = span_size(&heights, cell.row, cell.row_span, self.row_gap);
+            let size = if self.align == Align::Stretch {
+                child.layout(Constraints::tight(width, height), ctx);
+                Size::new(width, height)
+            } else {
+                let size = child.layout(Constraints::loose(width, height), ctx);
+                Size::new(size.width.min(width), size.height.min(height))
+            };
+            self.children.rects.push(Rect::new(
+                xs[cell.column] as i32 + self.align.offset(width, size.width),
+                ys[cell.row] as i32 + self.align.offset(height, size.height),
+                size.width,
+                size.height,
+            ));
+        }
+
+        let gaps = |count: usize, gap: u32| gap * count.saturating_sub(1) as u32;
+        let width = widths.iter().sum::<u32>() + gaps(column_count, self.column_gap);
+        let height = heights.iter().sum::<u32>() + gaps(row_count, self.row_gap);
+        clamp_size(
+            Size::new(
+                width + self.padding.horizontal(),
+                height + self.padding.vertical(),
+            ),
+            constraints,
+        )
+    }
+
+    container_widget!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::TextRenderer;
+
+    /// A widget that wants a fixed size, within its constraints
+    struct Block(Size);
+
+    impl Widget for Block {
+        fn id(&self) -> WidgetId {
+            WidgetId(0)
+        }
+
+        fn layout(&mut self, constraints: Constraints, _ctx: &mut LayoutContext) -> Size {
+            clamp_size(self.0, constraints)
+        }
+
+        fn render(&self, _bounds: Rect, _ctx: &mut RenderContext) {}
+    }
+
+    fn block(width: u32, height: u32) -> Block {
+        Block(Size::new(width, height))
+    }
+
+    fn layout(widget: &mut dyn Widget, constraints: Constraints) -> (Size, Vec<Rect>) {
+        let mut text = TextRenderer::new();
+        let size = widget.layout(constraints, &mut LayoutContext { text: &mut text });
+        let mut rects = Vec::new();
+        widget.visit_children(Rect::default(), &mut |_, rect| rects.push(rect));
+        (size, rects)
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect::new(x, y, width, height)
+    }
+
+    #[test]
+    fn test_stacks() {
+        let mut stack = HStack::new(WidgetId(1))
+            .spacing(5)
+            .padding(Insets::all(2))
+            .align(Align::Center)
+            .child(block(10, 10))
+            .child(block(20, 30));
+        let (size, rects) = layout(&mut stack, Constraints::loose(200, 200));
+        assert_eq!(size, Size::new(39, 34));
+        assert_eq!(rects, [rect(2, 12, 10, 10), rect(17, 2, 20, 30)]);
+
+        let mut stack = VStack::new(WidgetId(1))
+            .align(Align::Stretch)
+            .child(block(10, 10))
+            .child(block(20, 30));
+        let (size, rects) = layout(&mut stack, Constraints::loose(200, 200));
+        assert_eq!(size, Size::new(20, 40));
+        assert_eq!(rects, [rect(0, 0, 20, 10), rect(0, 10, 20, 30)]);
+    }
+
+    #[test]
+    fn test_flex_grow_and_shrink() {
+        let mut row = Flex::row(WidgetId(1))
+            .item(FlexItem::new(block(10, 10)).grow(1.0))
+            .item(FlexItem::new(block(10, 10)).grow(3.0).max_size(50, 100))
+            .child(block(20, 10));
+        let (size, rects) = layout(&mut row, Constraints::loose(200, 50));
+        // 160 spare: the second child stops at 50, so the first takes the rest
+        assert_eq!(size, Size::new(200, 10));
+        assert_eq!(
+            rects,
+            [
+                rect(0, 0, 130, 10),
+                rect(130, 0, 50, 10),
+                rect(180, 0, 20, 10)
+            ]
+        );
+
+        let mut row = Flex::row(WidgetId(1))
+            .child(block(100, 10))
+            .item(FlexItem::new(block(100, 10)).shrink(0.0))
+            .child(block(50, 10));
+        let (size, rects) = layout(&mut row, Constraints::loose(190, 50));
+        // 60 too wide, shrunk in proportion to size among shrinkable children
+        assert_eq!(size, Size::new(190, 10));
+        assert_eq!(
+            rects,
+            [
+                rect(0, 0, 60, 10),
+                rect(60, 0, 100, 10),
+                rect(160, 0, 30, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flex_justify_and_margins() {
+        let mut column = Flex::column(WidgetId(1))
+            .justify(Justify::SpaceBetween)
+            .align(Align::End)
+            .padding(Insets::symmetric(4, 0))
+            .item(FlexItem::new(block(10, 10)).margin(Insets::all(1)))
+            .child(block(20, 10))
+            .item(FlexItem::new(block(10, 10)).align_self(Align::Stretch));
+        let (size, rects) = layout(&mut column, Constraints::tight(40, 100));
+        assert_eq!(size, Size::new(40, 100));
+        assert_eq!(
+            rects,
+            [
+                rect(25, 1, 10, 10),
+                rect(16, 46, 20, 10),
+                rect(4, 90, 32, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut grid = Grid::new(WidgetId(1))
+            .columns(vec![
+                GridTrack::Fixed(30),
+                GridTrack::Auto,
+                GridTrack::Fraction(1.0),
+            ])
+            .gap(2)
+            .cell(0, 0, block(10, 10))
+            .cell(0, 1, block(20, 10))
+            .cell_span(1, 0, 1, 2, block(70, 15))
+            .cell_span(0, 2, 2, 1, block(5, 5));
+        let (size, rects) = layout(&mut grid, Constraints::loose(120, 100));
+        // The span widens the auto column to 70 - 30 - 2 = 38
+        assert_eq!(size, Size::new(120, 27));
+        assert_eq!(
+            rects,
+            [
+                rect(0, 0, 30, 10),
+                rect(32, 0, 38, 10),
+                rect(0, 12, 70, 15),
+                rect(72, 0, 48, 27),
+            ]
+        );
+    }
+}
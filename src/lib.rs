@@ -5,32 +5,74 @@
 
 mod app;
 mod attached_surface;
+mod clipboard;
+mod controls;
+mod cursor;
+mod dnd;
+mod focus;
 mod gpu;
+mod ime;
 mod input;
+mod keymap;
+mod layout;
+mod list;
 mod render;
+mod scale;
+mod scroll;
 mod split;
+mod table;
 mod text;
+mod text_edit;
+mod text_input;
+mod tree;
 mod widget;
 mod window;
 
-pub use app::{App, DropEvent};
+pub use app::{App, AppHandler, DropEvent};
 pub use attached_surface::{
     Anchor as AttachedAnchor, AttachedSurface, AttachedSurfaceHandler, AttachedSurfaceId,
     AttachedSurfaceManager,
 };
+pub use clipboard::{
+    ClipboardData, ClipboardEvent, ClipboardReadId, MIME_IMAGE_PNG, MIME_TEXT_PLAIN,
+    MIME_TEXT_UTF8, MIME_UTF8_STRING, Selection, TEXT_MIME_TYPES,
+};
+pub use controls::{Button, Checkbox, RadioGroup, Toggle};
+pub use cursor::CursorShape;
+pub use dnd::{
+    DragAction, DragEvent, DragIcon, DropData, MIME_URI_LIST, files_to_uri_list, parse_uri_list,
+};
+pub use focus::FocusManager;
 #[cfg(feature = "gpu")]
 pub use gpu::GpuRenderTarget;
 pub use gpu::{Renderer, RendererBackend};
+pub use ime::{ImeEvent, ImePurpose};
 pub use input::{
-    Key, KeyEvent, KeyState, Modifiers, PointerButton, PointerEvent, PointerEventKind,
+    GestureEvent, GestureEventKind, Key, KeyEvent, KeyState, Modifiers, PointerButton,
+    PointerEvent, PointerEventKind, ScrollEvent, ScrollSource, TabletAxes, TabletEvent,
+    TabletEventKind, TabletTool, TabletToolCapabilities, TabletToolType, TouchEvent,
+    TouchEventKind,
 };
+pub use keymap::{
+    KeyMatch, KeyMatcher, KeyStroke, Keymap, StrokeKey, format_key_sequence, parse_key_sequence,
+};
+pub use layout::{Align, Axis, Flex, FlexItem, Grid, GridTrack, HStack, Insets, Justify, VStack};
+pub use list::{ListRow, ListView, SelectionMode};
 pub use render::{Canvas, Rgba};
+pub use scale::SurfaceScale;
+pub use scroll::ScrollView;
 pub use split::{LeafId, SplitDirection, SplitTree};
+pub use table::{Column, Table, TableCell};
 pub use text::{HAlign, TextRenderer, VAlign};
-pub use widget::{Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetId};
+pub use text_input::{TextArea, TextInput};
+pub use tree::{TreeModel, TreeRow, TreeView};
+pub use widget::{
+    Constraints, Label, LayoutContext, Rect, RenderContext, Size, SortOrder, Widget, WidgetEvent,
+    WidgetEvents, WidgetId, is_animating, with_widget_mut,
+};
 pub use window::{
-    Overlay, OverlayId, Popup, PopupAnchor, PopupConfig, PopupGravity, PopupId, Subsurface,
-    SubsurfaceId, Window, WindowId, WindowManager,
+    FrameState, Overlay, OverlayId, Popup, PopupAnchor, PopupConfig, PopupGravity, PopupId,
+    Subsurface, SubsurfaceId, SurfaceTarget, Window, WindowId, WindowManager,
 };
 
 // Re-export key dependencies for users
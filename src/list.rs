@@ -0,0 +1,941 @@
+//! `ListView`: a scrolling list whose rows are drawn on demand.
+//!
+//! Rows aren't widgets. The list knows only how many there are and how tall
+//! each is, and asks a callback to draw the rows in view, so a hundred
+//! thousand rows cost no more to lay out and render than a screenful.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::controls::TEXT;
+use crate::input::{
+    Key, KeyEvent, KeyState, Modifiers, PointerButton, PointerEvent, PointerEventKind,
+};
+use crate::render::Rgba;
+use crate::scroll::{Scroller, viewport_size};
+use crate::text::{FONT_SIZE, HAlign, VAlign};
+use crate::widget::{
+    Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetEvent, WidgetEvents,
+    WidgetId,
+};
+
+pub(crate) const DEFAULT_ROW_HEIGHT: u32 = 24;
+/// Preferred width when the layout doesn't limit it
+pub(crate) const DEFAULT_WIDTH: u32 = 200;
+/// Space left of a row's label
+pub(crate) const LABEL_INSET: u32 = 8;
+const SELECTED: Rgba = Rgba::new(80, 150, 255, 90);
+const HOVERED: Rgba = Rgba::new(255, 255, 255, 18);
+const CURRENT: Rgba = Rgba::new(80, 150, 255, 220);
+/// A pause this long starts a new type-ahead search
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// How many rows can be selected at once
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    #[default]
+    Single,
+    /// Ctrl toggles rows and Shift selects ranges, by keyboard or pointer
+    Multiple,
+}
+
+/// A row for a row renderer to draw
+#[derive(Clone, Copy, Debug)]
+pub struct ListRow {
+    pub index: usize,
+    pub bounds: Rect,
+    pub selected: bool,
+    /// The keyboard cursor is on this row
+    pub current: bool,
+    pub hovered: bool,
+}
+
+type RowHeight = Box<dyn Fn(usize) -> u32>;
+type RowLabel = Box<dyn Fn(usize) -> String>;
+type RowRenderer = Box<dyn Fn(&ListRow, &mut RenderContext)>;
+type SelectCallback = Box<dyn FnMut(&[usize])>;
+
+/// Row heights: the same for every row, or per row with cached positions
+pub(crate) enum RowHeights {
+    Fixed(u32),
+    Variable {
+        height: RowHeight,
+        /// The top of each row, then the total height
+        starts: Vec<u32>,
+        stale: bool,
+    },
+}
+
+impl RowHeights {
+    pub(crate) fn variable(height: impl Fn(usize) -> u32 + 'static) -> Self {
+        Self::Variable {
+            height: Box::new(height),
+            starts: Vec::new(),
+            stale: true,
+        }
+    }
+
+    /// Measure `len` rows again if their heights may have changed
+    pub(crate) fn update(&mut self, len: usize) {
+        if let Self::Variable {
+            height,
+            starts,
+            stale,
+        } = self
+            && (*stale || starts.len() != len + 1)
+        {
+            starts.clear();
+            starts.push(0);
+            let mut y = 0u32;
+            for index in 0..len {
+                y = y.saturating_add(height(index));
+                starts.push(y);
+            }
+            *stale = false;
+        }
+    }
+
+    pub(crate) fn invalidate(&mut self) {
+        if let Self::Variable { stale, .. } = self {
+            *stale = true;
+        }
+    }
+
+    /// The top of row `index`; the total height for the row count
+    pub(crate) fn start(&self, index: usize) -> u32 {
+        match self {
+            Self::Fixed(height) => u32::try_from(index)
+                .unwrap_or(u32::MAX)
+                .saturating_mul(*height),
+            Self::Variable { starts, .. } => {
+                starts.get(index).or(starts.last()).copied().unwrap_or(0)
+            }
+        }
+    }
+
+    pub(crate) fn height(&self, index: usize) -> u32 {
+        match self {
+            Self::Fixed(height) => *height,
+            Self::Variable { .. } => self.start(index + 1) - self.start(index),
+        }
+    }
+
+    /// The row at content position `y`, clamped to the rows there are
+    pub(crate) fn index_at(&self, y: u32, len: usize) -> usize {
+        let index = match self {
+            Self::Fixed(height) => (y / (*height).max(1)) as usize,
+            Self::Variable { starts, .. } => starts.partition_point(|&s| s <= y).saturating_sub(1),
+        };
+        index.min(len.saturating_sub(1))
+    }
+
+    /// Rows overlapping `height` pixels from content position `top`
+    pub(crate) fn visible(&self, top: u32, height: u32, len: usize) -> Range<usize> {
+        if len == 0 || height == 0 {
+            return 0..0;
+        }
+        let first = self.index_at(top, len);
+        let last = self.index_at(top.saturating_add(height - 1), len);
+        first..last + 1
+    }
+
+    /// Where an arrow, PageUp/PageDown, Home or End press moves the cursor
+    /// from `current`, paging by `page` pixels
+    pub(crate) fn navigate(
+        &self,
+        key: Key,
+        current: Option<usize>,
+        page: u32,
+        len: usize,
+    ) -> Option<usize> {
+        let last = len.checked_sub(1)?;
+        Some(match (key, current) {
+            (Key::Home, _) => 0,
+            (Key::End, _) => last,
+            (Key::Up | Key::Down | Key::PageUp | Key::PageDown, None) => 0,
+            (Key::Up, Some(i)) => i.saturating_sub(1),
+            (Key::Down, Some(i)) => (i + 1).min(last),
+            (Key::PageUp, Some(i)) => self
+                .index_at(self.start(i).saturating_sub(page), len)
+                .min(i.saturating_sub(1)),
+            (Key::PageDown, Some(i)) => self
+                .index_at(self.start(i).saturating_add(page), len)
+                .max(i + 1)
+                .min(last),
+            _ => return None,
+        })
+    }
+}
+
+/// How picking a row changes the selection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Pick {
+    /// Select just this row
+    Replace,
+    /// Select from the anchor to this row
+    Extend,
+    /// Flip this row, keeping the rest
+    Toggle,
+    /// Move the cursor, leaving the selection alone
+    Move,
+}
+
+impl Pick {
+    /// Keyboard movement: Shift extends, Ctrl moves only the cursor
+    pub(crate) fn for_key(mods: Modifiers) -> Self {
+        if mods.shift {
+            Self::Extend
+        } else if mods.ctrl {
+            Self::Move
+        } else {
+            Self::Replace
+        }
+    }
+
+    /// Clicks: Shift extends, Ctrl toggles
+    pub(crate) fn for_click(mods: Modifiers) -> Self {
+        if mods.shift {
+            Self::Extend
+        } else if mods.ctrl {
+            Self::Toggle
+        } else {
+            Self::Replace
+        }
+    }
+}
+
+/// Selected rows, the keyboard cursor, and the anchor of range selections
+#[derive(Default)]
+pub(crate) struct RowSelection {
+    mode: SelectionMode,
+    selected: BTreeSet<usize>,
+    current: Option<usize>,
+    anchor: Option<usize>,
+}
+
+impl RowSelection {
+    pub(crate) fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: SelectionMode) {
+        self.mode = mode;
+    }
+
+    /// Follow rows that moved, dropping those `f` maps to None
+    pub(crate) fn remap(&mut self, f: impl Fn(usize) -> Option<usize>) {
+        self.selected = self.selected.iter().filter_map(|&i| f(i)).collect();
+        self.current = self.current.and_then(&f);
+        self.anchor = self.anchor.and_then(&f);
+    }
+
+    /// Move the cursor to `index`, changing the selection as `pick` says.
+    /// Returns whether the selection changed.
+    pub(crate) fn pick(&mut self, index: usize, pick: Pick) -> bool {
+        // With one selected row, the selection follows the cursor
+        let pick = match self.mode {
+            SelectionMode::Single => Pick::Replace,
+            SelectionMode::Multiple => pick,
+        };
+        let before = self.selected.len();
+        let changed = match pick {
+            Pick::Replace => {
+                let changed = before != 1 || !self.selected.contains(&index);
+                self.selected.clear();
+                self.selected.insert(index);
+                self.anchor = Some(index);
+                changed
+            }
+            Pick::Extend => {
+                let anchor = *self.anchor.get_or_insert(index);
+                let range = anchor.min(index)..=anchor.max(index);
+                let changed = before != range.clone().count()
+                    || !range.clone().all(|i| self.selected.contains(&i));
+                self.selected = range.collect();
+                changed
+            }
+            Pick::Toggle => {
+                if !self.selected.remove(&index) {
+                    self.selected.insert(index);
+                }
+                self.anchor = Some(index);
+                true
+            }
+            Pick::Move => false,
+        };
+        self.current = Some(index);
+        changed
+    }
+
+    /// Select all `len` rows, if more than one may be selected
+    pub(crate) fn select_all(&mut self, len: usize) -> bool {
+        if self.mode == SelectionMode::Single || self.selected.len() == len {
+            return false;
+        }
+        self.selected = (0..len).collect();
+        true
+    }
+
+    /// Replace the selection without moving the cursor
+    pub(crate) fn set(&mut self, rows: impl IntoIterator<Item = usize>, len: usize) {
+        self.selected = rows.into_iter().filter(|&i| i < len).collect();
+        if self.mode == SelectionMode::Single
+            && let Some(&first) = self.selected.iter().next()
+        {
+            self.selected = BTreeSet::from([first]);
+        }
+        self.anchor = self.selected.iter().next().copied();
+    }
+
+    /// Forget rows past a new row count
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.selected.retain(|&i| i < len);
+        self.current = self.current.filter(|&i| i < len);
+        self.anchor = self.anchor.filter(|&i| i < len);
+    }
+
+    pub(crate) fn is_selected(&self, index: usize) -> bool {
+        self.selected.contains(&index)
+    }
+
+    pub(crate) fn selected(&self) -> Vec<usize> {
+        self.selected.iter().copied().collect()
+    }
+
+    pub(crate) fn current(&self) -> Option<usize> {
+        self.current
+    }
+}
+
+/// Jumping to a row by typing the start of its label
+#[derive(Default)]
+pub(crate) struct TypeAhead {
+    typed: String,
+    last: Option<Instant>,
+}
+
+impl TypeAhead {
+    pub(crate) fn reset(&mut self) {
+        self.typed.clear();
+        self.last = None;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.typed.is_empty()
+    }
+
+    /// Add `text` to what's been typed and find the first row from `current`
+    /// whose label starts with it, wrapping around
+    pub(crate) fn search(
+        &mut self,
+        text: &str,
+        now: Instant,
+        current: Option<usize>,
+        len: usize,
+        label: &dyn Fn(usize) -> String,
+    ) -> Option<usize> {
+        if self
+            .last
+            .is_some_and(|last| now.duration_since(last) > TYPE_AHEAD_TIMEOUT)
+        {
+            self.typed.clear();
+        }
+        self.last = Some(now);
+        self.typed.push_str(&text.to_lowercase());
+        if len == 0 {
+            return None;
+        }
+
+        // Typing one letter over and over steps through the rows starting
+        // with it; otherwise the current row still matches a longer prefix
+        let first = self.typed.chars().next()?;
+        let repeated = self.typed.chars().all(|c| c == first);
+        let prefix = if repeated {
+            first.to_string()
+        } else {
+            self.typed.clone()
+        };
+        let start = match current {
+            Some(current) if repeated => current + 1,
+            Some(current) => current,
+            None => 0,
+        };
+        (0..len)
+            .map(|i| (start + i) % len)
+            .find(|&i| label(i).to_lowercase().starts_with(&prefix))
+    }
+}
+
+/// Spots a second click on the same row soon after the first
+#[derive(Default)]
+pub(crate) struct DoubleClick {
+    last: Option<(usize, Instant)>,
+}
+
+impl DoubleClick {
+    /// Record a click on row `index`; returns whether it completes a double-click
+    pub(crate) fn click(&mut self, index: usize, now: Instant) -> bool {
+        let double = self.last.is_some_and(|(last, time)| {
+            last == index && now.duration_since(time) <= DOUBLE_CLICK_TIME
+        });
+        self.last = (!double).then_some((index, now));
+        double
+    }
+}
+
+/// Fill a row's background to show it's selected or hovered
+pub(crate) fn fill_row(ctx: &mut RenderContext, bounds: Rect, selected: bool, hovered: bool) {
+    let color = if selected {
+        SELECTED
+    } else if hovered {
+        HOVERED
+    } else {
+        return;
+    };
+    ctx.canvas.fill_rect(
+        bounds.x as f32,
+        bounds.y as f32,
+        bounds.width as f32,
+        bounds.height as f32,
+        color.to_color(),
+    );
+}
+
+/// Outline the row with the keyboard cursor
+pub(crate) fn outline_row(ctx: &mut RenderContext, bounds: Rect) {
+    ctx.canvas.stroke_rect(
+        bounds.x as f32,
+        bounds.y as f32,
+        bounds.width as f32,
+        bounds.height as f32,
+        1.0,
+        CURRENT.to_color(),
+    );
+}
+
+/// A virtualised list of `len` rows with keyboard and pointer selection,
+/// type-ahead search and scrolling
+pub struct ListView {
+    id: WidgetId,
+    len: usize,
+    heights: RowHeights,
+    selection: RowSelection,
+    type_ahead: TypeAhead,
+    scroller: Scroller,
+    label: Option<RowLabel>,
+    render_row: Option<RowRenderer>,
+    hovered: Option<usize>,
+    focused: bool,
+    /// A row to scroll into view once the list has been laid out
+    reveal: Option<usize>,
+    double_click: DoubleClick,
+    on_select: Option<SelectCallback>,
+    on_activate: Option<Box<dyn FnMut(usize)>>,
+    events: Option<WidgetEvents>,
+}
+
+impl ListView {
+    pub fn new(id: WidgetId, len: usize) -> Self {
+        Self {
+            id,
+            len,
+            heights: RowHeights::Fixed(DEFAULT_ROW_HEIGHT),
+            selection: RowSelection::default(),
+            type_ahead: TypeAhead::default(),
+            scroller: Scroller::default(),
+            label: None,
+            render_row: None,
+            hovered: None,
+            focused: false,
+            reveal: None,
+            double_click: DoubleClick::default(),
+            on_select: None,
+            on_activate: None,
+            events: None,
+        }
+    }
+
+    /// Give every row the same height
+    pub fn row_height(mut self, height: u32) -> Self {
+        self.heights = RowHeights::Fixed(height);
+        self
+    }
+
+    /// Give each row its own height. Heights are measured once for all
+    /// rows; call `invalidate_row_heights` when they change.
+    pub fn row_heights(mut self, height: impl Fn(usize) -> u32 + 'static) -> Self {
+        self.heights = RowHeights::variable(height);
+        self
+    }
+
+    /// Text for each row, drawn unless there's a row renderer, and matched
+    /// by type-ahead search
+    pub fn label(mut self, label: impl Fn(usize) -> String + 'static) -> Self {
+        self.label = Some(Box::new(label));
+        self
+    }
+
+    /// Draw each visible row. The list has already filled the background
+    /// of selected and hovered rows.
+    pub fn render_row(mut self, render: impl Fn(&ListRow, &mut RenderContext) + 'static) -> Self {
+        self.render_row = Some(Box::new(render));
+        self
+    }
+
+    pub fn selection_mode(mut self, mode: SelectionMode) -> Self {
+        self.selection.set_mode(mode);
+        self
+    }
+
+    /// Called with the selected rows, in order, when the user changes them
+    pub fn on_select(mut self, f: impl FnMut(&[usize]) + 'static) -> Self {
+        self.on_select = Some(Box::new(f));
+        self
+    }
+
+    /// Called when a row is double-clicked or Enter is pressed on it
+    pub fn on_activate(mut self, f: impl FnMut(usize) + 'static) -> Self {
+        self.on_activate = Some(Box::new(f));
+        self
+    }
+
+    /// Also report selection changes and activations on `events`
+    pub fn events(mut self, events: &WidgetEvents) -> Self {
+        self.events = Some(events.clone());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Change the row count, dropping selected rows that no longer exist
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.heights.invalidate();
+        self.selection.truncate(len);
+        self.hovered = None;
+    }
+
+    /// Measure variable row heights again at the next layout
+    pub fn invalidate_row_heights(&mut self) {
+        self.heights.invalidate();
+    }
+
+    pub fn selected(&self) -> Vec<usize> {
+        self.selection.selected()
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selection.is_selected(index)
+    }
+
+    /// Replace the selection without reporting a change
+    pub fn set_selected(&mut self, rows: impl IntoIterator<Item = usize>) {
+        self.selection.set(rows, self.len);
+    }
+
+    /// The row with the keyboard cursor
+    pub fn current(&self) -> Option<usize> {
+        self.selection.current()
+    }
+
+    /// Put the keyboard cursor on `index` and scroll to it, leaving the
+    /// selection alone
+    pub fn set_current(&mut self, index: usize) {
+        if index < self.len {
+            self.selection.current = Some(index);
+            self.scroll_to_index(index);
+        }
+    }
+
+    /// Scroll as little as possible to show row `index`
+    pub fn scroll_to_index(&mut self, index: usize) {
+        if index >= self.len {
+            return;
+        }
+        self.reveal = Some(index);
+        if self.scroller.viewport().height > 0 {
+            self.reveal_row();
+        }
+    }
+
+    pub fn scroll_offset(&self) -> f32 {
+        self.scroller.offset().1
+    }
+
+    /// The rows in view as of the last layout
+    pub fn visible_rows(&self) -> Range<usize> {
+        let top = self.scroller.offset().1.round() as u32;
+        let height = self.scroller.viewport().height;
+        self.heights.visible(top, height, self.len)
+    }
+
+    fn reveal_row(&mut self) {
+        if let Some(index) = self.reveal.take()
+            && index < self.len
+        {
+            let top = self.heights.start(index);
+            let rect = Rect::new(0, top as i32, 0, self.heights.height(index));
+            self.scroller.scroll_to_rect(rect);
+        }
+    }
+
+    /// The row under surface position `y`
+    fn row_at(&self, bounds: Rect, y: f64) -> Option<usize> {
+        let y = y - bounds.y as f64 + self.scroller.offset().1 as f64;
+        let total = self.heights.start(self.len);
+        (self.len > 0 && y >= 0.0 && y < total as f64)
+            .then(|| self.heights.index_at(y as u32, self.len))
+    }
+
+    fn pick(&mut self, index: usize, pick: Pick) {
+        if self.selection.pick(index, pick) {
+            self.selection_changed();
+        }
+        self.scroll_to_index(index);
+    }
+
+    fn selection_changed(&mut self) {
+        let selected = self.selection.selected();
+        if let Some(f) = &mut self.on_select {
+            f(&selected);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::SelectionChanged {
+                id: self.id,
+                selected,
+            });
+        }
+    }
+
+    fn activate(&mut self, index: usize) {
+        if let Some(f) = &mut self.on_activate {
+            f(index);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::ItemActivated { id: self.id, index });
+        }
+    }
+
+    /// Jump to the row matching the text typed so far
+    fn type_ahead(&mut self, text: &str, now: Instant) -> bool {
+        let Some(label) = &self.label else {
+            return false;
+        };
+        let current = self.selection.current();
+        if let Some(index) = self
+            .type_ahead
+            .search(text, now, current, self.len, label.as_ref())
+        {
+            self.pick(index, Pick::Replace);
+        }
+        true
+    }
+}
+
+impl Widget for ListView {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, _ctx: &mut LayoutContext) -> Size {
+        self.heights.update(self.len);
+        let total = self.heights.start(self.len);
+        let viewport = viewport_size(Size::new(DEFAULT_WIDTH, total), constraints);
+        self.scroller
+            .set_sizes(Size::new(viewport.width, total), viewport);
+        self.reveal_row();
+        self.scroller.advance(Instant::now());
+        viewport
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        ctx.canvas.push_clip(
+            bounds.x as f32,
+            bounds.y as f32,
+            bounds.width as f32,
+            bounds.height as f32,
+        );
+        let offset = self.scroller.offset().1;
+        for index in self.visible_rows() {
+            let top = (self.heights.start(index) as f32 - offset).round() as i32;
+            let row = ListRow {
+                index,
+                bounds: Rect::new(
+                    bounds.x,
+                    bounds.y + top,
+                    bounds.width,
+                    self.heights.height(index),
+                ),
+                selected: self.selection.is_selected(index),
+                current: self.selection.current() == Some(index),
+                hovered: self.hovered == Some(index),
+            };
+            fill_row(ctx, row.bounds, row.selected, row.hovered);
+            if let Some(render) = &self.render_row {
+                render(&row, ctx);
+            } else if let Some(label) = &self.label {
+                let text_bounds = Rect::new(
+                    row.bounds.x + LABEL_INSET as i32,
+                    row.bounds.y,
+                    row.bounds.width.saturating_sub(LABEL_INSET),
+                    row.bounds.height,
+                );
+                ctx.text.draw_text_in_rect(
+                    ctx.canvas,
+                    &label(index),
+                    text_bounds,
+                    FONT_SIZE,
+                    TEXT,
+                    HAlign::Left,
+                    VAlign::Center,
+                );
+            }
+            if row.current && self.focused {
+                outline_row(ctx, row.bounds);
+            }
+        }
+        self.scroller.render_scrollbars(bounds, ctx);
+        ctx.canvas.pop_clip();
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        let mods = event.modifiers;
+        if event.state != KeyState::Pressed || self.len == 0 || mods.alt || mods.super_ {
+            return false;
+        }
+        let current = self.selection.current();
+        let page = self.scroller.viewport().height;
+        if let Some(index) = self.heights.navigate(event.key, current, page, self.len) {
+            self.type_ahead.reset();
+            self.pick(index, Pick::for_key(mods));
+            return true;
+        }
+
+        match event.key {
+            Key::Enter | Key::KpEnter if !mods.ctrl => {
+                if let Some(index) = current {
+                    self.activate(index);
+                }
+                true
+            }
+            Key::A if mods.ctrl && self.selection.mode() == SelectionMode::Multiple => {
+                if self.selection.select_all(self.len) {
+                    self.selection_changed();
+                }
+                true
+            }
+            // Space selects, unless it's part of a type-ahead search
+            Key::Space if mods.ctrl || self.type_ahead.is_empty() => {
+                let index = current.unwrap_or(0);
+                let pick = if mods.ctrl {
+                    Pick::Toggle
+                } else {
+                    Pick::Replace
+                };
+                self.pick(index, pick);
+                true
+            }
+            _ if mods.ctrl => false,
+            _ => match &event.text {
+                Some(text) if !text.is_empty() && !text.chars().any(char::is_control) => {
+                    self.type_ahead(text, Instant::now())
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        if self.scroller.handle_scrollbar(event, bounds) {
+            return true;
+        }
+        let inside = bounds.contains(event.x as i32, event.y as i32);
+        match event.kind {
+            PointerEventKind::Press(PointerButton::Left) if inside => {
+                let Some(index) = self.row_at(bounds, event.y) else {
+                    return false;
+                };
+                let pick = Pick::for_click(event.modifiers);
+                self.type_ahead.reset();
+                self.pick(index, pick);
+                if self.double_click.click(index, Instant::now()) && pick == Pick::Replace {
+                    self.activate(index);
+                }
+                true
+            }
+            PointerEventKind::Scroll(scroll) if inside => {
+                self.scroller.scroll(&scroll, Instant::now())
+            }
+            PointerEventKind::Enter | PointerEventKind::Motion | PointerEventKind::Leave => {
+                let hovered = match event.kind {
+                    PointerEventKind::Leave => None,
+                    _ if inside => self.row_at(bounds, event.y),
+                    _ => None,
+                };
+                let changed = hovered != self.hovered;
+                self.hovered = hovered;
+                self.scroller.update_hover(event, bounds) || changed
+            }
+            _ => false,
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn focus_changed(&mut self, focused: bool) {
+        self.focused = focused;
+        self.type_ahead.reset();
+    }
+
+    fn is_animating(&self) -> bool {
+        self.scroller.is_gliding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{make_key_event, make_pointer_event};
+    use crate::render::Canvas;
+    use crate::text::TextRenderer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn layout(list: &mut ListView, width: u32, height: u32) {
+        let mut text = TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        list.layout(Constraints::loose(width, height), &mut ctx);
+    }
+
+    fn modifiers(shift: bool, ctrl: bool) -> Modifiers {
+        Modifiers {
+            shift,
+            ctrl,
+            ..Default::default()
+        }
+    }
+
+    fn key(key: Key, text: Option<&str>, shift: bool, ctrl: bool) -> KeyEvent {
+        make_key_event(key, text, modifiers(shift, ctrl))
+    }
+
+    fn click(y: f64, shift: bool, ctrl: bool) -> PointerEvent {
+        let kind = PointerEventKind::Press(PointerButton::Left);
+        make_pointer_event(kind, 10.0, y).with_modifiers(modifiers(shift, ctrl))
+    }
+
+    #[test]
+    fn test_only_visible_rows_render() {
+        let drawn = Rc::new(RefCell::new(Vec::new()));
+        let log = drawn.clone();
+        let mut list = ListView::new(WidgetId(1), 100_000)
+            .render_row(move |row, _ctx| log.borrow_mut().push(row.index));
+        layout(&mut list, 200, 240);
+        assert_eq!(list.visible_rows(), 0..10);
+
+        list.scroll_to_index(50_000);
+        assert_eq!(list.visible_rows(), 49_991..50_001);
+
+        let mut data = vec![0u8; 200 * 240 * 4];
+        let mut canvas = Canvas::new(&mut data, 200, 240);
+        let mut text = TextRenderer::new();
+        let mut ctx = RenderContext::new(&mut canvas, &mut text);
+        list.render(Rect::new(0, 0, 200, 240), &mut ctx);
+        assert_eq!(*drawn.borrow(), (49_991..50_001).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_variable_row_heights() {
+        // Rows alternate 10 and 30 pixels tall
+        let mut list =
+            ListView::new(WidgetId(1), 1000).row_heights(|i| if i % 2 == 0 { 10 } else { 30 });
+        layout(&mut list, 200, 100);
+        assert_eq!(list.scroller.content().height, 20_000);
+        assert_eq!(list.visible_rows(), 0..6);
+
+        list.scroll_to_index(101);
+        // Row 101 spans 2010..2040, so the view ends at its bottom
+        assert_eq!(list.scroll_offset(), 1940.0);
+        assert_eq!(list.visible_rows(), 97..102);
+
+        list.set_len(10);
+        layout(&mut list, 200, 100);
+        assert_eq!(list.scroller.content().height, 200);
+        assert_eq!(list.scroll_offset(), 100.0);
+    }
+
+    #[test]
+    fn test_keyboard_and_pointer_selection() {
+        let events = WidgetEvents::new();
+        let mut list = ListView::new(WidgetId(1), 50)
+            .selection_mode(SelectionMode::Multiple)
+            .events(&events);
+        layout(&mut list, 200, 240);
+
+        list.handle_key(&key(Key::Down, None, false, false));
+        list.handle_key(&key(Key::Down, None, true, false));
+        list.handle_key(&key(Key::Down, None, true, false));
+        assert_eq!(list.selected(), [0, 1, 2]);
+        list.handle_key(&key(Key::Down, None, false, true));
+        list.handle_key(&key(Key::Down, None, false, true));
+        list.handle_key(&key(Key::Space, Some(" "), false, true));
+        assert_eq!(list.selected(), [0, 1, 2, 4]);
+        assert_eq!(list.current(), Some(4));
+
+        list.handle_key(&key(Key::End, None, true, false));
+        assert_eq!(list.selected(), (4..50).collect::<Vec<_>>());
+        assert!(list.scroll_offset() > 0.0);
+
+        let bounds = Rect::new(0, 0, 200, 240);
+        list.scroll_to_index(0);
+        list.handle_pointer(&click(30.0, false, false), bounds);
+        list.handle_pointer(&click(80.0, true, false), bounds);
+        list.handle_pointer(&click(130.0, false, true), bounds);
+        assert_eq!(list.selected(), [1, 2, 3, 5]);
+        list.handle_pointer(&click(130.0, false, true), bounds);
+        assert_eq!(list.selected(), [1, 2, 3]);
+
+        list.handle_key(&key(Key::Enter, None, false, false));
+        let events = events.poll();
+        assert_eq!(
+            events.last(),
+            Some(&WidgetEvent::ItemActivated {
+                id: WidgetId(1),
+                index: 5
+            })
+        );
+        assert!(events.contains(&WidgetEvent::SelectionChanged {
+            id: WidgetId(1),
+            selected: vec![0, 1, 2, 4]
+        }));
+    }
+
+    #[test]
+    fn test_type_ahead() {
+        let fruit = ["Apple", "Banana", "Blueberry", "Cherry", "avocado"];
+        let mut list = ListView::new(WidgetId(1), fruit.len()).label(move |i| fruit[i].into());
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        list.type_ahead("b", at(0));
+        assert_eq!(list.current(), Some(1));
+        list.type_ahead("l", at(100));
+        assert_eq!(list.current(), Some(2));
+        // After a pause a new search starts, and repeating a letter cycles
+        list.type_ahead("a", at(2000));
+        assert_eq!(list.current(), Some(4));
+        list.type_ahead("a", at(2100));
+        assert_eq!(list.current(), Some(0));
+        assert_eq!(list.selected(), [0]);
+    }
+}
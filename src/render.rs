@@ -1,28 +1,134 @@
-use tiny_skia::{Color, Paint, Pixmap, PixmapMut, Rect, Transform};
+use tiny_skia::{
+    Color, FillRule, FilterQuality, LineCap, Mask, Paint, Path, PathBuilder, Pattern, Pixmap,
+    PixmapMut, Rect, SpreadMode, Stroke, Transform,
+};
 
+/// One entry of the clip stack
+struct Clip {
+    /// Physical pixels as (x0, y0, x1, y1), already intersected with the clip below
+    bounds: (i32, i32, i32, i32),
+    /// Coverage mask for anti-aliased paths, built the first time one is
+    /// drawn under this clip. Rectangles and images are clipped directly.
+    mask: Option<Mask>,
+}
+
+/// A drawing target backed by a surface buffer.
+///
+/// Drawing methods take logical coordinates and are scaled to the buffer's
+/// physical resolution, so the same drawing code stays sharp on HiDPI outputs.
+/// `data`, `data_mut` and `set_pixel` work on raw physical pixels.
+///
+/// Drawing is limited to the innermost rectangle pushed with `push_clip`.
 pub struct Canvas<'a> {
     data: &'a mut [u8],
     width: u32,
     height: u32,
+    scale: f32,
+    clips: Vec<Clip>,
 }
 
 impl<'a> Canvas<'a> {
     pub fn new(data: &'a mut [u8], width: u32, height: u32) -> Self {
+        Self::with_scale(data, width, height, 1.0)
+    }
+
+    /// Create a canvas over a `width` x `height` pixel buffer rendered at `scale`
+    pub fn with_scale(data: &'a mut [u8], width: u32, height: u32, scale: f32) -> Self {
         Self {
             data,
             width,
             height,
+            scale: if scale > 0.0 { scale } else { 1.0 },
+            clips: Vec::new(),
         }
     }
 
+    /// Logical width
     pub fn width(&self) -> u32 {
-        self.width
+        (self.width as f32 / self.scale).round() as u32
     }
 
+    /// Logical height
     pub fn height(&self) -> u32 {
+        (self.height as f32 / self.scale).round() as u32
+    }
+
+    /// Buffer width in pixels
+    pub fn physical_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Buffer height in pixels
+    pub fn physical_height(&self) -> u32 {
         self.height
     }
 
+    /// Ratio of physical pixels to logical units
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::from_scale(self.scale, self.scale)
+    }
+
+    /// Limit drawing to the logical rectangle (x, y, w, h), within any clip
+    /// already in effect, until the matching `pop_clip`
+    pub fn push_clip(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let (x0, y0, x1, y1) = self.clip_bounds();
+        let bounds = (
+            ((x * self.scale).round() as i32).max(x0),
+            ((y * self.scale).round() as i32).max(y0),
+            (((x + w) * self.scale).round() as i32).min(x1),
+            (((y + h) * self.scale).round() as i32).min(y1),
+        );
+        self.clips.push(Clip { bounds, mask: None });
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    /// The drawable area in physical pixels as (x0, y0, x1, y1), exclusive at
+    /// the far edges
+    pub fn clip_bounds(&self) -> (i32, i32, i32, i32) {
+        self.clips.last().map(|clip| clip.bounds).unwrap_or((
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+        ))
+    }
+
+    /// Intersect a logical rectangle with the clip, in physical pixels
+    fn clip_rect(&self, rect: Rect) -> Option<Rect> {
+        let rect = rect.transform(self.transform())?;
+        let (x0, y0, x1, y1) = self.clip_bounds();
+        rect.intersect(&Rect::from_ltrb(
+            x0 as f32, y0 as f32, x1 as f32, y1 as f32,
+        )?)
+    }
+
+    /// Build the mask for the innermost clip, if there is one and it has none yet
+    fn prepare_mask(&mut self) {
+        let Some(clip) = self.clips.last_mut() else {
+            return;
+        };
+        if clip.mask.is_some() {
+            return;
+        }
+        let (x0, y0, x1, y1) = clip.bounds;
+        // Left empty, so nothing is drawable, if the clip is empty
+        clip.mask = Mask::new(self.width, self.height).map(|mut mask| {
+            if let Some(rect) = Rect::from_ltrb(x0 as f32, y0 as f32, x1 as f32, y1 as f32) {
+                let path = PathBuilder::from_rect(rect);
+                mask.fill_path(&path, FillRule::Winding, false, Transform::identity());
+            }
+            mask
+        });
+    }
+
+    /// Fill the whole canvas, ignoring the clip
     pub fn clear(&mut self, color: Color) {
         let Some(mut pixmap) = PixmapMut::from_bytes(self.data, self.width, self.height) else {
             return;
@@ -31,13 +137,11 @@ impl<'a> Canvas<'a> {
     }
 
     pub fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
-        let Some(mut pixmap) = PixmapMut::from_bytes(self.data, self.width, self.height) else {
+        let Some(rect) = Rect::from_xywh(x, y, w, h).and_then(|rect| self.clip_rect(rect)) else {
             return;
         };
-
-        let rect = match Rect::from_xywh(x, y, w, h) {
-            Some(r) => r,
-            None => return,
+        let Some(mut pixmap) = PixmapMut::from_bytes(self.data, self.width, self.height) else {
+            return;
         };
 
         let mut paint = Paint::default();
@@ -47,19 +151,124 @@ impl<'a> Canvas<'a> {
         pixmap.fill_rect(rect, &paint, Transform::identity(), None);
     }
 
-    pub fn draw_image(&mut self, x: i32, y: i32, image: &Pixmap) {
+    /// Outline a rectangle with a `line_width` border drawn inside its bounds
+    pub fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, line_width: f32, color: Color) {
+        let line = line_width.min(w / 2.0).min(h / 2.0);
+        self.fill_rect(x, y, w, line, color);
+        self.fill_rect(x, y + h - line, w, line, color);
+        self.fill_rect(x, y + line, line, h - line * 2.0, color);
+        self.fill_rect(x + w - line, y + line, line, h - line * 2.0, color);
+    }
+
+    /// Fill a rectangle with corners rounded to `radius`
+    pub fn fill_rounded_rect(&mut self, x: f32, y: f32, w: f32, h: f32, radius: f32, color: Color) {
+        let r = radius.min(w / 2.0).min(h / 2.0).max(0.0);
+        let mut pb = PathBuilder::new();
+        pb.move_to(x + r, y);
+        pb.line_to(x + w - r, y);
+        pb.quad_to(x + w, y, x + w, y + r);
+        pb.line_to(x + w, y + h - r);
+        pb.quad_to(x + w, y + h, x + w - r, y + h);
+        pb.line_to(x + r, y + h);
+        pb.quad_to(x, y + h, x, y + h - r);
+        pb.line_to(x, y + r);
+        pb.quad_to(x, y, x + r, y);
+        pb.close();
+        if let Some(path) = pb.finish() {
+            self.fill_path(&path, color);
+        }
+    }
+
+    pub fn fill_circle(&mut self, cx: f32, cy: f32, radius: f32, color: Color) {
+        if let Some(path) = PathBuilder::from_circle(cx, cy, radius) {
+            self.fill_path(&path, color);
+        }
+    }
+
+    /// Fill the polygon through `points`, e.g. a triangle for a disclosure arrow
+    pub fn fill_polygon(&mut self, points: &[(f32, f32)], color: Color) {
+        let Some((&(x, y), rest)) = points.split_first() else {
+            return;
+        };
+        let mut pb = PathBuilder::new();
+        pb.move_to(x, y);
+        for &(x, y) in rest {
+            pb.line_to(x, y);
+        }
+        pb.close();
+        if let Some(path) = pb.finish() {
+            self.fill_path(&path, color);
+        }
+    }
+
+    pub fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32, color: Color) {
+        self.prepare_mask();
+        let transform = self.transform();
+        let Some(mut pixmap) = PixmapMut::from_bytes(self.data, self.width, self.height) else {
+            return;
+        };
+        let mut pb = PathBuilder::new();
+        pb.move_to(x0, y0);
+        pb.line_to(x1, y1);
+        let Some(path) = pb.finish() else {
+            return;
+        };
+
+        let mut paint = Paint::default();
+        paint.set_color(color);
+        paint.anti_alias = true;
+        let stroke = Stroke {
+            width,
+            line_cap: LineCap::Round,
+            ..Default::default()
+        };
+        let mask = self.clips.last().and_then(|clip| clip.mask.as_ref());
+        pixmap.stroke_path(&path, &paint, &stroke, transform, mask);
+    }
+
+    fn fill_path(&mut self, path: &Path, color: Color) {
+        self.prepare_mask();
+        let transform = self.transform();
         let Some(mut pixmap) = PixmapMut::from_bytes(self.data, self.width, self.height) else {
             return;
         };
 
-        pixmap.draw_pixmap(
-            x,
-            y,
-            image.as_ref(),
-            &tiny_skia::PixmapPaint::default(),
-            Transform::identity(),
-            None,
+        let mut paint = Paint::default();
+        paint.set_color(color);
+        paint.anti_alias = true;
+
+        let mask = self.clips.last().and_then(|clip| clip.mask.as_ref());
+        pixmap.fill_path(path, &paint, FillRule::Winding, transform, mask);
+    }
+
+    pub fn draw_image(&mut self, x: i32, y: i32, image: &Pixmap) {
+        let bounds = Rect::from_xywh(
+            x as f32,
+            y as f32,
+            image.width() as f32,
+            image.height() as f32,
         );
+        let Some(rect) = bounds.and_then(|bounds| self.clip_rect(bounds)) else {
+            return;
+        };
+        // What `draw_pixmap` does, but filling only the visible part of the image
+        let placement = Transform::from_translate(x as f32, y as f32).post_concat(self.transform());
+        let Some(mut pixmap) = PixmapMut::from_bytes(self.data, self.width, self.height) else {
+            return;
+        };
+
+        let paint = Paint {
+            shader: Pattern::new(
+                image.as_ref(),
+                SpreadMode::Pad,
+                FilterQuality::Nearest,
+                1.0,
+                placement,
+            ),
+            anti_alias: false,
+            ..Default::default()
+        };
+        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
     }
 
     pub fn draw_rgba(&mut self, x: i32, y: i32, width: u32, height: u32, rgba_data: &[u8]) {
@@ -109,8 +318,11 @@ impl<'a> Canvas<'a> {
         self.data
     }
 
+    /// Set a single physical pixel (not affected by the canvas scale)
     pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
-        if x >= self.width || y >= self.height {
+        let (x0, y0, x1, y1) = self.clip_bounds();
+        let (px, py) = (x as i32, y as i32);
+        if px < x0 || py < y0 || px >= x1 || py >= y1 {
             return;
         }
         let offset = ((y * self.width + x) * 4) as usize;
@@ -165,3 +377,63 @@ impl Rgba {
     pub const BLUE: Self = Self::rgb(0, 0, 255);
     pub const TRANSPARENT: Self = Self::new(0, 0, 0, 0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alpha(canvas: &Canvas, x: u32, y: u32) -> u8 {
+        canvas.data()[((y * canvas.physical_width() + x) * 4 + 3) as usize]
+    }
+
+    #[test]
+    fn test_rects_and_images_clip_without_a_mask() {
+        let mut data = vec![0; 20 * 20 * 4];
+        let mut canvas = Canvas::with_scale(&mut data, 20, 20, 2.0);
+        canvas.push_clip(2.0, 2.0, 4.0, 4.0);
+        canvas.fill_rect(0.0, 0.0, 10.0, 10.0, Color::BLACK);
+        let mut image = Pixmap::new(10, 10).unwrap();
+        image.fill(Color::WHITE);
+        canvas.draw_image(0, 0, &image);
+        assert!(canvas.clips[0].mask.is_none());
+
+        assert_eq!(alpha(&canvas, 3, 3), 0);
+        assert_eq!(alpha(&canvas, 4, 4), 255);
+        assert_eq!(alpha(&canvas, 11, 11), 255);
+        assert_eq!(alpha(&canvas, 12, 12), 0);
+    }
+
+    #[test]
+    fn test_paths_clip_with_a_mask_per_clip() {
+        let mut data = vec![0; 20 * 20 * 4];
+        let mut canvas = Canvas::new(&mut data, 20, 20);
+        canvas.push_clip(0.0, 0.0, 10.0, 20.0);
+        canvas.push_clip(5.0, 0.0, 10.0, 20.0);
+        canvas.fill_circle(10.0, 10.0, 8.0, Color::BLACK);
+        assert!(canvas.clips[0].mask.is_none());
+        assert!(canvas.clips[1].mask.is_some());
+        assert_eq!(alpha(&canvas, 4, 10), 0);
+        assert_eq!(alpha(&canvas, 6, 10), 255);
+        assert_eq!(alpha(&canvas, 10, 10), 0);
+
+        canvas.pop_clip();
+        canvas.pop_clip();
+        assert_eq!(canvas.clip_bounds(), (0, 0, 20, 20));
+    }
+
+    #[test]
+    fn test_nested_clips_intersect() {
+        let mut data = vec![0; 20 * 20 * 4];
+        let mut canvas = Canvas::new(&mut data, 20, 20);
+        canvas.push_clip(0.0, 0.0, 10.0, 10.0);
+        canvas.push_clip(5.0, 5.0, 10.0, 10.0);
+        canvas.fill_rect(0.0, 0.0, 20.0, 20.0, Color::BLACK);
+        canvas.pop_clip();
+        canvas.pop_clip();
+
+        assert_eq!(alpha(&canvas, 4, 4), 0);
+        assert_eq!(alpha(&canvas, 5, 5), 255);
+        assert_eq!(alpha(&canvas, 9, 9), 255);
+        assert_eq!(alpha(&canvas, 10, 10), 0);
+    }
+}
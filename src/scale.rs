@@ -0,0 +1,165 @@
+//! Per-surface HiDPI scale tracking.
+//!
+//! Surfaces use `wp_fractional_scale_v1` + `wp_viewporter` when the compositor
+//! offers both, and fall back to the integer `wl_surface.set_buffer_scale` otherwise.
+
+use smithay_client_toolkit::reexports::client::{Proxy, protocol::wl_surface::WlSurface};
+use wayland_protocols::wp::{
+    fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1,
+    viewporter::client::wp_viewport::WpViewport,
+};
+
+/// Fractional scales are sent as numerators over this denominator
+const FRACTIONAL_SCALE_DENOMINATOR: f64 = 120.0;
+
+pub struct SurfaceScale {
+    buffer_scale: i32,
+    fractional: Option<f64>,
+    fractional_scale: Option<WpFractionalScaleV1>,
+    viewport: Option<WpViewport>,
+}
+
+impl Default for SurfaceScale {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl SurfaceScale {
+    pub fn new(
+        fractional_scale: Option<WpFractionalScaleV1>,
+        viewport: Option<WpViewport>,
+    ) -> Self {
+        Self {
+            buffer_scale: 1,
+            fractional: None,
+            fractional_scale,
+            viewport,
+        }
+    }
+
+    /// The scale factor content should be rendered at
+    pub fn factor(&self) -> f64 {
+        if self.uses_viewport() {
+            self.fractional.unwrap_or(self.buffer_scale as f64)
+        } else {
+            self.buffer_scale as f64
+        }
+    }
+
+    /// The integer buffer scale preferred by the compositor
+    pub fn buffer_scale(&self) -> i32 {
+        self.buffer_scale
+    }
+
+    /// Buffer size in pixels for a surface of the given logical size
+    pub fn physical_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.uses_viewport() {
+            let factor = self.factor();
+            (
+                (width as f64 * factor).round() as u32,
+                (height as f64 * factor).round() as u32,
+            )
+        } else {
+            let factor = self.buffer_scale.max(1) as u32;
+            (width * factor, height * factor)
+        }
+    }
+
+    /// Copy the current scale of a parent surface, used until the compositor
+    /// tells us the child's own preferred scale.
+    pub(crate) fn inherit(&mut self, parent: &SurfaceScale) {
+        self.buffer_scale = parent.buffer_scale;
+        self.fractional = parent.fractional;
+    }
+
+    /// Returns true if the scale changed
+    pub(crate) fn set_buffer_scale(&mut self, scale: i32) -> bool {
+        let scale = scale.max(1);
+        let changed = self.buffer_scale != scale;
+        self.buffer_scale = scale;
+        changed
+    }
+
+    /// Update from a `wp_fractional_scale_v1.preferred_scale` event.
+    /// Returns true if the scale changed.
+    pub(crate) fn set_fractional(&mut self, numerator: u32) -> bool {
+        let scale = Some(numerator as f64 / FRACTIONAL_SCALE_DENOMINATOR);
+        let changed = self.fractional != scale;
+        self.fractional = scale;
+        changed
+    }
+
+    fn uses_viewport(&self) -> bool {
+        self.fractional.is_some() && self.viewport.is_some()
+    }
+
+    /// Tell the compositor how the next buffer maps to the surface's logical size
+    pub(crate) fn apply(&self, surface: &WlSurface, width: u32, height: u32) {
+        if let Some(viewport) = self.viewport.as_ref().filter(|_| self.uses_viewport()) {
+            if surface.version() >= 3 {
+                surface.set_buffer_scale(1);
+            }
+            viewport.set_destination(width as i32, height as i32);
+        } else if surface.version() >= 3 {
+            surface.set_buffer_scale(self.buffer_scale.max(1));
+        }
+    }
+}
+
+impl Drop for SurfaceScale {
+    fn drop(&mut self) {
+        if let Some(fractional_scale) = self.fractional_scale.take() {
+            fractional_scale.destroy();
+        }
+        if let Some(viewport) = self.viewport.take() {
+            viewport.destroy();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_scale() {
+        let scale = SurfaceScale::default();
+        assert_eq!(scale.factor(), 1.0);
+        assert_eq!(scale.physical_size(800, 600), (800, 600));
+    }
+
+    #[test]
+    fn test_integer_scale() {
+        let mut scale = SurfaceScale::default();
+        assert!(scale.set_buffer_scale(2));
+        assert!(!scale.set_buffer_scale(2));
+        assert_eq!(scale.factor(), 2.0);
+        assert_eq!(scale.physical_size(800, 600), (1600, 1200));
+    }
+
+    #[test]
+    fn test_fractional_scale_needs_viewport() {
+        // Without a viewport the fractional scale can't be honoured
+        let mut scale = SurfaceScale::default();
+        assert!(scale.set_fractional(180));
+        assert_eq!(scale.factor(), 1.0);
+        assert_eq!(scale.physical_size(101, 50), (101, 50));
+    }
+
+    #[test]
+    fn test_buffer_scale_clamped() {
+        let mut scale = SurfaceScale::default();
+        scale.set_buffer_scale(0);
+        assert_eq!(scale.buffer_scale(), 1);
+    }
+
+    #[test]
+    fn test_inherit() {
+        let mut parent = SurfaceScale::default();
+        parent.set_buffer_scale(3);
+        let mut child = SurfaceScale::default();
+        child.inherit(&parent);
+        assert_eq!(child.factor(), 3.0);
+    }
+}
@@ -0,0 +1,801 @@
+//! `ScrollView`: a viewport onto a child larger than the space it's given.
+//!
+//! The child is laid out with no size limit along each scrolling axis and
+//! rendered clipped to the view. The wheel, touchpad, keyboard and overlay
+//! scrollbars move it, and touchpad flings keep gliding after the fingers
+//! lift. `Scroller` holds that machinery for other scrolling widgets.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::input::{
+    Key, KeyEvent, KeyState, PointerButton, PointerEvent, PointerEventKind, ScrollEvent,
+    ScrollSource,
+};
+use crate::layout::Axis;
+use crate::render::Rgba;
+use crate::widget::{Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetId};
+
+const SCROLLBAR_WIDTH: f32 = 6.0;
+/// Scrollbars widen under the pointer, and are this easy to grab anyway
+const SCROLLBAR_HOVER_WIDTH: f32 = 10.0;
+const SCROLLBAR_MARGIN: f32 = 2.0;
+const MIN_THUMB_LENGTH: f32 = 24.0;
+const THUMB: Rgba = Rgba::new(200, 200, 200, 110);
+const THUMB_ACTIVE: Rgba = Rgba::new(220, 220, 220, 190);
+/// Distance moved by the arrow keys
+const LINE_STEP: f32 = 40.0;
+/// Finger scrolls this recent set the speed of a fling
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+/// Flings slower than this (in pixels per second) don't glide
+const MIN_FLING_VELOCITY: f32 = 150.0;
+/// A glide stops once it slows below this
+const STOP_VELOCITY: f32 = 10.0;
+/// Seconds for a glide's velocity to fall to 1/e
+const GLIDE_TIME_CONSTANT: f32 = 0.35;
+
+/// Velocity of a glide after a fling, in pixels per second
+#[derive(Clone, Copy, Debug)]
+struct Glide {
+    vx: f32,
+    vy: f32,
+    last: Instant,
+}
+
+/// A scrollbar being dragged by its thumb
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    axis: Axis,
+    /// Pointer position along the track minus the thumb's start
+    grab: f32,
+}
+
+/// A scrollbar's track and thumb, along its axis
+#[derive(Clone, Copy, Debug)]
+struct Scrollbar {
+    track_start: f32,
+    track_length: f32,
+    thumb_start: f32,
+    thumb_length: f32,
+    /// Position across the bar of its far edge (right or bottom)
+    cross_end: f32,
+}
+
+impl Scrollbar {
+    fn rect(&self, axis: Axis, width: f32) -> Rect {
+        let (main, cross) = (self.thumb_start, self.cross_end - width);
+        let (length, width) = (self.thumb_length.round() as u32, width.round() as u32);
+        match axis {
+            Axis::Horizontal => Rect::new(main as i32, cross as i32, length, width),
+            Axis::Vertical => Rect::new(cross as i32, main as i32, width, length),
+        }
+    }
+
+    fn track_rect(&self, axis: Axis) -> Rect {
+        let bar = Self {
+            thumb_start: self.track_start,
+            thumb_length: self.track_length,
+            ..*self
+        };
+        bar.rect(axis, SCROLLBAR_HOVER_WIDTH)
+    }
+}
+
+/// Scroll position, flings and overlay scrollbars for content of one size
+/// seen through a viewport of another
+#[derive(Default)]
+pub(crate) struct Scroller {
+    content: Size,
+    viewport: Size,
+    offset_x: f32,
+    offset_y: f32,
+    /// Recent finger scrolls as (time, dx, dy), for fling velocity
+    samples: VecDeque<(Instant, f32, f32)>,
+    glide: Option<Glide>,
+    drag: Option<Drag>,
+    /// The scrollbar under the pointer
+    hover: Option<Axis>,
+}
+
+impl Scroller {
+    /// Update the sizes after a layout, keeping the offset in range
+    pub(crate) fn set_sizes(&mut self, content: Size, viewport: Size) {
+        self.content = content;
+        self.viewport = viewport;
+        self.clamp();
+    }
+
+    pub(crate) fn content(&self) -> Size {
+        self.content
+    }
+
+    pub(crate) fn viewport(&self) -> Size {
+        self.viewport
+    }
+
+    pub(crate) fn offset(&self) -> (f32, f32) {
+        (self.offset_x, self.offset_y)
+    }
+
+    pub(crate) fn scroll_to(&mut self, x: f32, y: f32) {
+        self.glide = None;
+        self.offset_x = x;
+        self.offset_y = y;
+        self.clamp();
+    }
+
+    /// Scroll as little as possible to bring `rect`, in content coordinates,
+    /// into view. A rect larger than the view shows its start.
+    pub(crate) fn scroll_to_rect(&mut self, rect: Rect) {
+        let reveal = |offset: f32, start: i32, length: u32, viewport: u32| {
+            let (start, end) = (start as f32, start as f32 + length as f32);
+            if start < offset {
+                start
+            } else if end > offset + viewport as f32 {
+                (end - viewport as f32).min(start)
+            } else {
+                offset
+            }
+        };
+        let x = reveal(self.offset_x, rect.x, rect.width, self.viewport.width);
+        let y = reveal(self.offset_y, rect.y, rect.height, self.viewport.height);
+        self.scroll_to(x, y);
+    }
+
+    fn max_offset(&self, axis: Axis) -> f32 {
+        let (content, viewport) = match axis {
+            Axis::Horizontal => (self.content.width, self.viewport.width),
+            Axis::Vertical => (self.content.height, self.viewport.height),
+        };
+        content.saturating_sub(viewport) as f32
+    }
+
+    pub(crate) fn can_scroll(&self, axis: Axis) -> bool {
+        self.max_offset(axis) > 0.0
+    }
+
+    fn clamp(&mut self) {
+        self.offset_x = self.offset_x.clamp(0.0, self.max_offset(Axis::Horizontal));
+        self.offset_y = self.offset_y.clamp(0.0, self.max_offset(Axis::Vertical));
+    }
+
+    fn axis_offset(&self, axis: Axis) -> f32 {
+        match axis {
+            Axis::Horizontal => self.offset_x,
+            Axis::Vertical => self.offset_y,
+        }
+    }
+
+    fn set_axis_offset(&mut self, axis: Axis, offset: f32) {
+        match axis {
+            Axis::Horizontal => self.offset_x = offset,
+            Axis::Vertical => self.offset_y = offset,
+        }
+        self.clamp();
+    }
+
+    /// Where content at the view's top left sits, given the view's `bounds`
+    pub(crate) fn content_bounds(&self, bounds: Rect) -> Rect {
+        Rect::new(
+            bounds.x - self.offset_x.round() as i32,
+            bounds.y - self.offset_y.round() as i32,
+            self.content.width,
+            self.content.height,
+        )
+    }
+
+    /// The scrollbar for `axis` within `bounds`, if that axis can scroll
+    fn scrollbar(&self, axis: Axis, bounds: Rect) -> Option<Scrollbar> {
+        if !self.can_scroll(axis) {
+            return None;
+        }
+        let (x, y) = (bounds.x as f32, bounds.y as f32);
+        let (width, height) = (bounds.width as f32, bounds.height as f32);
+        // Leave the corner free when both bars show
+        let other = match axis {
+            Axis::Horizontal => Axis::Vertical,
+            Axis::Vertical => Axis::Horizontal,
+        };
+        let corner = if self.can_scroll(other) {
+            SCROLLBAR_HOVER_WIDTH + SCROLLBAR_MARGIN
+        } else {
+            0.0
+        };
+        let (start, length, cross_end, viewport, content) = match axis {
+            Axis::Horizontal => (
+                x,
+                width,
+                y + height - SCROLLBAR_MARGIN,
+                self.viewport.width,
+                self.content.width,
+            ),
+            Axis::Vertical => (
+                y,
+                height,
+                x + width - SCROLLBAR_MARGIN,
+                self.viewport.height,
+                self.content.height,
+            ),
+        };
+        let track_start = start + SCROLLBAR_MARGIN;
+        let track_length = (length - SCROLLBAR_MARGIN * 2.0 - corner).max(0.0);
+        let thumb_length = (track_length * viewport as f32 / content as f32)
+            .max(MIN_THUMB_LENGTH)
+            .min(track_length);
+        let travel = track_length - thumb_length;
+        Some(Scrollbar {
+            track_start,
+            track_length,
+            thumb_start: track_start + travel * self.axis_offset(axis) / self.max_offset(axis),
+            thumb_length,
+            cross_end,
+        })
+    }
+
+    /// The scrollbar whose track contains (x, y)
+    fn scrollbar_at(&self, bounds: Rect, x: i32, y: i32) -> Option<(Axis, Scrollbar)> {
+        [Axis::Vertical, Axis::Horizontal]
+            .into_iter()
+            .filter_map(|axis| Some((axis, self.scrollbar(axis, bounds)?)))
+            .find(|(axis, bar)| bar.track_rect(*axis).contains(x, y))
+    }
+
+    /// Draw the scrollbars over the content in `bounds`
+    pub(crate) fn render_scrollbars(&self, bounds: Rect, ctx: &mut RenderContext) {
+        for axis in [Axis::Vertical, Axis::Horizontal] {
+            let Some(bar) = self.scrollbar(axis, bounds) else {
+                continue;
+            };
+            let active = self.hover == Some(axis) || self.drag.is_some_and(|d| d.axis == axis);
+            let (width, color) = if active {
+                (SCROLLBAR_HOVER_WIDTH, THUMB_ACTIVE)
+            } else {
+                (SCROLLBAR_WIDTH, THUMB)
+            };
+            let thumb = bar.rect(axis, width);
+            ctx.canvas.fill_rounded_rect(
+                thumb.x as f32,
+                thumb.y as f32,
+                thumb.width as f32,
+                thumb.height as f32,
+                width / 2.0,
+                color.to_color(),
+            );
+        }
+    }
+
+    /// Move the view so the dragged thumb follows the pointer
+    fn drag_to(&mut self, drag: Drag, bounds: Rect, x: f64, y: f64) {
+        let Some(bar) = self.scrollbar(drag.axis, bounds) else {
+            return;
+        };
+        let pointer = match drag.axis {
+            Axis::Horizontal => x as f32,
+            Axis::Vertical => y as f32,
+        };
+        let travel = bar.track_length - bar.thumb_length;
+        if travel > 0.0 {
+            let fraction = (pointer - drag.grab - bar.track_start) / travel;
+            self.set_axis_offset(drag.axis, fraction * self.max_offset(drag.axis));
+        }
+    }
+
+    /// Handle presses on and drags of the scrollbars. A press anywhere in
+    /// the view stops a glide. Returns whether the event was used up.
+    pub(crate) fn handle_scrollbar(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        let (x, y) = (event.x as i32, event.y as i32);
+        match event.kind {
+            PointerEventKind::Press(button) if bounds.contains(x, y) => {
+                self.glide = None;
+                let Some((axis, bar)) = self.scrollbar_at(bounds, x, y) else {
+                    return false;
+                };
+                if button == PointerButton::Left {
+                    let pointer = match axis {
+                        Axis::Horizontal => event.x as f32,
+                        Axis::Vertical => event.y as f32,
+                    };
+                    let on_thumb =
+                        (bar.thumb_start..bar.thumb_start + bar.thumb_length).contains(&pointer);
+                    // Clicking the track jumps the thumb's middle there
+                    let grab = if on_thumb {
+                        pointer - bar.thumb_start
+                    } else {
+                        bar.thumb_length / 2.0
+                    };
+                    let drag = Drag { axis, grab };
+                    self.drag = Some(drag);
+                    self.drag_to(drag, bounds, event.x, event.y);
+                }
+                true
+            }
+            PointerEventKind::Motion if self.drag.is_some() => {
+                if let Some(drag) = self.drag {
+                    self.drag_to(drag, bounds, event.x, event.y);
+                }
+                true
+            }
+            PointerEventKind::Release(PointerButton::Left) if self.drag.is_some() => {
+                self.drag = None;
+                self.hover = self.scrollbar_at(bounds, x, y).map(|(axis, _)| axis);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Track which scrollbar is under the pointer; returns whether that changed
+    pub(crate) fn update_hover(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        let (x, y) = (event.x as i32, event.y as i32);
+        let hover = match event.kind {
+            PointerEventKind::Leave => None,
+            _ if !bounds.contains(x, y) => None,
+            _ => self.scrollbar_at(bounds, x, y).map(|(axis, _)| axis),
+        };
+        let changed = hover != self.hover;
+        self.hover = hover;
+        changed
+    }
+
+    /// Scroll by arrows, PageUp/PageDown and Home/End
+    pub(crate) fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        let mods = event.modifiers;
+        if event.state != KeyState::Pressed || mods.ctrl || mods.alt || mods.super_ {
+            return false;
+        }
+        // Paging and Home/End work vertically, or sideways if that's all there is
+        let axis = if self.can_scroll(Axis::Vertical) || !self.can_scroll(Axis::Horizontal) {
+            Axis::Vertical
+        } else {
+            Axis::Horizontal
+        };
+        let page = match axis {
+            Axis::Horizontal => self.viewport.width,
+            Axis::Vertical => self.viewport.height,
+        } as f32
+            * 0.9;
+        let offset = self.axis_offset(axis);
+
+        let (axis, target) = match event.key {
+            Key::Up => (Axis::Vertical, self.offset_y - LINE_STEP),
+            Key::Down => (Axis::Vertical, self.offset_y + LINE_STEP),
+            Key::Left => (Axis::Horizontal, self.offset_x - LINE_STEP),
+            Key::Right => (Axis::Horizontal, self.offset_x + LINE_STEP),
+            Key::PageUp => (axis, offset - page),
+            Key::PageDown => (axis, offset + page),
+            Key::Home => (axis, 0.0),
+            Key::End => (axis, self.max_offset(axis)),
+            _ => return false,
+        };
+        if !self.can_scroll(axis) {
+            return false;
+        }
+        self.glide = None;
+        self.set_axis_offset(axis, target);
+        true
+    }
+
+    /// Apply a scroll, tracking finger motion so a fling can glide. Returns
+    /// whether the view moved or started gliding; a scroll past the edge is
+    /// left for an outer view.
+    pub(crate) fn scroll(&mut self, scroll: &ScrollEvent, now: Instant) -> bool {
+        let (mut dx, mut dy) = (scroll.dx as f32, scroll.dy as f32);
+        // A vertical wheel scrolls a view that only scrolls sideways
+        if !self.can_scroll(Axis::Vertical) && dx == 0.0 {
+            (dx, dy) = (dy, 0.0);
+        }
+        if !self.can_scroll(Axis::Horizontal) {
+            dx = 0.0;
+        }
+        if !self.can_scroll(Axis::Vertical) {
+            dy = 0.0;
+        }
+
+        self.glide = None;
+        let (x, y) = (self.offset_x, self.offset_y);
+        self.offset_x += dx;
+        self.offset_y += dy;
+        self.clamp();
+        let (dx, dy) = (self.offset_x - x, self.offset_y - y);
+
+        if scroll.source == ScrollSource::Finger {
+            if dx != 0.0 || dy != 0.0 {
+                self.samples.push_back((now, dx, dy));
+            }
+            while let Some(&(time, ..)) = self.samples.front()
+                && now.duration_since(time) > VELOCITY_WINDOW
+            {
+                self.samples.pop_front();
+            }
+            if scroll.stop_x || scroll.stop_y {
+                self.fling(now);
+            }
+        }
+        dx != 0.0 || dy != 0.0 || self.glide.is_some()
+    }
+
+    /// Start gliding at the speed of the recent finger scrolls
+    fn fling(&mut self, now: Instant) {
+        let Some(&(first, ..)) = self.samples.front() else {
+            return;
+        };
+        // At least a frame's worth, so one quick sample isn't a huge speed
+        let elapsed = now
+            .duration_since(first)
+            .max(Duration::from_millis(16))
+            .as_secs_f32();
+        let (dx, dy) = self
+            .samples
+            .drain(..)
+            .fold((0.0, 0.0), |(x, y), (_, dx, dy)| (x + dx, y + dy));
+        let (vx, vy) = (dx / elapsed, dy / elapsed);
+        if vx.hypot(vy) >= MIN_FLING_VELOCITY {
+            self.glide = Some(Glide { vx, vy, last: now });
+        }
+    }
+
+    /// Move a glide on to `now`, slowing it down
+    pub(crate) fn advance(&mut self, now: Instant) {
+        let Some(glide) = self.glide else {
+            return;
+        };
+        let dt = now.duration_since(glide.last).as_secs_f32();
+        let decay = (-dt / GLIDE_TIME_CONSTANT).exp();
+        // The distance covered while the speed decays exponentially
+        let travel = GLIDE_TIME_CONSTANT * (1.0 - decay);
+        let (x, y) = (self.offset_x, self.offset_y);
+        self.offset_x += glide.vx * travel;
+        self.offset_y += glide.vy * travel;
+        self.clamp();
+
+        // Hitting an edge stops the glide along that axis
+        let vx = if self.offset_x == x + glide.vx * travel {
+            glide.vx * decay
+        } else {
+            0.0
+        };
+        let vy = if self.offset_y == y + glide.vy * travel {
+            glide.vy * decay
+        } else {
+            0.0
+        };
+        self.glide = (vx.hypot(vy) >= STOP_VELOCITY).then_some(Glide { vx, vy, last: now });
+    }
+
+    pub(crate) fn is_gliding(&self) -> bool {
+        self.glide.is_some()
+    }
+}
+
+/// The size of a scrolling view: all the space given, or `preferred` along
+/// an axis with no limit
+pub(crate) fn viewport_size(preferred: Size, constraints: Constraints) -> Size {
+    let fit = |preferred: u32, min: u32, max: u32| {
+        if max == u32::MAX {
+            preferred.max(min)
+        } else {
+            max
+        }
+    };
+    Size::new(
+        fit(
+            preferred.width,
+            constraints.min_width,
+            constraints.max_width,
+        ),
+        fit(
+            preferred.height,
+            constraints.min_height,
+            constraints.max_height,
+        ),
+    )
+}
+
+/// Shows part of its child, scrolled with the wheel, touchpad, keyboard
+/// (arrows, PageUp/PageDown, Home/End) or by dragging the scrollbars
+pub struct ScrollView {
+    id: WidgetId,
+    child: Box<dyn Widget>,
+    horizontal: bool,
+    vertical: bool,
+    scroller: Scroller,
+}
+
+impl ScrollView {
+    /// Scroll `child` on both axes
+    pub fn new(id: WidgetId, child: impl Widget + 'static) -> Self {
+        Self {
+            id,
+            child: Box::new(child),
+            horizontal: true,
+            vertical: true,
+            scroller: Scroller::default(),
+        }
+    }
+
+    /// Whether the child can be wider than the view and scrolled sideways
+    pub fn horizontal(mut self, horizontal: bool) -> Self {
+        self.horizontal = horizontal;
+        self
+    }
+
+    /// Whether the child can be taller than the view and scrolled vertically
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    /// How far the child is scrolled, as (x, y)
+    pub fn scroll_offset(&self) -> (f32, f32) {
+        self.scroller.offset()
+    }
+
+    /// The child's size from the last layout
+    pub fn content_size(&self) -> Size {
+        self.scroller.content()
+    }
+
+    /// The visible size from the last layout
+    pub fn viewport_size(&self) -> Size {
+        self.scroller.viewport()
+    }
+
+    /// Scroll so the child's point (x, y) is at the top left, as far as the
+    /// content allows
+    pub fn scroll_to(&mut self, x: f32, y: f32) {
+        self.scroller.scroll_to(x, y);
+    }
+
+    pub fn scroll_by(&mut self, dx: f32, dy: f32) {
+        let (x, y) = self.scroller.offset();
+        self.scroller.scroll_to(x + dx, y + dy);
+    }
+
+    /// Scroll as little as possible to bring `rect`, in the child's
+    /// coordinates, into view. A rect larger than the view shows its start.
+    pub fn scroll_to_rect(&mut self, rect: Rect) {
+        self.scroller.scroll_to_rect(rect);
+    }
+
+    /// Apply a wheel or touchpad scroll that happened at `now`
+    fn scroll(&mut self, scroll: &ScrollEvent, now: Instant) -> bool {
+        self.scroller.scroll(scroll, now)
+    }
+
+    /// Move a glide on to `now`
+    fn advance(&mut self, now: Instant) {
+        self.scroller.advance(now);
+    }
+}
+
+impl Widget for ScrollView {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let unbounded = |scrolls: bool, min: u32, max: u32| {
+            if scrolls { (0, u32::MAX) } else { (min, max) }
+        };
+        let (min_width, max_width) = unbounded(
+            self.horizontal,
+            constraints.min_width,
+            constraints.max_width,
+        );
+        let (min_height, max_height) = unbounded(
+            self.vertical,
+            constraints.min_height,
+            constraints.max_height,
+        );
+        let content = self.child.layout(
+            Constraints {
+                min_width,
+                max_width,
+                min_height,
+                max_height,
+            },
+            ctx,
+        );
+
+        let viewport = viewport_size(content, constraints);
+        self.scroller.set_sizes(content, viewport);
+        self.advance(Instant::now());
+        viewport
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        ctx.canvas.push_clip(
+            bounds.x as f32,
+            bounds.y as f32,
+            bounds.width as f32,
+            bounds.height as f32,
+        );
+        ctx.render_child(self.child.as_ref(), self.scroller.content_bounds(bounds));
+        self.scroller.render_scrollbars(bounds, ctx);
+        ctx.canvas.pop_clip();
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        self.scroller.handle_key(event)
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        if self.scroller.handle_scrollbar(event, bounds) {
+            return true;
+        }
+        let inside = bounds.contains(event.x as i32, event.y as i32);
+        let child_bounds = self.scroller.content_bounds(bounds);
+        match event.kind {
+            // Outside the view the child is hidden, so it can't be pressed
+            PointerEventKind::Press(_) if !inside => false,
+            PointerEventKind::Scroll(scroll) if inside => {
+                self.child.handle_pointer(event, child_bounds)
+                    || self.scroll(&scroll, Instant::now())
+            }
+            PointerEventKind::Scroll(_) => false,
+            _ => {
+                let changed = self.scroller.update_hover(event, bounds);
+                self.child.handle_pointer(event, child_bounds) || changed
+            }
+        }
+    }
+
+    fn visit_children(&self, bounds: Rect, f: &mut dyn FnMut(&dyn Widget, Rect)) {
+        f(self.child.as_ref(), self.scroller.content_bounds(bounds));
+    }
+
+    fn visit_children_mut(&mut self, f: &mut dyn FnMut(&mut dyn Widget)) {
+        f(self.child.as_mut());
+    }
+
+    fn is_animating(&self) -> bool {
+        self.scroller.is_gliding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Modifiers, make_key_event, make_pointer_event};
+    use crate::layout::VStack;
+    use crate::render::Canvas;
+    use crate::text::TextRenderer;
+
+    /// A solid white block
+    struct Block(WidgetId, Size);
+
+    impl Widget for Block {
+        fn id(&self) -> WidgetId {
+            self.0
+        }
+
+        fn layout(&mut self, _constraints: Constraints, _ctx: &mut LayoutContext) -> Size {
+            self.1
+        }
+
+        fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+            ctx.canvas.fill_rect(
+                bounds.x as f32,
+                bounds.y as f32,
+                bounds.width as f32,
+                bounds.height as f32,
+                Rgba::WHITE.to_color(),
+            );
+        }
+    }
+
+    /// 100x100 view onto a column of ten 100x50 blocks
+    fn view() -> ScrollView {
+        let mut column = VStack::new(WidgetId(1));
+        for i in 0..10 {
+            column = column.child(Block(WidgetId(10 + i), Size::new(100, 50)));
+        }
+        let mut view = ScrollView::new(WidgetId(0), column);
+        let mut text = TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        view.layout(Constraints::loose(100, 100), &mut ctx);
+        view
+    }
+
+    #[test]
+    fn test_scrolling_is_clamped_to_content() {
+        let mut view = view();
+        let bounds = Rect::new(0, 0, 100, 100);
+        assert_eq!(view.content_size(), Size::new(100, 500));
+        assert_eq!(view.viewport_size(), Size::new(100, 100));
+
+        let wheel = ScrollEvent {
+            dy: 30.0,
+            ..Default::default()
+        };
+        assert!(view.handle_pointer(
+            &make_pointer_event(PointerEventKind::Scroll(wheel), 50.0, 50.0),
+            bounds
+        ));
+        assert_eq!(view.scroll_offset(), (0.0, 30.0));
+
+        view.handle_key(&make_key_event(Key::PageDown, None, Modifiers::default()));
+        assert_eq!(view.scroll_offset(), (0.0, 120.0));
+        view.handle_key(&make_key_event(Key::End, None, Modifiers::default()));
+        assert_eq!(view.scroll_offset(), (0.0, 400.0));
+        // Already at the bottom, so the wheel is left for an outer view
+        assert!(!view.handle_pointer(
+            &make_pointer_event(PointerEventKind::Scroll(wheel), 50.0, 50.0),
+            bounds
+        ));
+        // Nothing to scroll sideways, so Right is left for someone else
+        assert!(!view.handle_key(&make_key_event(Key::Right, None, Modifiers::default())));
+
+        view.scroll_to_rect(Rect::new(0, 150, 100, 50));
+        assert_eq!(view.scroll_offset(), (0.0, 150.0));
+        view.scroll_to_rect(Rect::new(0, 280, 100, 50));
+        assert_eq!(view.scroll_offset(), (0.0, 230.0));
+        view.scroll_by(0.0, -1000.0);
+        assert_eq!(view.scroll_offset(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_scrollbar_drag() {
+        let mut view = view();
+        let bounds = Rect::new(0, 0, 100, 100);
+        // The thumb is a fifth of the 96px track: 19.2px, grown to 24px
+        let press = PointerEventKind::Press(PointerButton::Left);
+        assert!(view.handle_pointer(&make_pointer_event(press, 95.0, 10.0), bounds));
+        assert!(view.handle_pointer(
+            &make_pointer_event(PointerEventKind::Motion, 95.0, 46.0),
+            bounds
+        ));
+        assert_eq!(view.scroll_offset(), (0.0, 200.0));
+
+        let release = PointerEventKind::Release(PointerButton::Left);
+        view.handle_pointer(&make_pointer_event(release, 95.0, 46.0), bounds);
+        view.handle_pointer(
+            &make_pointer_event(PointerEventKind::Motion, 95.0, 90.0),
+            bounds,
+        );
+        assert_eq!(view.scroll_offset(), (0.0, 200.0));
+    }
+
+    #[test]
+    fn test_fling_glides_then_stops() {
+        let mut view = view();
+        let start = Instant::now();
+        let finger = |dy, stop_y| ScrollEvent {
+            dy,
+            source: ScrollSource::Finger,
+            stop_y,
+            ..Default::default()
+        };
+        for i in 0..4 {
+            view.scroll(&finger(10.0, false), start + Duration::from_millis(i * 10));
+        }
+        view.scroll(&finger(0.0, true), start + Duration::from_millis(40));
+        assert_eq!(view.scroll_offset(), (0.0, 40.0));
+        assert!(view.is_animating());
+
+        view.advance(start + Duration::from_millis(100));
+        let (_, y) = view.scroll_offset();
+        assert!(y > 40.0);
+        view.advance(start + Duration::from_secs(10));
+        assert!(view.scroll_offset().1 > y);
+        assert!(!view.is_animating());
+    }
+
+    #[test]
+    fn test_render_is_clipped_to_view() {
+        let mut view = view();
+        view.scroll_to(0.0, 25.0);
+        let mut data = vec![0u8; 200 * 200 * 4];
+        let mut canvas = Canvas::new(&mut data, 200, 200);
+        let mut text = TextRenderer::new();
+        let mut ctx = RenderContext::new(&mut canvas, &mut text);
+        view.render(Rect::new(0, 50, 100, 100), &mut ctx);
+
+        let alpha = |x: usize, y: usize| data[(y * 200 + x) * 4 + 3];
+        assert_eq!(alpha(10, 49), 0);
+        assert_eq!(alpha(10, 50), 255);
+        assert_eq!(alpha(10, 149), 255);
+        assert_eq!(alpha(10, 150), 0);
+        assert_eq!(alpha(150, 100), 0);
+    }
+}
@@ -0,0 +1,1051 @@
+//! `Table`: rows of cells under a header of sortable, resizable columns.
+//!
+//! Like `ListView`, the table only knows its row count and asks a callback
+//! for the cells in view. Sorting keeps a permutation of the rows rather
+//! than moving any data, so row numbers given to and by the table are
+//! always the app's own.
+
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::time::Instant;
+
+use crate::controls::{BORDER, CONTROL, TEXT};
+use crate::input::{Key, KeyEvent, KeyState, PointerButton, PointerEvent, PointerEventKind};
+use crate::list::{
+    DEFAULT_ROW_HEIGHT, DoubleClick, Pick, RowHeights, RowSelection, SelectionMode, TypeAhead,
+    fill_row, outline_row,
+};
+use crate::render::Rgba;
+use crate::scroll::{Scroller, viewport_size};
+use crate::text::{FONT_SIZE, HAlign, VAlign};
+use crate::widget::{
+    Constraints, LayoutContext, Rect, RenderContext, Size, SortOrder, Widget, WidgetEvent,
+    WidgetEvents, WidgetId,
+};
+
+const HEADER_HEIGHT: u32 = 28;
+const DEFAULT_COLUMN_WIDTH: u32 = 120;
+const DEFAULT_MIN_WIDTH: u32 = 32;
+/// Space either side of a cell's text
+const CELL_PADDING: u32 = 6;
+/// How far either side of a column's right edge grabs it for resizing
+const RESIZE_HANDLE: f64 = 4.0;
+/// How far a header must be dragged before it moves rather than sorts
+const DRAG_THRESHOLD: f64 = 4.0;
+/// Room for the sort indicator right of a sorted column's title
+const INDICATOR_WIDTH: u32 = 14;
+const HEADER_DRAGGED: Rgba = Rgba::rgb(78, 78, 86);
+const INDICATOR: Rgba = Rgba::rgb(180, 180, 186);
+
+/// A table column's title, sizing and alignment
+pub struct Column {
+    title: String,
+    width: u32,
+    min_width: u32,
+    align: HAlign,
+    sortable: bool,
+}
+
+impl Column {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            width: DEFAULT_COLUMN_WIDTH,
+            min_width: DEFAULT_MIN_WIDTH,
+            align: HAlign::Left,
+            sortable: true,
+        }
+    }
+
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// The narrowest the user can drag the column
+    pub fn min_width(mut self, min_width: u32) -> Self {
+        self.min_width = min_width;
+        self
+    }
+
+    /// Where cell text and the title sit across the column
+    pub fn align(mut self, align: HAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Whether clicking the header sorts by this column; true by default
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+}
+
+/// A cell in view for a cell renderer to draw
+pub struct TableCell {
+    /// The app's row number, whatever the sort
+    pub row: usize,
+    /// The column's index in the table's column list, wherever it's been dragged
+    pub column: usize,
+    pub bounds: Rect,
+    pub selected: bool,
+    /// The keyboard cursor is on this cell's row
+    pub current: bool,
+    pub hovered: bool,
+}
+
+/// The order rows show in while sorted
+struct RowOrder {
+    /// The row shown at each position
+    rows: Vec<usize>,
+    /// The position each row is shown at
+    positions: Vec<usize>,
+}
+
+/// A header press in progress
+#[derive(Clone, Copy, Debug)]
+enum HeaderDrag {
+    /// Pressed on the header at `position`; sorts on release unless it's
+    /// dragged far enough to move
+    Press { position: usize, x: f64 },
+    /// Dragging `column`'s right edge
+    Resize { column: usize, grab: f64 },
+    /// Dragging the header at `position` to reorder the columns
+    Move { position: usize, grab: f64, x: f64 },
+}
+
+type CellText = Box<dyn Fn(usize, usize) -> String>;
+type CellRenderer = Box<dyn Fn(&TableCell, &mut RenderContext)>;
+type RowCompare = Box<dyn Fn(usize, usize, usize) -> Ordering>;
+type SelectCallback = Box<dyn FnMut(&[usize])>;
+type SortCallback = Box<dyn FnMut(usize, SortOrder)>;
+
+/// A virtualised table of `len` rows with a header that sorts, resizes and
+/// reorders its columns
+pub struct Table {
+    id: WidgetId,
+    columns: Vec<Column>,
+    /// Column indices, left to right
+    order: Vec<usize>,
+    len: usize,
+    sort: Option<(usize, SortOrder)>,
+    row_order: Option<RowOrder>,
+    cell: Option<CellText>,
+    render_cell: Option<CellRenderer>,
+    compare: Option<RowCompare>,
+    heights: RowHeights,
+    /// By display position, not row number
+    selection: RowSelection,
+    type_ahead: TypeAhead,
+    scroller: Scroller,
+    double_click: DoubleClick,
+    header_drag: Option<HeaderDrag>,
+    hovered: Option<usize>,
+    focused: bool,
+    /// A display position to scroll into view once the table has been laid out
+    reveal: Option<usize>,
+    on_select: Option<SelectCallback>,
+    on_activate: Option<Box<dyn FnMut(usize)>>,
+    on_sort: Option<SortCallback>,
+    events: Option<WidgetEvents>,
+}
+
+impl Table {
+    pub fn new(id: WidgetId, columns: Vec<Column>, len: usize) -> Self {
+        Self {
+            id,
+            order: (0..columns.len()).collect(),
+            columns,
+            len,
+            sort: None,
+            row_order: None,
+            cell: None,
+            render_cell: None,
+            compare: None,
+            heights: RowHeights::Fixed(DEFAULT_ROW_HEIGHT),
+            selection: RowSelection::default(),
+            type_ahead: TypeAhead::default(),
+            scroller: Scroller::default(),
+            double_click: DoubleClick::default(),
+            header_drag: None,
+            hovered: None,
+            focused: false,
+            reveal: None,
+            on_select: None,
+            on_activate: None,
+            on_sort: None,
+            events: None,
+        }
+    }
+
+    pub fn row_height(mut self, height: u32) -> Self {
+        self.heights = RowHeights::Fixed(height);
+        self
+    }
+
+    /// Text for the cell at (row, column), drawn elided unless there's a
+    /// cell renderer. Also sorted by, and matched by type-ahead search in
+    /// the leftmost column.
+    pub fn cell(mut self, cell: impl Fn(usize, usize) -> String + 'static) -> Self {
+        self.cell = Some(Box::new(cell));
+        self
+    }
+
+    /// Draw each visible cell. The table has already filled the background
+    /// of selected and hovered rows.
+    pub fn render_cell(
+        mut self,
+        render: impl Fn(&TableCell, &mut RenderContext) + 'static,
+    ) -> Self {
+        self.render_cell = Some(Box::new(render));
+        self
+    }
+
+    /// Order two rows by a column, in place of comparing cell text (as
+    /// numbers where both parse as one)
+    pub fn compare(mut self, compare: impl Fn(usize, usize, usize) -> Ordering + 'static) -> Self {
+        self.compare = Some(Box::new(compare));
+        self
+    }
+
+    pub fn selection_mode(mut self, mode: SelectionMode) -> Self {
+        self.selection.set_mode(mode);
+        self
+    }
+
+    /// Called with the selected rows, in order, when the user changes them
+    pub fn on_select(mut self, f: impl FnMut(&[usize]) + 'static) -> Self {
+        self.on_select = Some(Box::new(f));
+        self
+    }
+
+    /// Called when a row is double-clicked or Enter is pressed on it
+    pub fn on_activate(mut self, f: impl FnMut(usize) + 'static) -> Self {
+        self.on_activate = Some(Box::new(f));
+        self
+    }
+
+    /// Called with the column and order when the user sorts by clicking a header
+    pub fn on_sort(mut self, f: impl FnMut(usize, SortOrder) + 'static) -> Self {
+        self.on_sort = Some(Box::new(f));
+        self
+    }
+
+    /// Also report selection changes, activations and sorting on `events`
+    pub fn events(mut self, events: &WidgetEvents) -> Self {
+        self.events = Some(events.clone());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Change the row count, dropping selected rows that no longer exist
+    /// and sorting again
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.hovered = None;
+        self.resort();
+    }
+
+    pub fn column_width(&self, column: usize) -> Option<u32> {
+        Some(self.columns.get(column)?.width)
+    }
+
+    /// Resize `column`, no narrower than its minimum width
+    pub fn set_column_width(&mut self, column: usize, width: u32) {
+        if let Some(column) = self.columns.get_mut(column) {
+            column.width = width.max(column.min_width);
+        }
+    }
+
+    /// Column indices as shown, left to right
+    pub fn column_order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Show the columns in `order`. Ignored unless it holds each column once.
+    pub fn set_column_order(&mut self, order: Vec<usize>) {
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        if sorted.iter().copied().eq(0..self.columns.len()) {
+            self.order = order;
+        }
+    }
+
+    /// The column and order the rows are sorted by
+    pub fn sort(&self) -> Option<(usize, SortOrder)> {
+        self.sort
+    }
+
+    /// Sort by `column` without reporting it, keeping the selection on the
+    /// same rows
+    pub fn sort_by(&mut self, column: usize, order: SortOrder) {
+        if column < self.columns.len() {
+            self.sort = Some((column, order));
+            self.resort();
+        }
+    }
+
+    /// Show rows in their own order again
+    pub fn clear_sort(&mut self) {
+        self.sort = None;
+        self.resort();
+    }
+
+    /// Sort again after the cells changed
+    pub fn resort(&mut self) {
+        let old = self.row_order.take();
+        if let Some((column, order)) = self.sort {
+            let mut rows: Vec<usize> = (0..self.len).collect();
+            let direct = |ordering: Ordering| match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            };
+            if let Some(compare) = &self.compare {
+                rows.sort_by(|&a, &b| direct(compare(a, b, column)));
+            } else if let Some(cell) = &self.cell {
+                let keys: Vec<String> = rows.iter().map(|&row| cell(row, column)).collect();
+                rows.sort_by(|&a, &b| direct(compare_text(&keys[a], &keys[b])));
+            }
+            let mut positions = vec![0; self.len];
+            for (position, &row) in rows.iter().enumerate() {
+                positions[row] = position;
+            }
+            self.row_order = Some(RowOrder { rows, positions });
+        }
+
+        // Keep the selection on the same rows
+        let (len, new) = (self.len, &self.row_order);
+        self.selection.remap(|position| {
+            let row = old
+                .as_ref()
+                .map_or(Some(position), |o| o.rows.get(position).copied())?;
+            (row < len).then(|| new.as_ref().map_or(row, |n| n.positions[row]))
+        });
+    }
+
+    /// The row shown at display position `position`, if there is one
+    pub fn row_at_position(&self, position: usize) -> Option<usize> {
+        match &self.row_order {
+            Some(order) => order.rows.get(position).copied(),
+            None => (position < self.len).then_some(position),
+        }
+    }
+
+    /// Where `row` is shown, counting from the top, if there is such a row
+    pub fn position_of_row(&self, row: usize) -> Option<usize> {
+        match &self.row_order {
+            Some(order) => order.positions.get(row).copied(),
+            None => (row < self.len).then_some(row),
+        }
+    }
+
+    /// The selected rows, in ascending order
+    pub fn selected(&self) -> Vec<usize> {
+        let mut rows: Vec<usize> = self
+            .selection
+            .selected()
+            .into_iter()
+            .filter_map(|position| self.row_at_position(position))
+            .collect();
+        rows.sort_unstable();
+        rows
+    }
+
+    pub fn is_selected(&self, row: usize) -> bool {
+        self.position_of_row(row)
+            .is_some_and(|position| self.selection.is_selected(position))
+    }
+
+    /// Replace the selection without reporting a change
+    pub fn set_selected(&mut self, rows: impl IntoIterator<Item = usize>) {
+        let positions: Vec<usize> = rows
+            .into_iter()
+            .filter_map(|row| self.position_of_row(row))
+            .collect();
+        self.selection.set(positions, self.len);
+    }
+
+    /// The row with the keyboard cursor
+    pub fn current(&self) -> Option<usize> {
+        self.row_at_position(self.selection.current()?)
+    }
+
+    /// Put the keyboard cursor on `row` and scroll to it, leaving the
+    /// selection alone
+    pub fn set_current(&mut self, row: usize) {
+        if let Some(position) = self.position_of_row(row) {
+            self.selection.pick(position, Pick::Move);
+            self.scroll_to_position(position);
+        }
+    }
+
+    /// Scroll as little as possible to show `row`
+    pub fn scroll_to_row(&mut self, row: usize) {
+        if let Some(position) = self.position_of_row(row) {
+            self.scroll_to_position(position);
+        }
+    }
+
+    pub fn scroll_offset(&self) -> (f32, f32) {
+        self.scroller.offset()
+    }
+
+    /// The display positions in view as of the last layout
+    pub fn visible_rows(&self) -> Range<usize> {
+        let top = self.scroller.offset().1.round() as u32;
+        let height = self.scroller.viewport().height;
+        self.heights.visible(top, height, self.len)
+    }
+
+    fn scroll_to_position(&mut self, position: usize) {
+        self.reveal = Some(position);
+        if self.scroller.viewport().height > 0 {
+            self.reveal_row();
+        }
+    }
+
+    fn reveal_row(&mut self) {
+        if let Some(position) = self.reveal.take()
+            && position < self.len
+        {
+            // Zero width at the current offset leaves the sideways scroll alone
+            let x = self.scroller.offset().0.ceil() as i32;
+            let top = self.heights.start(position);
+            let rect = Rect::new(x, top as i32, 0, self.heights.height(position));
+            self.scroller.scroll_to_rect(rect);
+        }
+    }
+
+    fn total_width(&self) -> u32 {
+        self.columns.iter().map(|column| column.width).sum()
+    }
+
+    /// Where the header at `position` starts, in content coordinates
+    fn column_x(&self, position: usize) -> u32 {
+        self.order[..position]
+            .iter()
+            .map(|&column| self.columns[column].width)
+            .sum()
+    }
+
+    /// The area below the header
+    fn body_bounds(bounds: Rect) -> Rect {
+        Rect::new(
+            bounds.x,
+            bounds.y + HEADER_HEIGHT as i32,
+            bounds.width,
+            bounds.height.saturating_sub(HEADER_HEIGHT),
+        )
+    }
+
+    /// Surface position `x` in content coordinates
+    fn content_x(&self, bounds: Rect, x: f64) -> f64 {
+        x - bounds.x as f64 + self.scroller.offset().0 as f64
+    }
+
+    /// The header under content position `x`, and whether `x` is on its
+    /// right edge's resize handle
+    fn header_at(&self, x: f64) -> Option<(usize, bool)> {
+        let mut left = 0.0;
+        for (position, &column) in self.order.iter().enumerate() {
+            let right = left + self.columns[column].width as f64;
+            if (x - right).abs() <= RESIZE_HANDLE {
+                return Some((position, true));
+            }
+            if (left..right).contains(&x) {
+                return Some((position, false));
+            }
+            left = right;
+        }
+        None
+    }
+
+    /// Swap the dragged header at `position` past neighbours whose middle
+    /// its middle has crossed, returning where it ends up
+    fn move_column(&mut self, mut position: usize, middle: f64) -> usize {
+        let column_middle = |table: &Self, position: usize| {
+            let width = table.columns[table.order[position]].width;
+            table.column_x(position) as f64 + width as f64 / 2.0
+        };
+        loop {
+            if position > 0 && middle < column_middle(self, position - 1) {
+                self.order.swap(position - 1, position);
+                position -= 1;
+            } else if position + 1 < self.order.len() && middle > column_middle(self, position + 1)
+            {
+                self.order.swap(position, position + 1);
+                position += 1;
+            } else {
+                return position;
+            }
+        }
+    }
+
+    /// Move the dragged header at `position` so its grabbed point is at `x`
+    fn drag_column(&mut self, position: usize, grab: f64, x: f64) {
+        let width = self.columns[self.order[position]].width as f64;
+        let position = self.move_column(position, x - grab + width / 2.0);
+        self.header_drag = Some(HeaderDrag::Move { position, grab, x });
+    }
+
+    /// Sort by the column at header `position`, or reverse the sort if it's
+    /// already sorted by it
+    fn header_clicked(&mut self, position: usize) {
+        let column = self.order[position];
+        if !self.columns[column].sortable {
+            return;
+        }
+        let order = match self.sort {
+            Some((sorted, order)) if sorted == column => order.reversed(),
+            _ => SortOrder::Ascending,
+        };
+        self.sort_by(column, order);
+        if let Some(f) = &mut self.on_sort {
+            f(column, order);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::SortChanged {
+                id: self.id,
+                column,
+                order,
+            });
+        }
+    }
+
+    fn handle_header_drag(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        let Some(drag) = self.header_drag else {
+            return false;
+        };
+        let x = self.content_x(bounds, event.x);
+        match (event.kind, drag) {
+            (PointerEventKind::Motion, HeaderDrag::Press { position, x: start }) => {
+                if (x - start).abs() >= DRAG_THRESHOLD {
+                    let grab = start - self.column_x(position) as f64;
+                    self.drag_column(position, grab, x);
+                }
+            }
+            (PointerEventKind::Motion, HeaderDrag::Resize { column, grab }) => {
+                self.set_column_width(column, (x - grab).max(0.0).round() as u32);
+            }
+            (PointerEventKind::Motion, HeaderDrag::Move { position, grab, .. }) => {
+                self.drag_column(position, grab, x);
+            }
+            (PointerEventKind::Release(PointerButton::Left), drag) => {
+                self.header_drag = None;
+                if let HeaderDrag::Press { position, .. } = drag {
+                    self.header_clicked(position);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The display position under surface position `y`
+    fn position_at(&self, body: Rect, y: f64) -> Option<usize> {
+        let y = y - body.y as f64 + self.scroller.offset().1 as f64;
+        let total = self.heights.start(self.len);
+        (self.len > 0 && y >= 0.0 && y < total as f64)
+            .then(|| self.heights.index_at(y as u32, self.len))
+    }
+
+    fn pick(&mut self, position: usize, pick: Pick) {
+        if self.selection.pick(position, pick) {
+            self.selection_changed();
+        }
+        self.scroll_to_position(position);
+    }
+
+    fn selection_changed(&mut self) {
+        let selected = self.selected();
+        if let Some(f) = &mut self.on_select {
+            f(&selected);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::SelectionChanged {
+                id: self.id,
+                selected,
+            });
+        }
+    }
+
+    fn activate(&mut self, position: usize) {
+        let Some(index) = self.row_at_position(position) else {
+            return;
+        };
+        if let Some(f) = &mut self.on_activate {
+            f(index);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::ItemActivated { id: self.id, index });
+        }
+    }
+
+    /// Jump to the row whose leftmost cell matches the text typed so far
+    fn type_ahead(&mut self, text: &str, now: Instant) -> bool {
+        let (Some(cell), Some(&column)) = (&self.cell, self.order.first()) else {
+            return false;
+        };
+        let order = &self.row_order;
+        let label = |position: usize| {
+            let row = order
+                .as_ref()
+                .map_or(position, |order| order.rows[position]);
+            cell(row, column)
+        };
+        let current = self.selection.current();
+        if let Some(position) = self.type_ahead.search(text, now, current, self.len, &label) {
+            self.pick(position, Pick::Replace);
+        }
+        true
+    }
+
+    /// Draw `text` elided to fit across `bounds`, inset by the cell padding
+    fn draw_cell_text(ctx: &mut RenderContext, text: &str, bounds: Rect, align: HAlign) {
+        let inner = Rect::new(
+            bounds.x + CELL_PADDING as i32,
+            bounds.y,
+            bounds.width.saturating_sub(CELL_PADDING * 2),
+            bounds.height,
+        );
+        ctx.text.draw_text_elided(
+            ctx.canvas,
+            text,
+            inner,
+            FONT_SIZE,
+            TEXT,
+            align,
+            VAlign::Center,
+        );
+    }
+
+    fn render_header(&self, bounds: Rect, ctx: &mut RenderContext) {
+        let header = Rect::new(bounds.x, bounds.y, bounds.width, HEADER_HEIGHT);
+        ctx.canvas.fill_rect(
+            header.x as f32,
+            header.y as f32,
+            header.width as f32,
+            header.height as f32,
+            CONTROL.to_color(),
+        );
+        let left = bounds.x - self.scroller.offset().0.round() as i32;
+        let dragged = match self.header_drag {
+            Some(HeaderDrag::Move { position, grab, x }) => Some((position, x - grab)),
+            _ => None,
+        };
+        for (position, &column) in self.order.iter().enumerate() {
+            // The dragged header follows the pointer
+            let x = match dragged {
+                Some((dragged, x)) if dragged == position => left + x.round() as i32,
+                _ => left + self.column_x(position) as i32,
+            };
+            let cell = Rect::new(x, header.y, self.columns[column].width, HEADER_HEIGHT);
+            if cell.x >= header.x + header.width as i32 || cell.x + cell.width as i32 <= header.x {
+                continue;
+            }
+            if dragged.is_some_and(|(dragged, _)| dragged == position) {
+                ctx.canvas.fill_rect(
+                    cell.x as f32,
+                    cell.y as f32,
+                    cell.width as f32,
+                    cell.height as f32,
+                    HEADER_DRAGGED.to_color(),
+                );
+            }
+            self.render_header_cell(column, cell, ctx);
+        }
+        ctx.canvas.fill_rect(
+            header.x as f32,
+            (header.y + header.height as i32 - 1) as f32,
+            header.width as f32,
+            1.0,
+            BORDER.to_color(),
+        );
+    }
+
+    fn render_header_cell(&self, column: usize, cell: Rect, ctx: &mut RenderContext) {
+        let definition = &self.columns[column];
+        let sorted = self
+            .sort
+            .filter(|&(sorted, _)| sorted == column)
+            .map(|(_, order)| order);
+        let title = Rect {
+            width: cell
+                .width
+                .saturating_sub(if sorted.is_some() { INDICATOR_WIDTH } else { 0 }),
+            ..cell
+        };
+        Self::draw_cell_text(ctx, &definition.title, title, definition.align);
+
+        if let Some(order) = sorted {
+            let cx = (title.x + title.width as i32) as f32 + INDICATOR_WIDTH as f32 / 2.0 - 3.0;
+            let cy = cell.y as f32 + cell.height as f32 / 2.0;
+            let points = match order {
+                SortOrder::Ascending => {
+                    [(cx - 4.0, cy + 2.0), (cx + 4.0, cy + 2.0), (cx, cy - 3.0)]
+                }
+                SortOrder::Descending => {
+                    [(cx - 4.0, cy - 2.0), (cx + 4.0, cy - 2.0), (cx, cy + 3.0)]
+                }
+            };
+            ctx.canvas.fill_polygon(&points, INDICATOR.to_color());
+        }
+        let right = (cell.x + cell.width as i32 - 1) as f32;
+        ctx.canvas.fill_rect(
+            right,
+            cell.y as f32 + 6.0,
+            1.0,
+            cell.height as f32 - 12.0,
+            BORDER.to_color(),
+        );
+    }
+
+    fn render_row(&self, position: usize, bounds: Rect, ctx: &mut RenderContext) {
+        let Some(row) = self.row_at_position(position) else {
+            return;
+        };
+        let selected = self.selection.is_selected(position);
+        let current = self.selection.current() == Some(position);
+        let hovered = self.hovered == Some(position);
+        fill_row(ctx, bounds, selected, hovered);
+
+        let left = bounds.x - self.scroller.offset().0.round() as i32;
+        let mut x = left;
+        for &column in &self.order {
+            let width = self.columns[column].width;
+            let cell = Rect::new(x, bounds.y, width, bounds.height);
+            x += width as i32;
+            if cell.x >= bounds.x + bounds.width as i32 || x <= bounds.x {
+                continue;
+            }
+            if let Some(render) = &self.render_cell {
+                let cell = TableCell {
+                    row,
+                    column,
+                    bounds: cell,
+                    selected,
+                    current,
+                    hovered,
+                };
+                render(&cell, ctx);
+            } else if let Some(text) = &self.cell {
+                Self::draw_cell_text(ctx, &text(row, column), cell, self.columns[column].align);
+            }
+        }
+        if current && self.focused {
+            outline_row(ctx, bounds);
+        }
+    }
+}
+
+/// Compare cell text as numbers if both are, otherwise case-insensitively
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a
+            .to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b)),
+    }
+}
+
+impl Widget for Table {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, _ctx: &mut LayoutContext) -> Size {
+        let content = Size::new(self.total_width(), self.heights.start(self.len));
+        let preferred = Size::new(content.width, content.height + HEADER_HEIGHT);
+        let size = viewport_size(preferred, constraints);
+        let body = Size::new(size.width, size.height.saturating_sub(HEADER_HEIGHT));
+        self.scroller.set_sizes(content, body);
+        self.reveal_row();
+        self.scroller.advance(Instant::now());
+        size
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        ctx.canvas.push_clip(
+            bounds.x as f32,
+            bounds.y as f32,
+            bounds.width as f32,
+            bounds.height as f32,
+        );
+        self.render_header(bounds, ctx);
+
+        let body = Self::body_bounds(bounds);
+        ctx.canvas.push_clip(
+            body.x as f32,
+            body.y as f32,
+            body.width as f32,
+            body.height as f32,
+        );
+        let offset = self.scroller.offset().1;
+        for position in self.visible_rows() {
+            let top = (self.heights.start(position) as f32 - offset).round() as i32;
+            let row = Rect::new(
+                body.x,
+                body.y + top,
+                body.width,
+                self.heights.height(position),
+            );
+            self.render_row(position, row, ctx);
+        }
+        self.scroller.render_scrollbars(body, ctx);
+        ctx.canvas.pop_clip();
+        ctx.canvas.pop_clip();
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        let mods = event.modifiers;
+        if event.state != KeyState::Pressed || self.len == 0 || mods.alt || mods.super_ {
+            return false;
+        }
+        let current = self.selection.current();
+        let page = self.scroller.viewport().height;
+        if let Some(position) = self.heights.navigate(event.key, current, page, self.len) {
+            self.type_ahead.reset();
+            self.pick(position, Pick::for_key(mods));
+            return true;
+        }
+
+        match event.key {
+            Key::Left | Key::Right => self.scroller.handle_key(event),
+            Key::Enter | Key::KpEnter if !mods.ctrl => {
+                if let Some(position) = current {
+                    self.activate(position);
+                }
+                true
+            }
+            Key::A if mods.ctrl && self.selection.mode() == SelectionMode::Multiple => {
+                if self.selection.select_all(self.len) {
+                    self.selection_changed();
+                }
+                true
+            }
+            // Space selects, unless it's part of a type-ahead search
+            Key::Space if mods.ctrl || self.type_ahead.is_empty() => {
+                let position = current.unwrap_or(0);
+                let pick = if mods.ctrl {
+                    Pick::Toggle
+                } else {
+                    Pick::Replace
+                };
+                self.pick(position, pick);
+                true
+            }
+            _ if mods.ctrl => false,
+            _ => match &event.text {
+                Some(text) if !text.is_empty() && !text.chars().any(char::is_control) => {
+                    self.type_ahead(text, Instant::now())
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        let body = Self::body_bounds(bounds);
+        if self.handle_header_drag(event, bounds) || self.scroller.handle_scrollbar(event, body) {
+            return true;
+        }
+        let (x, y) = (event.x as i32, event.y as i32);
+        let inside = bounds.contains(x, y);
+        match event.kind {
+            PointerEventKind::Press(PointerButton::Left) if inside && !body.contains(x, y) => {
+                let content_x = self.content_x(bounds, event.x);
+                let Some((position, on_edge)) = self.header_at(content_x) else {
+                    return false;
+                };
+                self.header_drag = Some(if on_edge {
+                    let column = self.order[position];
+                    let grab = content_x - self.columns[column].width as f64;
+                    HeaderDrag::Resize { column, grab }
+                } else {
+                    HeaderDrag::Press {
+                        position,
+                        x: content_x,
+                    }
+                });
+                true
+            }
+            PointerEventKind::Press(PointerButton::Left) if inside => {
+                let Some(position) = self.position_at(body, event.y) else {
+                    return false;
+                };
+                let pick = Pick::for_click(event.modifiers);
+                self.type_ahead.reset();
+                self.pick(position, pick);
+                if self.double_click.click(position, Instant::now()) && pick == Pick::Replace {
+                    self.activate(position);
+                }
+                true
+            }
+            PointerEventKind::Scroll(scroll) if inside => {
+                self.scroller.scroll(&scroll, Instant::now())
+            }
+            PointerEventKind::Enter | PointerEventKind::Motion | PointerEventKind::Leave => {
+                let hovered = match event.kind {
+                    PointerEventKind::Leave => None,
+                    _ if body.contains(x, y) => self.position_at(body, event.y),
+                    _ => None,
+                };
+                let changed = hovered != self.hovered;
+                self.hovered = hovered;
+                self.scroller.update_hover(event, body) || changed
+            }
+            _ => false,
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn focus_changed(&mut self, focused: bool) {
+        self.focused = focused;
+        self.type_ahead.reset();
+    }
+
+    fn is_animating(&self) -> bool {
+        self.scroller.is_gliding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::make_pointer_event;
+    use crate::render::Canvas;
+    use crate::text::TextRenderer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const NAMES: [&str; 4] = ["delta", "Alpha", "charlie", "bravo"];
+    const SIZES: [&str; 4] = ["10", "9", "100", "9.5"];
+
+    fn table() -> Table {
+        let columns = vec![
+            Column::new("Name").width(100),
+            Column::new("Size").width(60).min_width(40),
+        ];
+        let mut table = Table::new(WidgetId(1), columns, 4).cell(|row, column| match column {
+            0 => NAMES[row].to_string(),
+            _ => SIZES[row].to_string(),
+        });
+        let mut text = TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        table.layout(Constraints::loose(300, 200), &mut ctx);
+        table
+    }
+
+    fn drag(table: &mut Table, from: f64, to: f64) {
+        let bounds = Rect::new(0, 0, 300, 200);
+        let left = PointerButton::Left;
+        table.handle_pointer(
+            &make_pointer_event(PointerEventKind::Press(left), from, 10.0),
+            bounds,
+        );
+        table.handle_pointer(
+            &make_pointer_event(PointerEventKind::Motion, to, 10.0),
+            bounds,
+        );
+        table.handle_pointer(
+            &make_pointer_event(PointerEventKind::Release(left), to, 10.0),
+            bounds,
+        );
+    }
+
+    fn shown(table: &Table, column: usize) -> Vec<&'static str> {
+        let cells = if column == 0 { NAMES } else { SIZES };
+        (0..table.len())
+            .filter_map(|position| table.row_at_position(position))
+            .map(|row| cells[row])
+            .collect()
+    }
+
+    #[test]
+    fn test_header_click_sorts() {
+        let sorts = Rc::new(RefCell::new(Vec::new()));
+        let log = sorts.clone();
+        let mut table =
+            table().on_sort(move |column, order| log.borrow_mut().push((column, order)));
+        table.set_selected([2]);
+
+        drag(&mut table, 50.0, 50.0);
+        assert_eq!(shown(&table, 0), ["Alpha", "bravo", "charlie", "delta"]);
+        drag(&mut table, 50.0, 50.0);
+        assert_eq!(shown(&table, 0), ["delta", "charlie", "bravo", "Alpha"]);
+
+        // Numbers sort as numbers
+        drag(&mut table, 130.0, 130.0);
+        assert_eq!(shown(&table, 1), ["9", "9.5", "10", "100"]);
+        assert_eq!(
+            *sorts.borrow(),
+            [
+                (0, SortOrder::Ascending),
+                (0, SortOrder::Descending),
+                (1, SortOrder::Ascending)
+            ]
+        );
+
+        // The selection stays on the same row, wherever it moves
+        assert_eq!(table.selected(), [2]);
+        assert_eq!(table.position_of_row(2), Some(3));
+        assert_eq!(table.position_of_row(4), None);
+        assert_eq!(table.row_at_position(4), None);
+        table.clear_sort();
+        assert_eq!(table.selected(), [2]);
+        assert!(table.selection.is_selected(2));
+    }
+
+    #[test]
+    fn test_resize_and_reorder_columns() {
+        let mut table = table();
+
+        // The Name column's right edge is at 100
+        drag(&mut table, 101.0, 151.0);
+        assert_eq!(table.column_width(0), Some(150));
+        assert_eq!(table.sort(), None);
+        drag(&mut table, 209.0, 100.0);
+        assert_eq!(table.column_width(1), Some(40));
+
+        // Dragging Size's header past Name's middle puts it first
+        drag(&mut table, 170.0, 40.0);
+        assert_eq!(table.column_order(), [1, 0]);
+        assert_eq!(table.sort(), None);
+        drag(&mut table, 20.0, 180.0);
+        assert_eq!(table.column_order(), [0, 1]);
+    }
+
+    #[test]
+    fn test_only_visible_cells_render() {
+        let drawn = Rc::new(RefCell::new(Vec::new()));
+        let log = drawn.clone();
+        let columns = (0..20).map(|i| Column::new(format!("{i}"))).collect();
+        let mut table = Table::new(WidgetId(1), columns, 100_000)
+            .render_cell(move |cell, _ctx| log.borrow_mut().push((cell.row, cell.column)));
+        let mut text = TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        table.layout(Constraints::loose(300, 268), &mut ctx);
+        table.scroll_to_row(50_000);
+        assert_eq!(table.visible_rows(), 49_991..50_001);
+
+        let mut data = vec![0u8; 300 * 268 * 4];
+        let mut canvas = Canvas::new(&mut data, 300, 268);
+        let mut ctx = RenderContext::new(&mut canvas, &mut text);
+        table.render(Rect::new(0, 0, 300, 268), &mut ctx);
+        let drawn = drawn.borrow();
+        assert_eq!(drawn.len(), 10 * 3);
+        assert_eq!(drawn[0], (49_991, 0));
+        assert_eq!(drawn[29], (50_000, 2));
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
 use cosmic_text::{Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, SwashCache};
 
 use crate::render::Canvas;
@@ -19,16 +21,45 @@ pub enum VAlign {
     Bottom,
 }
 
+/// Text size of the stock widgets
+pub(crate) const FONT_SIZE: f32 = 14.0;
+/// Line height as a multiple of the font size
+const LINE_SPACING: f32 = 1.2;
+/// Height of a line of `FONT_SIZE` text
+pub(crate) const LINE_HEIGHT: f32 = FONT_SIZE * LINE_SPACING;
+
+const ELLIPSIS: &str = "…";
+
+static FONT_SYSTEM: OnceLock<Arc<Mutex<FontSystem>>> = OnceLock::new();
+
+/// The process' font system. Loading the system's fonts takes a while and
+/// holds them all in memory, so every renderer and text editor shares one
+/// rather than loading its own.
+pub(crate) fn font_system() -> Arc<Mutex<FontSystem>> {
+    FONT_SYSTEM
+        .get_or_init(|| Arc::new(Mutex::new(FontSystem::new())))
+        .clone()
+}
+
+/// Lock a shared font system. The lock is only held while shaping or
+/// drawing glyphs, never across callbacks, so it can't be taken twice.
+pub(crate) fn lock(font_system: &Mutex<FontSystem>) -> MutexGuard<'_, FontSystem> {
+    font_system.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 pub struct TextRenderer {
-    font_system: FontSystem,
+    font_system: Arc<Mutex<FontSystem>>,
     swash_cache: SwashCache,
+    /// "…" as last shaped for eliding
+    ellipsis: Option<Buffer>,
 }
 
 impl TextRenderer {
     pub fn new() -> Self {
         Self {
-            font_system: FontSystem::new(),
+            font_system: font_system(),
             swash_cache: SwashCache::new(),
+            ellipsis: None,
         }
     }
 
@@ -41,15 +72,81 @@ impl TextRenderer {
         font_size: f32,
         color: Color,
     ) {
-        let metrics = Metrics::new(font_size, font_size * 1.2);
-        let mut buffer = Buffer::new(&mut self.font_system, metrics);
-
+        let metrics = Metrics::new(font_size, font_size * LINE_SPACING);
         let attrs = Attrs::new().family(Family::Monospace);
-        buffer.set_text(&mut self.font_system, text, attrs, Shaping::Advanced);
-        buffer.shape_until_scroll(&mut self.font_system, false);
+        let buffer = self.shape(text, metrics, attrs);
 
         // y is top of text area - no adjustment needed, render_buffer handles baseline
-        self.render_buffer(canvas, &buffer, x, y, color);
+        self.render_buffer(canvas, &buffer, x, y, color, None);
+    }
+
+    /// Like `draw_text`, but only drawing the part inside `clip`
+    pub fn draw_text_clipped(
+        &mut self,
+        canvas: &mut Canvas,
+        text: &str,
+        x: i32,
+        y: i32,
+        font_size: f32,
+        color: Color,
+        clip: Rect,
+    ) {
+        let metrics = Metrics::new(font_size, font_size * LINE_SPACING);
+        let attrs = Attrs::new().family(Family::Monospace);
+        let buffer = self.shape(text, metrics, attrs);
+
+        self.render_buffer(canvas, &buffer, x, y, color, Some(clip));
+    }
+
+    /// The x offset of each caret position in a single line of `text`, as
+    /// (byte index, x) in order, ending with the end of the line
+    pub fn caret_offsets(&mut self, text: &str, font_size: f32) -> Vec<(usize, f32)> {
+        let metrics = Metrics::new(font_size, font_size * LINE_SPACING);
+        let attrs = Attrs::new().family(Family::Monospace);
+        let buffer = self.shape(text, metrics, attrs);
+        line_offsets(&buffer, text)
+    }
+
+    /// Like `draw_text_in_rect`, but cut short with "…" if `text` doesn't
+    /// fit across `rect`. The text is only shaped once.
+    ///
+    /// This is its own entry point rather than an option on
+    /// `draw_text_in_rect`: that one lets text overflow its rect, which
+    /// labels and buttons rely on, and where to cut needs the shaped glyph
+    /// offsets it never looks at.
+    pub fn draw_text_elided(
+        &mut self,
+        canvas: &mut Canvas,
+        text: &str,
+        rect: Rect,
+        font_size: f32,
+        color: Color,
+        h_align: HAlign,
+        v_align: VAlign,
+    ) {
+        let metrics = Metrics::new(font_size, font_size * LINE_SPACING);
+        let buffer = self.shape(text, metrics, Attrs::new().family(Family::Monospace));
+        let offsets = line_offsets(&buffer, text);
+        let width = offsets.last().map_or(0.0, |&(_, x)| x);
+        let height = buffer.layout_runs().count() as f32 * metrics.line_height;
+        let y = align_y(rect, height, v_align);
+        if width <= rect.width as f32 {
+            let x = align_x(rect, width, h_align);
+            self.render_buffer(canvas, &buffer, x, y, color, None);
+            return;
+        }
+
+        // Draw the glyphs that fit, clipped where the ellipsis starts
+        let ellipsis = self.shape_ellipsis(font_size);
+        let ellipsis_width = line_width(&ellipsis);
+        let (_, cut) = elision(text, &offsets, rect.width as f32 - ellipsis_width);
+        let x = align_x(rect, cut + ellipsis_width, h_align);
+        let top = rect.y.min(y);
+        let bottom = (rect.y + rect.height as i32).max(y + height.ceil() as i32);
+        let clip = Rect::new(x, top, cut.round() as u32, (bottom - top) as u32);
+        self.render_buffer(canvas, &buffer, x, y, color, Some(clip));
+        self.render_buffer(canvas, &ellipsis, x + cut.round() as i32, y, color, None);
+        self.ellipsis = Some(ellipsis);
     }
 
     pub fn draw_text_with_attrs(
@@ -62,12 +159,10 @@ impl TextRenderer {
         attrs: Attrs,
         color: Color,
     ) {
-        let mut buffer = Buffer::new(&mut self.font_system, metrics);
-        buffer.set_text(&mut self.font_system, text, attrs, Shaping::Advanced);
-        buffer.shape_until_scroll(&mut self.font_system, false);
+        let buffer = self.shape(text, metrics, attrs);
 
         // y is top of text area - no adjustment needed
-        self.render_buffer(canvas, &buffer, x, y, color);
+        self.render_buffer(canvas, &buffer, x, y, color, None);
     }
 
     pub fn draw_text_in_rect(
@@ -81,20 +176,28 @@ impl TextRenderer {
         v_align: VAlign,
     ) {
         let (text_width, text_height) = self.measure_text(text, font_size);
+        let x = align_x(rect, text_width, h_align);
+        let y = align_y(rect, text_height, v_align);
+        self.draw_text(canvas, text, x, y, font_size, color);
+    }
 
-        let x = match h_align {
-            HAlign::Left => rect.x,
-            HAlign::Center => rect.x + (rect.width as i32 - text_width as i32) / 2,
-            HAlign::Right => rect.x + rect.width as i32 - text_width as i32,
-        };
-
-        let y = match v_align {
-            VAlign::Top => rect.y,
-            VAlign::Center => rect.y + (rect.height as i32 - text_height as i32) / 2,
-            VAlign::Bottom => rect.y + rect.height as i32 - text_height as i32,
-        };
+    fn shape(&self, text: &str, metrics: Metrics, attrs: Attrs) -> Buffer {
+        let font_system = &mut *lock(&self.font_system);
+        let mut buffer = Buffer::new(font_system, metrics);
+        buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+        buffer.shape_until_scroll(font_system, false);
+        buffer
+    }
 
-        self.draw_text(canvas, text, x, y, font_size, color);
+    /// "…" shaped at `font_size`, reusing the last one if it matches
+    fn shape_ellipsis(&mut self, font_size: f32) -> Buffer {
+        match self.ellipsis.take() {
+            Some(buffer) if buffer.metrics().font_size == font_size => buffer,
+            _ => {
+                let metrics = Metrics::new(font_size, font_size * LINE_SPACING);
+                self.shape(ELLIPSIS, metrics, Attrs::new().family(Family::Monospace))
+            }
+        }
     }
 
     fn render_buffer(
@@ -104,19 +207,31 @@ impl TextRenderer {
         x: i32,
         y: i32,
         color: Color,
+        clip: Option<Rect>,
     ) {
-        let canvas_width = canvas.width() as i32;
-        let canvas_height = canvas.height() as i32;
+        // Glyphs are rasterised at the canvas' physical resolution
+        let scale = canvas.scale();
+        let (x0, y0, x1, y1) = canvas.clip_bounds();
+        let (min_x, min_y, max_x, max_y) = match clip {
+            Some(clip) => (
+                ((clip.x as f32 * scale) as i32).max(x0),
+                ((clip.y as f32 * scale) as i32).max(y0),
+                (((clip.x + clip.width as i32) as f32 * scale) as i32).min(x1),
+                (((clip.y + clip.height as i32) as f32 * scale) as i32).min(y1),
+            ),
+            None => (x0, y0, x1, y1),
+        };
 
+        let font_system = &mut *lock(&self.font_system);
         for run in buffer.layout_runs() {
             // run.line_y is the baseline position for this line
-            let line_y = y as f32 + run.line_y;
+            let line_y = (y as f32 + run.line_y) * scale;
             for glyph in run.glyphs.iter() {
-                let physical_glyph = glyph.physical((x as f32, line_y), 1.0);
+                let physical_glyph = glyph.physical((x as f32 * scale, line_y), scale);
 
                 let Some(image) = self
                     .swash_cache
-                    .get_image(&mut self.font_system, physical_glyph.cache_key)
+                    .get_image(font_system, physical_glyph.cache_key)
                 else {
                     continue;
                 };
@@ -131,13 +246,13 @@ impl TextRenderer {
                     .enumerate()
                 {
                     let py = glyph_y + row_idx as i32;
-                    if py < 0 || py >= canvas_height {
+                    if py < min_y.max(0) || py >= max_y {
                         continue;
                     }
 
                     for (col_idx, &alpha) in row.iter().enumerate() {
                         let px = glyph_x + col_idx as i32;
-                        if px < 0 || px >= canvas_width {
+                        if px < min_x.max(0) || px >= max_x {
                             continue;
                         }
 
@@ -145,7 +260,8 @@ impl TextRenderer {
                             continue;
                         }
 
-                        let offset = ((py as u32 * canvas.width() + px as u32) * 4) as usize;
+                        let offset =
+                            ((py as u32 * canvas.physical_width() + px as u32) * 4) as usize;
                         let data = canvas.data_mut();
                         if offset + 3 >= data.len() {
                             continue;
@@ -173,12 +289,9 @@ impl TextRenderer {
     }
 
     pub fn measure_text(&mut self, text: &str, font_size: f32) -> (f32, f32) {
-        let metrics = Metrics::new(font_size, font_size * 1.2);
-        let mut buffer = Buffer::new(&mut self.font_system, metrics);
-
+        let metrics = Metrics::new(font_size, font_size * LINE_SPACING);
         let attrs = Attrs::new().family(Family::Monospace);
-        buffer.set_text(&mut self.font_system, text, attrs, Shaping::Advanced);
-        buffer.shape_until_scroll(&mut self.font_system, false);
+        let buffer = self.shape(text, metrics, attrs);
 
         let mut width = 0.0f32;
         let mut height = 0.0f32;
@@ -195,9 +308,10 @@ impl TextRenderer {
     /// Returns true if at least one font can render this codepoint.
     pub fn has_glyph(&mut self, c: char) -> bool {
         let codepoint = c as u32;
-        let face_ids: Vec<_> = self.font_system.db().faces().map(|f| f.id).collect();
+        let font_system = &mut *lock(&self.font_system);
+        let face_ids: Vec<_> = font_system.db().faces().map(|f| f.id).collect();
         for face_id in face_ids {
-            if let Some(font) = self.font_system.get_font(face_id) {
+            if let Some(font) = font_system.get_font(face_id) {
                 let codepoints = font.unicode_codepoints();
                 if codepoints.binary_search(&codepoint).is_ok() {
                     return true;
@@ -208,8 +322,127 @@ impl TextRenderer {
     }
 }
 
+/// The x offset of each caret position in a shaped single line of `text`,
+/// ending with the end of the line
+fn line_offsets(buffer: &Buffer, text: &str) -> Vec<(usize, f32)> {
+    let mut offsets = Vec::new();
+    let mut end = 0.0f32;
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            offsets.push((glyph.start, glyph.x));
+        }
+        end = end.max(run.line_w);
+    }
+    offsets.sort_by_key(|&(index, _)| index);
+    offsets.dedup_by_key(|&mut (index, _)| index);
+    offsets.push((text.len(), end));
+    offsets
+}
+
+fn line_width(buffer: &Buffer) -> f32 {
+    buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0, f32::max)
+}
+
+/// Where to cut `text` so it fits in `max_width`, dropping trailing
+/// whitespace: the byte index and its x offset
+fn elision(text: &str, offsets: &[(usize, f32)], max_width: f32) -> (usize, f32) {
+    let end = offsets
+        .iter()
+        .take_while(|&&(_, x)| x <= max_width)
+        .last()
+        .map_or(0, |&(index, _)| index);
+    let end = text[..end].trim_end().len();
+    let x = offsets
+        .iter()
+        .find(|&&(index, _)| index >= end)
+        .map_or(0.0, |&(_, x)| x);
+    (end, x)
+}
+
+fn align_x(rect: Rect, width: f32, align: HAlign) -> i32 {
+    match align {
+        HAlign::Left => rect.x,
+        HAlign::Center => rect.x + (rect.width as i32 - width as i32) / 2,
+        HAlign::Right => rect.x + rect.width as i32 - width as i32,
+    }
+}
+
+fn align_y(rect: Rect, height: f32, align: VAlign) -> i32 {
+    match align {
+        VAlign::Top => rect.y,
+        VAlign::Center => rect.y + (rect.height as i32 - height as i32) / 2,
+        VAlign::Bottom => rect.y + rect.height as i32 - height as i32,
+    }
+}
+
 impl Default for TextRenderer {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_edit::{EditBuffer, Motion};
+
+    #[test]
+    fn test_renderers_can_move_between_threads() {
+        let mut text = TextRenderer::new();
+        let width = std::thread::spawn(move || text.measure_text("moved", 14.0).0)
+            .join()
+            .unwrap();
+        assert_eq!(width, TextRenderer::new().measure_text("moved", 14.0).0);
+    }
+
+    #[test]
+    fn test_renderers_and_editors_share_fonts() {
+        let mut first = TextRenderer::new();
+        let mut second = TextRenderer::new();
+        let mut buffer = EditBuffer::new(false);
+        assert!(Arc::ptr_eq(&first.font_system, &second.font_system));
+
+        // Interleaved use mustn't deadlock or shape differently
+        let (width, _) = first.measure_text("shared", 14.0);
+        buffer.set_text("shared");
+        assert_eq!(second.measure_text("shared", 14.0).0, width);
+        buffer.move_cursor(Motion::Start, false);
+        buffer.insert("!");
+        assert_eq!(buffer.text(), "!shared");
+        assert_eq!(first.measure_text("shared", 14.0).0, width);
+    }
+
+    #[test]
+    fn test_elision_drops_trailing_whitespace() {
+        let text = "ab cd";
+        let offsets: Vec<_> = (0..=text.len()).map(|i| (i, i as f32 * 5.0)).collect();
+        assert_eq!(elision(text, &offsets, 16.0), (2, 10.0));
+        assert_eq!(elision(text, &offsets, 100.0), (5, 25.0));
+        assert_eq!(elision(text, &offsets, 2.0), (0, 0.0));
+    }
+
+    #[test]
+    fn test_elided_text_stays_in_rect() {
+        let mut text = TextRenderer::new();
+        let name = "a long file name.txt";
+        let (width, _) = text.measure_text(name, 14.0);
+        let rect = Rect::new(0, 0, (width / 2.0) as u32, 20);
+        let mut data = vec![0u8; 200 * 20 * 4];
+        let mut canvas = Canvas::new(&mut data, 200, 20);
+        text.draw_text_elided(
+            &mut canvas,
+            name,
+            rect,
+            14.0,
+            Color::rgb(0, 0, 0),
+            HAlign::Left,
+            VAlign::Center,
+        );
+        let inked = |x: usize| (0..20).any(|y| data[(y * 200 + x) * 4 + 3] != 0);
+        let right = (0..200).rev().find(|&x| inked(x));
+        assert!(right.is_some_and(|x| x < rect.width as usize));
+    }
+}
@@ -0,0 +1,440 @@
+//! The editing model behind `TextInput` and `TextArea`: text, caret,
+//! selection and undo history, independent of how the text is drawn.
+//!
+//! Editing is done by cosmic-text's `Editor`, which moves by grapheme and
+//! word; this wraps it in byte offsets into the text, which always sit on
+//! char boundaries.
+
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use cosmic_text::{
+    Action, Attrs, Buffer, Change, Cursor, Edit, Editor, Family, FontSystem, Metrics, Selection,
+    Shaping, Wrap,
+};
+
+use crate::text::{self, FONT_SIZE, LINE_HEIGHT};
+
+/// How many edits undo remembers
+const UNDO_LIMIT: usize = 200;
+
+/// Where a caret motion goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Motion {
+    /// One grapheme back or forward
+    Left,
+    Right,
+    /// To the start of the previous word or the end of the next one
+    WordLeft,
+    WordRight,
+    /// Up (negative) or down this many lines, keeping the column
+    Lines(isize),
+    LineStart,
+    LineEnd,
+    Start,
+    End,
+}
+
+/// Edits of the same kind in a row undo together, like a typed word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EditKind {
+    Typing,
+    Deleting,
+    Other,
+}
+
+/// An undoable edit, with the caret and selection from either side of it
+struct UndoStep {
+    change: Change,
+    before: (Cursor, Selection),
+    after: (Cursor, Selection),
+}
+
+pub(crate) struct EditBuffer {
+    editor: Editor<'static>,
+    font_system: Arc<Mutex<FontSystem>>,
+    /// The editor's lines joined with newlines, kept in step with each edit
+    text: String,
+    multiline: bool,
+    undo: Vec<UndoStep>,
+    redo: Vec<UndoStep>,
+    /// The kind of the last edit, or None if the caret moved since
+    last_edit: Option<EditKind>,
+}
+
+impl EditBuffer {
+    pub(crate) fn new(multiline: bool) -> Self {
+        let font_system = text::font_system();
+        let editor = {
+            let font_system = &mut *text::lock(&font_system);
+            let mut buffer = Buffer::new(font_system, Metrics::new(FONT_SIZE, LINE_HEIGHT));
+            buffer.set_wrap(font_system, Wrap::None);
+            Editor::new(buffer)
+        };
+        Self {
+            editor,
+            font_system,
+            text: String::new(),
+            multiline,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, putting the caret at the end and forgetting history
+    pub(crate) fn set_text(&mut self, text: &str) {
+        let text = self.clean(text);
+        let font_system = &mut *text::lock(&self.font_system);
+        self.editor.with_buffer_mut(|buffer| {
+            let attrs = Attrs::new().family(Family::Monospace);
+            buffer.set_text(font_system, &text, attrs, Shaping::Advanced);
+        });
+        self.text = text;
+        self.editor.set_selection(Selection::None);
+        self.editor.set_cursor(self.cursor_at(self.text.len()));
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = None;
+    }
+
+    pub(crate) fn cursor(&self) -> usize {
+        self.position(self.editor.cursor())
+    }
+
+    /// The selected range, if it isn't empty
+    pub(crate) fn selection(&self) -> Option<Range<usize>> {
+        let (start, end) = self.editor.selection_bounds()?;
+        let range = self.position(start)..self.position(end);
+        (!range.is_empty()).then_some(range)
+    }
+
+    pub(crate) fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|range| &self.text[range])
+    }
+
+    /// Move the caret to `pos`, extending the selection from where it was if
+    /// `extend` is set
+    pub(crate) fn set_cursor(&mut self, pos: usize, extend: bool) {
+        self.start_selection(extend);
+        self.editor.set_cursor(self.cursor_at(pos));
+        self.forget_column();
+        self.last_edit = None;
+    }
+
+    pub(crate) fn select_all(&mut self) {
+        self.editor
+            .set_selection(Selection::Normal(Cursor::new(0, 0)));
+        self.editor.set_cursor(self.cursor_at(self.text.len()));
+        self.forget_column();
+        self.last_edit = None;
+    }
+
+    pub(crate) fn move_cursor(&mut self, motion: Motion, extend: bool) {
+        // Left and Right without Shift collapse a selection to its edge
+        if !extend && let Some(range) = self.selection() {
+            match motion {
+                Motion::Left => return self.set_cursor(range.start, false),
+                Motion::Right => return self.set_cursor(range.end, false),
+                _ => {}
+            }
+        }
+
+        self.start_selection(extend);
+        self.motion(motion);
+        self.last_edit = None;
+    }
+
+    /// Insert `text` at the caret, replacing the selection. Newlines become
+    /// spaces in single-line buffers.
+    pub(crate) fn insert(&mut self, text: &str) {
+        let text = self.clean(text);
+        if text.is_empty() && self.selection().is_none() {
+            return;
+        }
+        let typing = text.chars().count() == 1 && self.selection().is_none();
+        let kind = if typing {
+            EditKind::Typing
+        } else {
+            EditKind::Other
+        };
+        self.edit(kind, |editor| editor.insert_string(&text, None));
+    }
+
+    /// Delete the selection, or from the caret to where `motion` would take
+    /// it. Returns whether anything was deleted.
+    pub(crate) fn delete(&mut self, motion: Motion) -> bool {
+        let kind = if self.selection().is_some() {
+            EditKind::Other
+        } else {
+            let anchor = self.editor.cursor();
+            self.editor.set_selection(Selection::Normal(anchor));
+            self.motion(motion);
+            EditKind::Deleting
+        };
+        if self.selection().is_none() {
+            self.editor.set_selection(Selection::None);
+            return false;
+        }
+        self.edit(kind, |editor| {
+            editor.delete_selection();
+        });
+        true
+    }
+
+    /// Remove and return the selected text
+    pub(crate) fn cut(&mut self) -> Option<String> {
+        let text = self.selected_text()?.to_string();
+        self.delete(Motion::Left);
+        Some(text)
+    }
+
+    pub(crate) fn undo(&mut self) -> bool {
+        let Some(step) = self.undo.pop() else {
+            return false;
+        };
+        let mut change = step.change.clone();
+        change.reverse();
+        self.restore(&change, step.before);
+        self.redo.push(step);
+        true
+    }
+
+    pub(crate) fn redo(&mut self) -> bool {
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        self.restore(&step.change, step.after);
+        self.undo.push(step);
+        true
+    }
+
+    /// The range of each line, without its newline
+    pub(crate) fn lines(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let mut start = 0;
+        self.text.split('\n').map(move |line| {
+            let range = start..start + line.len();
+            start = range.end + 1;
+            range
+        })
+    }
+
+    /// The index and range of the line holding `pos`
+    pub(crate) fn line_at(&self, pos: usize) -> (usize, Range<usize>) {
+        let index = self.text[..pos].matches('\n').count();
+        let range = self.lines().nth(index).unwrap_or(0..0);
+        (index, range)
+    }
+
+    /// The editor cursor at the char boundary at or before `pos`
+    fn cursor_at(&self, pos: usize) -> Cursor {
+        let mut pos = pos.min(self.text.len());
+        while !self.text.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        let (line, range) = self.line_at(pos);
+        Cursor::new(line, pos - range.start)
+    }
+
+    /// The byte offset of an editor cursor
+    fn position(&self, cursor: Cursor) -> usize {
+        self.lines()
+            .nth(cursor.line)
+            .map_or(self.text.len(), |range| {
+                range.start + cursor.index.min(range.len())
+            })
+    }
+
+    /// Anchor a selection at the caret if `extend` is set and there isn't
+    /// one already, or drop the selection if it isn't
+    fn start_selection(&mut self, extend: bool) {
+        if !extend {
+            self.editor.set_selection(Selection::None);
+        } else if self.editor.selection() == Selection::None {
+            self.editor
+                .set_selection(Selection::Normal(self.editor.cursor()));
+        }
+    }
+
+    fn motion(&mut self, motion: Motion) {
+        use cosmic_text::Motion as Move;
+
+        let moves = match motion {
+            Motion::Left => vec![Move::Previous],
+            Motion::Right => vec![Move::Next],
+            Motion::WordLeft => vec![Move::PreviousWord],
+            Motion::WordRight => vec![Move::NextWord],
+            Motion::Lines(delta) => {
+                let line = self.editor.cursor().line;
+                let last = self.lines().count() - 1;
+                let steps = if delta < 0 {
+                    line.min(delta.unsigned_abs())
+                } else {
+                    (last - line).min(delta as usize)
+                };
+                match (steps, delta < 0) {
+                    // Moving past the first or last line goes to its end
+                    (0, true) => vec![Move::ParagraphStart],
+                    (0, false) => vec![Move::ParagraphEnd],
+                    (_, true) => vec![Move::Up; steps],
+                    (_, false) => vec![Move::Down; steps],
+                }
+            }
+            Motion::LineStart => vec![Move::ParagraphStart],
+            Motion::LineEnd => vec![Move::ParagraphEnd],
+            Motion::Start => vec![Move::BufferStart],
+            Motion::End => vec![Move::BufferEnd],
+        };
+        let font_system = &mut *text::lock(&self.font_system);
+        for motion in moves {
+            self.editor.action(font_system, Action::Motion(motion));
+        }
+    }
+
+    /// The editor keeps the column Up and Down started from until another
+    /// motion; forget it when the caret is placed directly
+    fn forget_column(&mut self) {
+        let cursor = self.editor.cursor();
+        self.motion(Motion::LineStart);
+        self.editor.set_cursor(cursor);
+    }
+
+    fn clean(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.multiline {
+            text
+        } else {
+            text.replace('\n', " ")
+        }
+    }
+
+    /// Make an edit, recording it for undo
+    fn edit(&mut self, kind: EditKind, edit: impl FnOnce(&mut Editor<'static>)) {
+        let before = (self.editor.cursor(), self.editor.selection());
+        self.editor.start_change();
+        edit(&mut self.editor);
+        let change = self.editor.finish_change().unwrap_or_default();
+        self.editor.set_selection(Selection::None);
+        self.sync();
+        let after = (self.editor.cursor(), self.editor.selection());
+
+        let continues = kind != EditKind::Other && self.last_edit == Some(kind);
+        match self.undo.last_mut() {
+            Some(step) if continues => {
+                step.change.items.extend(change.items);
+                step.after = after;
+            }
+            _ => {
+                self.undo.push(UndoStep {
+                    change,
+                    before,
+                    after,
+                });
+                if self.undo.len() > UNDO_LIMIT {
+                    self.undo.remove(0);
+                }
+            }
+        }
+        self.redo.clear();
+        self.last_edit = Some(kind);
+    }
+
+    fn restore(&mut self, change: &Change, (cursor, selection): (Cursor, Selection)) {
+        self.editor.apply_change(change);
+        self.editor.set_cursor(cursor);
+        self.editor.set_selection(selection);
+        self.sync();
+        self.last_edit = None;
+    }
+
+    /// Refresh `text` from the editor and reset the column after an edit
+    fn sync(&mut self) {
+        self.text = self.editor.with_buffer(|buffer| {
+            let lines: Vec<_> = buffer.lines.iter().map(|line| line.text()).collect();
+            lines.join("\n")
+        });
+        self.forget_column();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(text: &str, multiline: bool) -> EditBuffer {
+        let mut buffer = EditBuffer::new(multiline);
+        buffer.set_text(text);
+        buffer
+    }
+
+    #[test]
+    fn test_grapheme_and_word_motion() {
+        // "é" as e + combining acute is one grapheme
+        let mut b = buffer("cafe\u{301} au lait", false);
+        b.move_cursor(Motion::Start, false);
+        b.move_cursor(Motion::WordRight, false);
+        assert_eq!(b.cursor(), 6);
+        b.move_cursor(Motion::Left, false);
+        assert_eq!(b.cursor(), 3);
+        b.move_cursor(Motion::WordRight, false);
+        b.move_cursor(Motion::WordRight, false);
+        assert_eq!(b.cursor(), 9);
+        b.move_cursor(Motion::WordLeft, true);
+        assert_eq!(b.selected_text(), Some("au"));
+        // Right without Shift collapses the selection to its end
+        b.move_cursor(Motion::Right, false);
+        assert_eq!((b.cursor(), b.selection()), (9, None));
+    }
+
+    #[test]
+    fn test_line_motion_keeps_column() {
+        let mut b = buffer("first line\nab\nthird line", true);
+        b.set_cursor(8, false);
+        b.move_cursor(Motion::Lines(1), false);
+        assert_eq!(b.cursor(), 13);
+        b.move_cursor(Motion::Lines(1), false);
+        assert_eq!(b.cursor(), 22);
+        b.move_cursor(Motion::Lines(-5), true);
+        assert_eq!(b.selected_text(), Some("ne\nab\nthird li"));
+        b.move_cursor(Motion::Lines(-1), false);
+        assert_eq!(b.cursor(), 0);
+        assert_eq!(b.lines().collect::<Vec<_>>(), [0..10, 11..13, 14..24]);
+    }
+
+    #[test]
+    fn test_editing_and_undo() {
+        let mut b = buffer("", false);
+        for c in ["h", "i", " ", "y", "o", "u"] {
+            b.insert(c);
+        }
+        b.move_cursor(Motion::WordLeft, true);
+        b.insert("there\nfriend");
+        assert_eq!(b.text(), "hi there friend");
+        b.delete(Motion::WordLeft);
+        b.delete(Motion::Left);
+        assert_eq!(b.text(), "hi there");
+
+        // The two deletions, the paste and the typing each undo in one step
+        assert!(b.undo());
+        assert_eq!(b.text(), "hi there friend");
+        assert!(b.undo());
+        assert_eq!(b.text(), "hi you");
+        assert_eq!(b.selected_text(), Some("you"));
+        assert!(b.undo());
+        assert_eq!(b.text(), "");
+        assert!(!b.undo());
+        assert!(b.redo());
+        assert!(b.redo());
+        assert_eq!(b.text(), "hi there friend");
+
+        b.set_cursor(3, false);
+        b.select_all();
+        assert_eq!(b.cut().as_deref(), Some("hi there friend"));
+        assert_eq!(b.text(), "");
+        assert!(!b.redo());
+    }
+}
@@ -0,0 +1,887 @@
+//! Editable text: single-line `TextInput` and multi-line `TextArea`.
+//!
+//! Editing happens in an `EditBuffer`; layout measures each line's caret
+//! positions with the `TextRenderer` so clicks, the caret and selections line
+//! up with the shaped text. Content that overflows scrolls to keep the caret
+//! in view and with the scroll wheel.
+
+use crate::clipboard::Selection;
+use crate::controls::{ACCENT, BORDER, CONTROL, CONTROL_DISABLED, TEXT, TEXT_DISABLED};
+use crate::input::{Key, KeyEvent, KeyState, PointerButton, PointerEvent, PointerEventKind};
+use crate::render::Rgba;
+use crate::text::{FONT_SIZE, LINE_HEIGHT};
+use crate::text_edit::{EditBuffer, Motion};
+use crate::widget::{
+    Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetEvent, WidgetEvents,
+    WidgetId,
+};
+
+/// Space between the field's border and its text
+const PADDING: u32 = 6;
+const CARET_WIDTH: f32 = 1.5;
+const SELECTION: Rgba = Rgba::new(80, 150, 255, 110);
+const CARET: Rgba = Rgba::rgb(230, 230, 230);
+const PLACEHOLDER: cosmic_text::Color = cosmic_text::Color::rgb(130, 130, 136);
+/// Shown for each character of a password
+const MASK: char = '•';
+
+type TextCallback = Box<dyn FnMut(&str)>;
+type CopyCallback = Box<dyn FnMut(Selection, &str)>;
+
+/// Editing, layout and rendering shared by `TextInput` and `TextArea`
+struct TextEditor {
+    id: WidgetId,
+    buffer: EditBuffer,
+    placeholder: String,
+    password: bool,
+    enabled: bool,
+    focused: bool,
+    /// Caret positions of each displayed line, from the last layout
+    offsets: Vec<Vec<(usize, f32)>>,
+    line_height: f32,
+    /// The text area inside the padding, from the last layout
+    viewport: Size,
+    scroll_x: f32,
+    scroll_y: f32,
+    /// Scroll the caret into view at the next layout
+    reveal_caret: bool,
+    dragging: bool,
+    /// The last text offered as the primary selection
+    primary: Option<String>,
+    on_change: Option<TextCallback>,
+    on_submit: Option<TextCallback>,
+    on_copy: Option<CopyCallback>,
+    on_paste_request: Option<Box<dyn FnMut(Selection)>>,
+    events: Option<WidgetEvents>,
+}
+
+impl TextEditor {
+    fn new(id: WidgetId, multiline: bool) -> Self {
+        Self {
+            id,
+            buffer: EditBuffer::new(multiline),
+            placeholder: String::new(),
+            password: false,
+            enabled: true,
+            focused: false,
+            offsets: Vec::new(),
+            line_height: LINE_HEIGHT,
+            viewport: Size::default(),
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            reveal_caret: true,
+            dragging: false,
+            primary: None,
+            on_change: None,
+            on_submit: None,
+            on_copy: None,
+            on_paste_request: None,
+            events: None,
+        }
+    }
+
+    fn emit(&self, event: WidgetEvent) {
+        if let Some(events) = &self.events {
+            events.push(event);
+        }
+    }
+
+    fn changed(&mut self) {
+        self.reveal_caret = true;
+        let text = self.buffer.text();
+        if let Some(f) = &mut self.on_change {
+            f(text);
+        }
+        self.emit(WidgetEvent::TextChanged {
+            id: self.id,
+            text: text.to_string(),
+        });
+    }
+
+    fn submit(&mut self) {
+        let text = self.buffer.text();
+        if let Some(f) = &mut self.on_submit {
+            f(text);
+        }
+        self.emit(WidgetEvent::Submitted {
+            id: self.id,
+            text: text.to_string(),
+        });
+    }
+
+    fn copy(&mut self, selection: Selection, text: String) {
+        // Passwords never leave the field
+        if self.password {
+            return;
+        }
+        if let Some(f) = &mut self.on_copy {
+            f(selection, &text);
+        }
+        self.emit(WidgetEvent::Copy {
+            id: self.id,
+            selection,
+            text,
+        });
+    }
+
+    fn request_paste(&mut self, selection: Selection) {
+        if let Some(f) = &mut self.on_paste_request {
+            f(selection);
+        }
+        self.emit(WidgetEvent::PasteRequested {
+            id: self.id,
+            selection,
+        });
+    }
+
+    /// Offer a new, non-empty selection as the primary selection
+    fn update_primary(&mut self) {
+        let selected = self.buffer.selected_text().map(str::to_string);
+        if selected.is_some() && selected != self.primary {
+            self.primary = selected.clone();
+            self.copy(Selection::Primary, selected.unwrap_or_default());
+        }
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.buffer.set_text(text);
+        self.reveal_caret = true;
+    }
+
+    fn move_cursor(&mut self, motion: Motion, extend: bool) {
+        self.buffer.move_cursor(motion, extend);
+        self.reveal_caret = true;
+        if extend {
+            self.update_primary();
+        }
+    }
+
+    fn paste(&mut self, text: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.buffer.insert(text);
+        self.changed();
+        true
+    }
+
+    /// The text as displayed, with passwords masked
+    fn display_lines(&self) -> Vec<String> {
+        let text = self.buffer.text();
+        if self.password {
+            vec![text.chars().map(|_| MASK).collect()]
+        } else {
+            text.split('\n').map(str::to_string).collect()
+        }
+    }
+
+    /// The displayed line and byte offset within it of text position `pos`
+    fn display_position(&self, pos: usize) -> (usize, usize) {
+        let (line, range) = self.buffer.line_at(pos);
+        let column = &self.buffer.text()[range.start..pos];
+        if self.password {
+            (line, column.chars().count() * MASK.len_utf8())
+        } else {
+            (line, column.len())
+        }
+    }
+
+    /// The text position of byte `offset` in displayed line `line`
+    fn text_position(&self, line: usize, offset: usize) -> usize {
+        let Some(range) = self.buffer.lines().nth(line) else {
+            return self.buffer.text().len();
+        };
+        let text = &self.buffer.text()[range.clone()];
+        if self.password {
+            let index = offset / MASK.len_utf8();
+            text.char_indices()
+                .nth(index)
+                .map_or(range.end, |(i, _)| range.start + i)
+        } else {
+            range.start + offset.min(text.len())
+        }
+    }
+
+    /// x of the caret position at `offset` in displayed line `line`
+    fn caret_x(&self, line: usize, offset: usize) -> f32 {
+        self.offsets
+            .get(line)
+            .and_then(|offsets| offsets.iter().rev().find(|&&(i, _)| i <= offset))
+            .map_or(0.0, |&(_, x)| x)
+    }
+
+    fn content_width(&self) -> f32 {
+        self.offsets
+            .iter()
+            .filter_map(|offsets| offsets.last().map(|&(_, x)| x))
+            .fold(0.0, f32::max)
+    }
+
+    /// The text position nearest a point, in surface coordinates
+    fn hit(&self, bounds: Rect, x: f64, y: f64) -> usize {
+        let inner = inner_bounds(bounds);
+        let local_y = y as f32 - inner.y as f32 + self.scroll_y;
+        let last_line = self.offsets.len().saturating_sub(1);
+        let line = ((local_y / self.line_height).max(0.0) as usize).min(last_line);
+        let local_x = x as f32 - inner.x as f32 + self.scroll_x;
+        let offset = self
+            .offsets
+            .get(line)
+            .and_then(|offsets| {
+                offsets
+                    .iter()
+                    .min_by(|a, b| (a.1 - local_x).abs().total_cmp(&(b.1 - local_x).abs()))
+            })
+            .map_or(0, |&(i, _)| i);
+        self.text_position(line, offset)
+    }
+
+    fn clamp_scroll(&mut self) {
+        let max_x = (self.content_width() + CARET_WIDTH - self.viewport.width as f32).max(0.0);
+        let content_height = self.offsets.len() as f32 * self.line_height;
+        let max_y = (content_height - self.viewport.height as f32).max(0.0);
+        self.scroll_x = self.scroll_x.clamp(0.0, max_x);
+        self.scroll_y = self.scroll_y.clamp(0.0, max_y);
+    }
+
+    fn layout(&mut self, viewport: Size, ctx: &mut LayoutContext) {
+        self.line_height = LINE_HEIGHT;
+        self.viewport = viewport;
+        self.offsets = self
+            .display_lines()
+            .iter()
+            .map(|line| ctx.text.caret_offsets(line, FONT_SIZE))
+            .collect();
+
+        if std::mem::take(&mut self.reveal_caret) {
+            let (line, offset) = self.display_position(self.buffer.cursor());
+            let x = self.caret_x(line, offset);
+            let (width, height) = (viewport.width as f32, viewport.height as f32);
+            if x < self.scroll_x {
+                self.scroll_x = x;
+            } else if x + CARET_WIDTH > self.scroll_x + width {
+                self.scroll_x = x + CARET_WIDTH - width;
+            }
+            let top = line as f32 * self.line_height;
+            if top < self.scroll_y {
+                self.scroll_y = top;
+            } else if top + self.line_height > self.scroll_y + height {
+                self.scroll_y = top + self.line_height - height;
+            }
+        }
+        self.clamp_scroll();
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        let (x, y, w, h) = (
+            bounds.x as f32,
+            bounds.y as f32,
+            bounds.width as f32,
+            bounds.height as f32,
+        );
+        let border = if self.focused { ACCENT } else { BORDER };
+        let fill = if self.enabled {
+            CONTROL
+        } else {
+            CONTROL_DISABLED
+        };
+        ctx.canvas
+            .fill_rounded_rect(x, y, w, h, 4.0, border.to_color());
+        ctx.canvas
+            .fill_rounded_rect(x + 1.0, y + 1.0, w - 2.0, h - 2.0, 3.0, fill.to_color());
+
+        let clip = inner_bounds(bounds);
+        let origin_x = clip.x as f32 - self.scroll_x;
+        let origin_y = clip.y as f32 - self.scroll_y;
+        let line_top = |line: usize| origin_y + line as f32 * self.line_height;
+
+        if self.buffer.text().is_empty() && !self.placeholder.is_empty() {
+            ctx.text.draw_text_clipped(
+                ctx.canvas,
+                &self.placeholder,
+                origin_x as i32,
+                origin_y as i32,
+                FONT_SIZE,
+                PLACEHOLDER,
+                clip,
+            );
+        }
+
+        if let Some(range) = self.buffer.selection() {
+            let (start_line, start) = self.display_position(range.start);
+            let (end_line, end) = self.display_position(range.end);
+            for line in start_line..=end_line {
+                let x0 = if line == start_line {
+                    self.caret_x(line, start)
+                } else {
+                    0.0
+                };
+                // A selected newline shows as a sliver past the line's end
+                let x1 = if line == end_line {
+                    self.caret_x(line, end)
+                } else {
+                    self.caret_x(line, usize::MAX) + FONT_SIZE / 3.0
+                };
+                let rect = Rect::new(
+                    (origin_x + x0) as i32,
+                    line_top(line) as i32,
+                    (x1 - x0).max(0.0).ceil() as u32,
+                    self.line_height.ceil() as u32,
+                );
+                if let Some(rect) = rect.intersect(clip) {
+                    ctx.canvas.fill_rect(
+                        rect.x as f32,
+                        rect.y as f32,
+                        rect.width as f32,
+                        rect.height as f32,
+                        SELECTION.to_color(),
+                    );
+                }
+            }
+        }
+
+        let color = if self.enabled { TEXT } else { TEXT_DISABLED };
+        for (i, line) in self.display_lines().iter().enumerate() {
+            let top = line_top(i);
+            if top + self.line_height < clip.y as f32 || top > (clip.y + clip.height as i32) as f32
+            {
+                continue;
+            }
+            ctx.text.draw_text_clipped(
+                ctx.canvas,
+                line,
+                origin_x as i32,
+                top as i32,
+                FONT_SIZE,
+                color,
+                clip,
+            );
+        }
+
+        if self.focused && self.enabled {
+            let (line, offset) = self.display_position(self.buffer.cursor());
+            let caret = Rect::new(
+                (origin_x + self.caret_x(line, offset)) as i32,
+                line_top(line) as i32,
+                CARET_WIDTH.ceil() as u32,
+                self.line_height as u32,
+            );
+            if let Some(caret) = caret.intersect(clip) {
+                ctx.canvas.fill_rect(
+                    caret.x as f32,
+                    caret.y as f32,
+                    CARET_WIDTH,
+                    caret.height as f32,
+                    CARET.to_color(),
+                );
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent, multiline: bool) -> bool {
+        if !self.enabled || event.state != KeyState::Pressed {
+            return false;
+        }
+        let mods = event.modifiers;
+        let (ctrl, shift) = (mods.ctrl, mods.shift);
+        let page = ((self.viewport.height as f32 / self.line_height) as isize).max(1);
+
+        let motion = match event.key {
+            Key::Left if ctrl => Some(Motion::WordLeft),
+            Key::Right if ctrl => Some(Motion::WordRight),
+            Key::Left => Some(Motion::Left),
+            Key::Right => Some(Motion::Right),
+            Key::Up if multiline => Some(Motion::Lines(-1)),
+            Key::Down if multiline => Some(Motion::Lines(1)),
+            Key::PageUp if multiline => Some(Motion::Lines(-page)),
+            Key::PageDown if multiline => Some(Motion::Lines(page)),
+            Key::Home if ctrl => Some(Motion::Start),
+            Key::End if ctrl => Some(Motion::End),
+            Key::Home => Some(Motion::LineStart),
+            Key::End => Some(Motion::LineEnd),
+            _ => None,
+        };
+        if let Some(motion) = motion {
+            self.move_cursor(motion, shift);
+            return true;
+        }
+
+        match event.key {
+            Key::Backspace | Key::Delete => {
+                let motion = match (event.key, ctrl) {
+                    (Key::Backspace, true) => Motion::WordLeft,
+                    (Key::Backspace, false) => Motion::Left,
+                    (_, true) => Motion::WordRight,
+                    (_, false) => Motion::Right,
+                };
+                if self.buffer.delete(motion) {
+                    self.changed();
+                }
+                true
+            }
+            Key::Enter | Key::KpEnter if multiline => self.paste("\n"),
+            Key::Enter | Key::KpEnter => {
+                self.submit();
+                true
+            }
+            Key::A if ctrl => {
+                self.buffer.select_all();
+                self.update_primary();
+                true
+            }
+            Key::C if ctrl => {
+                if let Some(text) = self.buffer.selected_text() {
+                    self.copy(Selection::Clipboard, text.to_string());
+                }
+                true
+            }
+            Key::X if ctrl => {
+                if !self.password
+                    && let Some(text) = self.buffer.cut()
+                {
+                    self.copy(Selection::Clipboard, text);
+                    self.changed();
+                }
+                true
+            }
+            Key::V if ctrl => {
+                self.request_paste(Selection::Clipboard);
+                true
+            }
+            Key::Insert if shift => {
+                self.request_paste(Selection::Clipboard);
+                true
+            }
+            Key::Z if ctrl && shift => {
+                self.redo();
+                true
+            }
+            Key::Z if ctrl => {
+                self.undo();
+                true
+            }
+            Key::Y if ctrl => {
+                self.redo();
+                true
+            }
+            _ if ctrl || mods.alt || mods.super_ => false,
+            _ => match &event.text {
+                Some(text) if !text.is_empty() && !text.chars().any(char::is_control) => {
+                    self.buffer.insert(text);
+                    self.changed();
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn undo(&mut self) -> bool {
+        let undone = self.buffer.undo();
+        if undone {
+            self.changed();
+        }
+        undone
+    }
+
+    fn redo(&mut self) -> bool {
+        let redone = self.buffer.redo();
+        if redone {
+            self.changed();
+        }
+        redone
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect, multiline: bool) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let inside = bounds.contains(event.x as i32, event.y as i32);
+        match event.kind {
+            PointerEventKind::Press(PointerButton::Left) if inside => {
+                let pos = self.hit(bounds, event.x, event.y);
+                self.buffer.set_cursor(pos, false);
+                self.dragging = true;
+                true
+            }
+            PointerEventKind::Motion if self.dragging => {
+                let pos = self.hit(bounds, event.x, event.y);
+                self.buffer.set_cursor(pos, true);
+                self.reveal_caret = true;
+                true
+            }
+            PointerEventKind::Release(PointerButton::Left) if self.dragging => {
+                self.dragging = false;
+                self.update_primary();
+                true
+            }
+            PointerEventKind::Leave => {
+                self.dragging = false;
+                false
+            }
+            PointerEventKind::Press(PointerButton::Middle) if inside => {
+                let pos = self.hit(bounds, event.x, event.y);
+                self.buffer.set_cursor(pos, false);
+                self.request_paste(Selection::Primary);
+                true
+            }
+            PointerEventKind::Scroll(scroll) if inside => {
+                // A single line scrolls sideways with either wheel
+                if multiline {
+                    self.scroll_x += scroll.dx as f32;
+                    self.scroll_y += scroll.dy as f32;
+                } else if scroll.dx != 0.0 {
+                    self.scroll_x += scroll.dx as f32;
+                } else {
+                    self.scroll_x += scroll.dy as f32;
+                }
+                self.clamp_scroll();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn inner_bounds(bounds: Rect) -> Rect {
+    Rect::new(
+        bounds.x + PADDING as i32,
+        bounds.y + PADDING as i32,
+        bounds.width.saturating_sub(PADDING * 2),
+        bounds.height.saturating_sub(PADDING * 2),
+    )
+}
+
+/// Builder and accessor methods shared by `TextInput` and `TextArea`
+macro_rules! editor_methods {
+    () => {
+        /// Initial text
+        pub fn text(mut self, text: &str) -> Self {
+            self.editor.set_text(text);
+            self
+        }
+
+        /// Shown dimmed while the field is empty
+        pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+            self.editor.placeholder = placeholder.into();
+            self
+        }
+
+        /// Called with the new text after each edit
+        pub fn on_change(mut self, f: impl FnMut(&str) + 'static) -> Self {
+            self.editor.on_change = Some(Box::new(f));
+            self
+        }
+
+        /// Called with text to put on the clipboard or primary selection
+        pub fn on_copy(mut self, f: impl FnMut(Selection, &str) + 'static) -> Self {
+            self.editor.on_copy = Some(Box::new(f));
+            self
+        }
+
+        /// Called when the user pastes; answer by passing the clipboard or
+        /// primary selection text to `paste`
+        pub fn on_paste_request(mut self, f: impl FnMut(Selection) + 'static) -> Self {
+            self.editor.on_paste_request = Some(Box::new(f));
+            self
+        }
+
+        /// Also report edits, copies and paste requests on `events`
+        pub fn events(mut self, events: &WidgetEvents) -> Self {
+            self.editor.events = Some(events.clone());
+            self
+        }
+
+        pub fn get_text(&self) -> &str {
+            self.editor.buffer.text()
+        }
+
+        /// Replace the text without reporting a change; clears undo history
+        pub fn set_text(&mut self, text: &str) {
+            self.editor.set_text(text);
+        }
+
+        pub fn selected_text(&self) -> Option<&str> {
+            self.editor.buffer.selected_text()
+        }
+
+        pub fn select_all(&mut self) {
+            self.editor.buffer.select_all();
+        }
+
+        pub fn undo(&mut self) -> bool {
+            self.editor.undo()
+        }
+
+        pub fn redo(&mut self) -> bool {
+            self.editor.redo()
+        }
+
+        pub fn set_enabled(&mut self, enabled: bool) {
+            self.editor.enabled = enabled;
+            self.editor.dragging = false;
+        }
+
+        pub fn is_enabled(&self) -> bool {
+            self.editor.enabled
+        }
+    };
+}
+
+/// `Widget` methods shared by `TextInput` and `TextArea`
+macro_rules! editor_widget {
+    ($multiline:expr) => {
+        fn id(&self) -> WidgetId {
+            self.editor.id
+        }
+
+        fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+            self.editor.render(bounds, ctx);
+        }
+
+        fn handle_key(&mut self, event: &KeyEvent) -> bool {
+            self.editor.handle_key(event, $multiline)
+        }
+
+        fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+            self.editor.handle_pointer(event, bounds, $multiline)
+        }
+
+        fn is_focusable(&self) -> bool {
+            self.editor.enabled
+        }
+
+        fn focus_changed(&mut self, focused: bool) {
+            self.editor.focused = focused;
+            self.editor.dragging = false;
+        }
+
+        fn paste(&mut self, text: &str) -> bool {
+            self.editor.paste(text)
+        }
+    };
+}
+
+/// A single-line text field. Enter submits it.
+pub struct TextInput {
+    editor: TextEditor,
+    width: u32,
+}
+
+impl TextInput {
+    pub fn new(id: WidgetId) -> Self {
+        Self {
+            editor: TextEditor::new(id, false),
+            width: 200,
+        }
+    }
+
+    editor_methods!();
+
+    /// Preferred width, within the layout constraints
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Show a mask character instead of each character, and never copy
+    pub fn password(mut self, password: bool) -> Self {
+        self.editor.password = password;
+        self
+    }
+
+    /// Called with the text when Enter is pressed
+    pub fn on_submit(mut self, f: impl FnMut(&str) + 'static) -> Self {
+        self.editor.on_submit = Some(Box::new(f));
+        self
+    }
+}
+
+impl Widget for TextInput {
+    editor_widget!(false);
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let height = LINE_HEIGHT.ceil() as u32 + PADDING * 2;
+        let size = Size::new(
+            self.width
+                .clamp(constraints.min_width, constraints.max_width),
+            height.clamp(constraints.min_height, constraints.max_height),
+        );
+        let viewport = inner_bounds(Rect::new(0, 0, size.width, size.height));
+        self.editor
+            .layout(Size::new(viewport.width, viewport.height), ctx);
+        size
+    }
+}
+
+/// A multi-line text editor
+pub struct TextArea {
+    editor: TextEditor,
+    width: u32,
+    rows: u32,
+}
+
+impl TextArea {
+    pub fn new(id: WidgetId) -> Self {
+        Self {
+            editor: TextEditor::new(id, true),
+            width: 300,
+            rows: 5,
+        }
+    }
+
+    editor_methods!();
+
+    /// Preferred width, within the layout constraints
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Preferred height in lines of text
+    pub fn rows(mut self, rows: u32) -> Self {
+        self.rows = rows.max(1);
+        self
+    }
+}
+
+impl Widget for TextArea {
+    editor_widget!(true);
+
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
+        let height = (LINE_HEIGHT * self.rows as f32).ceil() as u32 + PADDING * 2;
+        let size = Size::new(
+            self.width
+                .clamp(constraints.min_width, constraints.max_width),
+            height.clamp(constraints.min_height, constraints.max_height),
+        );
+        let viewport = inner_bounds(Rect::new(0, 0, size.width, size.height));
+        self.editor
+            .layout(Size::new(viewport.width, viewport.height), ctx);
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Modifiers, make_key_event};
+    use crate::text::TextRenderer;
+
+    fn ctrl(shift: bool) -> Modifiers {
+        Modifiers {
+            ctrl: true,
+            shift,
+            ..Default::default()
+        }
+    }
+
+    fn type_text(widget: &mut dyn Widget, text: &str) {
+        for c in text.chars() {
+            let s = c.to_string();
+            widget.handle_key(&make_key_event(
+                Key::Char(c),
+                Some(&s),
+                Modifiers::default(),
+            ));
+        }
+    }
+
+    #[test]
+    fn test_text_input_editing() {
+        let events = WidgetEvents::new();
+        let mut input = TextInput::new(WidgetId(1)).events(&events);
+        type_text(&mut input, "hello world");
+        assert_eq!(input.get_text(), "hello world");
+
+        input.handle_key(&make_key_event(Key::Backspace, None, ctrl(false)));
+        input.handle_key(&make_key_event(Key::Left, None, ctrl(true)));
+        assert_eq!(input.selected_text(), Some("hello "));
+        input.handle_key(&make_key_event(Key::X, None, ctrl(false)));
+        input.handle_key(&make_key_event(Key::Z, None, ctrl(false)));
+        assert_eq!(input.get_text(), "hello ");
+        input.handle_key(&make_key_event(Key::Enter, None, Modifiers::default()));
+        input.handle_key(&make_key_event(Key::End, None, Modifiers::default()));
+        input.handle_key(&make_key_event(Key::V, None, ctrl(false)));
+        assert!(input.paste("bye\nnow"));
+        assert_eq!(input.get_text(), "hello bye now");
+
+        let emitted = events.poll();
+        let id = WidgetId(1);
+        assert!(emitted.contains(&WidgetEvent::Copy {
+            id,
+            selection: Selection::Primary,
+            text: "hello ".into()
+        }));
+        assert!(emitted.contains(&WidgetEvent::Copy {
+            id,
+            selection: Selection::Clipboard,
+            text: "hello ".into()
+        }));
+        assert!(emitted.contains(&WidgetEvent::Submitted {
+            id,
+            text: "hello ".into()
+        }));
+        assert!(emitted.contains(&WidgetEvent::PasteRequested {
+            id,
+            selection: Selection::Clipboard
+        }));
+        assert_eq!(
+            emitted.last(),
+            Some(&WidgetEvent::TextChanged {
+                id,
+                text: "hello bye now".into()
+            })
+        );
+
+        // Undo from code reports the change like Ctrl+Z does
+        assert!(input.undo());
+        assert_eq!(
+            events.poll(),
+            [WidgetEvent::TextChanged {
+                id,
+                text: "hello ".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_password_is_masked_and_never_copied() {
+        let events = WidgetEvents::new();
+        let mut input = TextInput::new(WidgetId(1))
+            .password(true)
+            .text("hunter2")
+            .events(&events);
+        input.select_all();
+        input.handle_key(&make_key_event(Key::C, None, ctrl(false)));
+        assert!(events.poll().is_empty());
+        assert_eq!(input.editor.display_lines(), ["•••••••"]);
+        assert_eq!(input.editor.display_position(3), (0, 3 * MASK.len_utf8()));
+        assert_eq!(input.editor.text_position(0, 3 * MASK.len_utf8()), 3);
+    }
+
+    #[test]
+    fn test_text_area_lines_and_scrolling() {
+        let mut area = TextArea::new(WidgetId(1)).rows(2).width(80);
+        type_text(&mut area, "one");
+        area.handle_key(&make_key_event(Key::Enter, None, Modifiers::default()));
+        type_text(&mut area, "two");
+        area.handle_key(&make_key_event(Key::Enter, None, Modifiers::default()));
+        type_text(&mut area, "three and a long line");
+        assert_eq!(area.get_text(), "one\ntwo\nthree and a long line");
+
+        let mut text = TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        let size = area.layout(Constraints::loose(500, 500), &mut ctx);
+        // The caret is on the third line, so the view scrolled down and right
+        assert!(area.editor.scroll_y > 0.0);
+        assert!(area.editor.content_width() > size.width as f32);
+        assert!(area.editor.scroll_x > 0.0);
+
+        area.handle_key(&make_key_event(Key::Home, None, ctrl(false)));
+        area.handle_key(&make_key_event(Key::Down, None, Modifiers::default()));
+        area.layout(Constraints::loose(500, 500), &mut ctx);
+        assert_eq!(area.editor.buffer.cursor(), 4);
+        assert_eq!(area.editor.scroll_x, 0.0);
+    }
+}
@@ -0,0 +1,885 @@
+//! `TreeView`: an expandable outline over a `TreeModel`.
+//!
+//! The view asks the model for a node's children only when the node is
+//! first expanded, and keeps the expanded part of the tree flattened into
+//! rows so that scrolling, selection and rendering work as in a `ListView`,
+//! touching only the rows in view.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Range;
+use std::time::Instant;
+
+use crate::controls::{TEXT, TEXT_DISABLED};
+use crate::input::{Key, KeyEvent, KeyState, PointerButton, PointerEvent, PointerEventKind};
+use crate::list::{
+    DEFAULT_ROW_HEIGHT, DEFAULT_WIDTH, DoubleClick, LABEL_INSET, Pick, RowHeights, RowSelection,
+    SelectionMode, TypeAhead, fill_row, outline_row,
+};
+use crate::render::Rgba;
+use crate::scroll::{Scroller, viewport_size};
+use crate::text::{FONT_SIZE, HAlign, VAlign};
+use crate::widget::{
+    Constraints, LayoutContext, Rect, RenderContext, Size, Widget, WidgetEvent, WidgetEvents,
+    WidgetId,
+};
+
+/// Indentation per level of depth
+const INDENT: u32 = 16;
+/// Width of the column holding the disclosure triangle
+const DISCLOSURE_WIDTH: u32 = 16;
+const DISCLOSURE: Rgba = Rgba::rgb(180, 180, 186);
+const LOADING_TEXT: &str = "Loading…";
+
+/// The data behind a `TreeView`, asked for children as nodes are expanded
+pub trait TreeModel {
+    /// Identifies a node, e.g. by path; must stay the same across reloads
+    type Node: Clone + Eq + Hash;
+
+    fn roots(&mut self) -> Vec<Self::Node>;
+
+    /// The children of `node`, or None while they're still loading; call
+    /// `TreeView::reload` once they're ready
+    fn children(&mut self, node: &Self::Node) -> Option<Vec<Self::Node>>;
+
+    /// Whether `node` can be expanded, without loading its children. Asked
+    /// for nodes as they're drawn or clicked and for expanded nodes as the
+    /// tree is rebuilt, so it should be cheap.
+    fn has_children(&self, node: &Self::Node) -> bool;
+
+    /// Text drawn for `node` unless there's a row renderer, and matched by
+    /// type-ahead search
+    fn label(&self, node: &Self::Node) -> String;
+}
+
+/// A node in view for a row renderer to draw
+pub struct TreeRow<'a, N> {
+    pub node: &'a N,
+    pub index: usize,
+    pub depth: usize,
+    /// The part of the row right of the indentation and disclosure triangle
+    pub bounds: Rect,
+    /// None if the node can't be expanded
+    pub expanded: Option<bool>,
+    pub selected: bool,
+    /// The keyboard cursor is on this row
+    pub current: bool,
+    pub hovered: bool,
+}
+
+/// A visible node: the expanded part of the tree, flattened
+struct Row<N> {
+    /// None for the placeholder shown while a node's children load
+    node: Option<N>,
+    depth: usize,
+    parent: Option<usize>,
+    expanded: bool,
+}
+
+type RowRenderer<N> = Box<dyn Fn(&TreeRow<N>, &mut RenderContext)>;
+type SelectCallback<N> = Box<dyn FnMut(&[N])>;
+type ActivateCallback<N> = Box<dyn FnMut(&N)>;
+type ExpandCallback<N> = Box<dyn FnMut(&N, bool)>;
+
+/// An outline of `model`'s nodes with disclosure triangles, keyboard and
+/// pointer expansion, selection and type-ahead search
+pub struct TreeView<M: TreeModel> {
+    id: WidgetId,
+    model: M,
+    roots: Option<Vec<M::Node>>,
+    /// Children loaded so far
+    children: HashMap<M::Node, Vec<M::Node>>,
+    expanded: HashSet<M::Node>,
+    rows: Vec<Row<M::Node>>,
+    heights: RowHeights,
+    selection: RowSelection,
+    type_ahead: TypeAhead,
+    scroller: Scroller,
+    double_click: DoubleClick,
+    render_row: Option<RowRenderer<M::Node>>,
+    hovered: Option<usize>,
+    focused: bool,
+    /// A row to scroll into view once the tree has been laid out
+    reveal: Option<usize>,
+    on_select: Option<SelectCallback<M::Node>>,
+    on_activate: Option<ActivateCallback<M::Node>>,
+    on_expand: Option<ExpandCallback<M::Node>>,
+    events: Option<WidgetEvents>,
+}
+
+impl<M: TreeModel> TreeView<M> {
+    /// Show `model`'s roots, all collapsed
+    pub fn new(id: WidgetId, model: M) -> Self {
+        let mut tree = Self {
+            id,
+            model,
+            roots: None,
+            children: HashMap::new(),
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            heights: RowHeights::Fixed(DEFAULT_ROW_HEIGHT),
+            selection: RowSelection::default(),
+            type_ahead: TypeAhead::default(),
+            scroller: Scroller::default(),
+            double_click: DoubleClick::default(),
+            render_row: None,
+            hovered: None,
+            focused: false,
+            reveal: None,
+            on_select: None,
+            on_activate: None,
+            on_expand: None,
+            events: None,
+        };
+        tree.rebuild();
+        tree
+    }
+
+    pub fn row_height(mut self, height: u32) -> Self {
+        self.heights = RowHeights::Fixed(height);
+        self
+    }
+
+    pub fn selection_mode(mut self, mode: SelectionMode) -> Self {
+        self.selection.set_mode(mode);
+        self
+    }
+
+    /// Draw each node in view. The tree has already drawn the row's
+    /// background, indentation and disclosure triangle.
+    pub fn render_row(
+        mut self,
+        render: impl Fn(&TreeRow<M::Node>, &mut RenderContext) + 'static,
+    ) -> Self {
+        self.render_row = Some(Box::new(render));
+        self
+    }
+
+    /// Called with the selected nodes, in display order, when the user
+    /// changes them
+    pub fn on_select(mut self, f: impl FnMut(&[M::Node]) + 'static) -> Self {
+        self.on_select = Some(Box::new(f));
+        self
+    }
+
+    /// Called when a node is double-clicked or Enter is pressed on it
+    pub fn on_activate(mut self, f: impl FnMut(&M::Node) + 'static) -> Self {
+        self.on_activate = Some(Box::new(f));
+        self
+    }
+
+    /// Called when the user expands (true) or collapses (false) a node
+    pub fn on_expand(mut self, f: impl FnMut(&M::Node, bool) + 'static) -> Self {
+        self.on_expand = Some(Box::new(f));
+        self
+    }
+
+    /// Also report selection changes and activations on `events`, by row
+    /// index; see `node_at`. Expanding or collapsing rows moves the selected
+    /// rows, which is reported again with their new indices.
+    pub fn events(mut self, events: &WidgetEvents) -> Self {
+        self.events = Some(events.clone());
+        self
+    }
+
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Changes to the nodes show after `reload` or `reload_all`
+    pub fn model_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+
+    /// Ask the model for `node`'s children again, e.g. once they've loaded
+    pub fn reload(&mut self, node: &M::Node) {
+        self.children.remove(node);
+        self.rebuild();
+    }
+
+    /// Ask the model for everything again, keeping expanded nodes expanded
+    pub fn reload_all(&mut self) {
+        self.roots = None;
+        self.children.clear();
+        self.rebuild();
+    }
+
+    pub fn is_expanded(&self, node: &M::Node) -> bool {
+        self.expanded.contains(node)
+    }
+
+    /// Expand `node`, loading its children if need be. It shows once its
+    /// ancestors are expanded too.
+    pub fn expand(&mut self, node: &M::Node) {
+        if self.expanded.insert(node.clone()) {
+            self.rebuild();
+        }
+    }
+
+    pub fn collapse(&mut self, node: &M::Node) {
+        if self.expanded.remove(node) {
+            self.rebuild();
+        }
+    }
+
+    /// The number of rows in view when scrolled through: nodes whose
+    /// ancestors are all expanded, plus loading placeholders
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The node on row `index`, until the next expand, collapse or reload
+    pub fn node_at(&self, index: usize) -> Option<&M::Node> {
+        self.rows.get(index)?.node.as_ref()
+    }
+
+    /// The row showing `node`, if its ancestors are expanded
+    pub fn row_of(&self, node: &M::Node) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|row| row.node.as_ref() == Some(node))
+    }
+
+    /// The selected nodes, in display order
+    pub fn selected(&self) -> Vec<M::Node> {
+        self.selection
+            .selected()
+            .into_iter()
+            .filter_map(|i| self.node_at(i).cloned())
+            .collect()
+    }
+
+    /// The node with the keyboard cursor
+    pub fn current(&self) -> Option<&M::Node> {
+        self.node_at(self.selection.current()?)
+    }
+
+    /// Select just `node`, moving the cursor to it and scrolling to it,
+    /// without reporting a change. Returns false if it isn't in view.
+    pub fn select(&mut self, node: &M::Node) -> bool {
+        let Some(index) = self.row_of(node) else {
+            return false;
+        };
+        self.selection.pick(index, Pick::Replace);
+        self.scroll_to_row(index);
+        true
+    }
+
+    /// Scroll as little as possible to show `node`, if its ancestors are expanded
+    pub fn scroll_to(&mut self, node: &M::Node) {
+        if let Some(index) = self.row_of(node) {
+            self.scroll_to_row(index);
+        }
+    }
+
+    /// The rows in view as of the last layout
+    pub fn visible_rows(&self) -> Range<usize> {
+        let top = self.scroller.offset().1.round() as u32;
+        let height = self.scroller.viewport().height;
+        self.heights.visible(top, height, self.rows.len())
+    }
+
+    fn scroll_to_row(&mut self, index: usize) {
+        self.reveal = Some(index);
+        if self.scroller.viewport().height > 0 {
+            self.reveal_row();
+        }
+    }
+
+    fn reveal_row(&mut self) {
+        if let Some(index) = self.reveal.take()
+            && index < self.rows.len()
+        {
+            let top = self.heights.start(index);
+            let rect = Rect::new(0, top as i32, 0, self.heights.height(index));
+            self.scroller.scroll_to_rect(rect);
+        }
+    }
+
+    /// Flatten the expanded tree into rows again, keeping the selection on
+    /// the same nodes
+    fn rebuild(&mut self) {
+        let roots = match &self.roots {
+            Some(roots) => roots.clone(),
+            None => {
+                let roots = self.model.roots();
+                self.roots = Some(roots.clone());
+                roots
+            }
+        };
+        let mut rows = Vec::new();
+        self.push_rows(&mut rows, roots, 0, None);
+        let (selected, nodes) = (self.selection.selected(), self.selected());
+
+        let new_index: HashMap<&M::Node, usize> = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, row)| Some((row.node.as_ref()?, i)))
+            .collect();
+        let old_rows = &self.rows;
+        self.selection.remap(|i| {
+            let node = old_rows.get(i)?.node.as_ref()?;
+            new_index.get(node).copied()
+        });
+        self.rows = rows;
+        self.hovered = None;
+        self.heights.update(self.rows.len());
+
+        // Row indices already reported are stale once the selection moves
+        if self.selection.selected() != selected {
+            let nodes_changed = self.selected() != nodes;
+            self.selection_changed(nodes_changed);
+        }
+    }
+
+    fn push_rows(
+        &mut self,
+        rows: &mut Vec<Row<M::Node>>,
+        nodes: Vec<M::Node>,
+        depth: usize,
+        parent: Option<usize>,
+    ) {
+        for node in nodes {
+            let expanded = self.expanded.contains(&node) && self.model.has_children(&node);
+            let index = rows.len();
+            rows.push(Row {
+                node: Some(node.clone()),
+                depth,
+                parent,
+                expanded,
+            });
+            if !expanded {
+                continue;
+            }
+            match self.load_children(&node) {
+                Some(children) => self.push_rows(rows, children, depth + 1, Some(index)),
+                None => rows.push(Row {
+                    node: None,
+                    depth: depth + 1,
+                    parent: Some(index),
+                    expanded: false,
+                }),
+            }
+        }
+    }
+
+    fn load_children(&mut self, node: &M::Node) -> Option<Vec<M::Node>> {
+        if let Some(children) = self.children.get(node) {
+            return Some(children.clone());
+        }
+        let children = self.model.children(node)?;
+        self.children.insert(node.clone(), children.clone());
+        Some(children)
+    }
+
+    /// Expand or collapse the node on row `index` for the user. Collapsing
+    /// the node around the cursor moves the cursor to it.
+    fn set_expanded(&mut self, index: usize, expanded: bool) {
+        let Some(row) = self.rows.get(index) else {
+            return;
+        };
+        let Some(node) = row.node.clone() else {
+            return;
+        };
+        if !self.model.has_children(&node) || row.expanded == expanded {
+            return;
+        }
+        let depth = row.depth;
+        let cursor_inside = self.selection.current().is_some_and(|current| {
+            current > index
+                && self.rows[index + 1..=current]
+                    .iter()
+                    .all(|r| r.depth > depth)
+        });
+
+        if expanded {
+            self.expanded.insert(node.clone());
+        } else {
+            self.expanded.remove(&node);
+        }
+        self.rebuild();
+        if cursor_inside {
+            self.pick(index, Pick::Replace);
+        }
+        if let Some(f) = &mut self.on_expand {
+            f(&node, expanded);
+        }
+    }
+
+    /// The row under surface position `y`
+    fn row_at(&self, bounds: Rect, y: f64) -> Option<usize> {
+        let len = self.rows.len();
+        let y = y - bounds.y as f64 + self.scroller.offset().1 as f64;
+        let total = self.heights.start(len);
+        (len > 0 && y >= 0.0 && y < total as f64).then(|| self.heights.index_at(y as u32, len))
+    }
+
+    /// Whether row `index` has a disclosure triangle
+    fn expandable(&self, index: usize) -> bool {
+        self.rows[index]
+            .node
+            .as_ref()
+            .is_some_and(|node| self.model.has_children(node))
+    }
+
+    /// Where row `index`'s disclosure triangle starts, from the row's left edge
+    fn indent(&self, index: usize) -> u32 {
+        self.rows[index].depth as u32 * INDENT
+    }
+
+    fn pick(&mut self, index: usize, pick: Pick) {
+        if self.selection.pick(index, pick) {
+            self.selection_changed(true);
+        }
+        self.scroll_to_row(index);
+    }
+
+    /// Report the selection, to the callback only if different nodes are
+    /// selected rather than the same ones on different rows
+    fn selection_changed(&mut self, nodes_changed: bool) {
+        let rows = self.selection.selected();
+        if nodes_changed && let Some(f) = &mut self.on_select {
+            let nodes: Vec<M::Node> = rows
+                .iter()
+                .filter_map(|&i| self.rows.get(i)?.node.clone())
+                .collect();
+            f(&nodes);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::SelectionChanged {
+                id: self.id,
+                selected: rows,
+            });
+        }
+    }
+
+    fn activate(&mut self, index: usize) {
+        let Some(node) = self.node_at(index).cloned() else {
+            return;
+        };
+        if let Some(f) = &mut self.on_activate {
+            f(&node);
+        }
+        if let Some(events) = &self.events {
+            events.push(WidgetEvent::ItemActivated { id: self.id, index });
+        }
+    }
+
+    /// Jump to the row matching the text typed so far
+    fn type_ahead(&mut self, text: &str, now: Instant) -> bool {
+        let (model, rows) = (&self.model, &self.rows);
+        let label = |i: usize| {
+            rows[i]
+                .node
+                .as_ref()
+                .map_or(String::new(), |n| model.label(n))
+        };
+        let current = self.selection.current();
+        if let Some(index) = self
+            .type_ahead
+            .search(text, now, current, rows.len(), &label)
+        {
+            self.pick(index, Pick::Replace);
+        }
+        true
+    }
+
+    fn draw_disclosure(&self, ctx: &mut RenderContext, x: f32, bounds: Rect, expanded: bool) {
+        let cx = x + DISCLOSURE_WIDTH as f32 / 2.0;
+        let cy = bounds.y as f32 + bounds.height as f32 / 2.0;
+        let points = if expanded {
+            [(cx - 4.0, cy - 2.0), (cx + 4.0, cy - 2.0), (cx, cy + 3.0)]
+        } else {
+            [(cx - 2.0, cy - 4.0), (cx + 3.0, cy), (cx - 2.0, cy + 4.0)]
+        };
+        ctx.canvas.fill_polygon(&points, DISCLOSURE.to_color());
+    }
+}
+
+impl<M: TreeModel> Widget for TreeView<M> {
+    fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn layout(&mut self, constraints: Constraints, _ctx: &mut LayoutContext) -> Size {
+        let total = self.heights.start(self.rows.len());
+        let viewport = viewport_size(Size::new(DEFAULT_WIDTH, total), constraints);
+        self.scroller
+            .set_sizes(Size::new(viewport.width, total), viewport);
+        self.reveal_row();
+        self.scroller.advance(Instant::now());
+        viewport
+    }
+
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
+        ctx.canvas.push_clip(
+            bounds.x as f32,
+            bounds.y as f32,
+            bounds.width as f32,
+            bounds.height as f32,
+        );
+        let offset = self.scroller.offset().1;
+        for index in self.visible_rows() {
+            let row = &self.rows[index];
+            let top = (self.heights.start(index) as f32 - offset).round() as i32;
+            let row_bounds = Rect::new(
+                bounds.x,
+                bounds.y + top,
+                bounds.width,
+                self.heights.height(index),
+            );
+            let selected = self.selection.is_selected(index);
+            let hovered = self.hovered == Some(index);
+            fill_row(ctx, row_bounds, selected, hovered);
+
+            let indent = self.indent(index);
+            let expandable = self.expandable(index);
+            if expandable {
+                let x = (row_bounds.x + indent as i32) as f32;
+                self.draw_disclosure(ctx, x, row_bounds, row.expanded);
+            }
+            let content_x = indent + DISCLOSURE_WIDTH;
+            let content = Rect::new(
+                row_bounds.x + content_x as i32,
+                row_bounds.y,
+                row_bounds.width.saturating_sub(content_x),
+                row_bounds.height,
+            );
+            let current = self.selection.current() == Some(index);
+
+            match (&row.node, &self.render_row) {
+                (Some(node), Some(render)) => render(
+                    &TreeRow {
+                        node,
+                        index,
+                        depth: row.depth,
+                        bounds: content,
+                        expanded: expandable.then_some(row.expanded),
+                        selected,
+                        current,
+                        hovered,
+                    },
+                    ctx,
+                ),
+                (node, _) => {
+                    let (text, color) = match node {
+                        Some(node) => (self.model.label(node), TEXT),
+                        None => (LOADING_TEXT.to_string(), TEXT_DISABLED),
+                    };
+                    let text_bounds = Rect::new(
+                        content.x + LABEL_INSET as i32 / 2,
+                        content.y,
+                        content.width.saturating_sub(LABEL_INSET / 2),
+                        content.height,
+                    );
+                    ctx.text.draw_text_in_rect(
+                        ctx.canvas,
+                        &text,
+                        text_bounds,
+                        FONT_SIZE,
+                        color,
+                        HAlign::Left,
+                        VAlign::Center,
+                    );
+                }
+            }
+            if current && self.focused {
+                outline_row(ctx, row_bounds);
+            }
+        }
+        self.scroller.render_scrollbars(bounds, ctx);
+        ctx.canvas.pop_clip();
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        let mods = event.modifiers;
+        let len = self.rows.len();
+        if event.state != KeyState::Pressed || len == 0 || mods.alt || mods.super_ {
+            return false;
+        }
+        let current = self.selection.current();
+        let page = self.scroller.viewport().height;
+        if let Some(index) = self.heights.navigate(event.key, current, page, len) {
+            self.type_ahead.reset();
+            self.pick(index, Pick::for_key(mods));
+            return true;
+        }
+
+        match event.key {
+            // Right expands, then steps to the first child
+            Key::Right if !mods.ctrl => {
+                if let Some(index) = current {
+                    let expanded = self.rows[index].expanded;
+                    if !expanded && self.expandable(index) {
+                        self.set_expanded(index, true);
+                    } else if expanded && index + 1 < len {
+                        self.pick(index + 1, Pick::Replace);
+                    }
+                }
+                true
+            }
+            // Left collapses, then steps to the parent
+            Key::Left if !mods.ctrl => {
+                if let Some(index) = current {
+                    let row = &self.rows[index];
+                    if row.expanded {
+                        self.set_expanded(index, false);
+                    } else if let Some(parent) = row.parent {
+                        self.pick(parent, Pick::Replace);
+                    }
+                }
+                true
+            }
+            Key::Enter | Key::KpEnter if !mods.ctrl => {
+                if let Some(index) = current {
+                    self.activate(index);
+                }
+                true
+            }
+            Key::A if mods.ctrl && self.selection.mode() == SelectionMode::Multiple => {
+                if self.selection.select_all(len) {
+                    self.selection_changed(true);
+                }
+                true
+            }
+            // Space selects, unless it's part of a type-ahead search
+            Key::Space if mods.ctrl || self.type_ahead.is_empty() => {
+                let index = current.unwrap_or(0);
+                let pick = if mods.ctrl {
+                    Pick::Toggle
+                } else {
+                    Pick::Replace
+                };
+                self.pick(index, pick);
+                true
+            }
+            _ if mods.ctrl => false,
+            _ => match &event.text {
+                Some(text) if !text.is_empty() && !text.chars().any(char::is_control) => {
+                    self.type_ahead(text, Instant::now())
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
+        if self.scroller.handle_scrollbar(event, bounds) {
+            return true;
+        }
+        let inside = bounds.contains(event.x as i32, event.y as i32);
+        match event.kind {
+            PointerEventKind::Press(PointerButton::Left) if inside => {
+                let Some(index) = self.row_at(bounds, event.y) else {
+                    return false;
+                };
+                let (expandable, expanded) = (self.expandable(index), self.rows[index].expanded);
+                let x = event.x - bounds.x as f64 - self.indent(index) as f64;
+                if expandable && (0.0..DISCLOSURE_WIDTH as f64).contains(&x) {
+                    self.set_expanded(index, !expanded);
+                    return true;
+                }
+
+                let pick = Pick::for_click(event.modifiers);
+                self.type_ahead.reset();
+                self.pick(index, pick);
+                if self.double_click.click(index, Instant::now()) && pick == Pick::Replace {
+                    if expandable {
+                        self.set_expanded(index, !expanded);
+                    }
+                    self.activate(index);
+                }
+                true
+            }
+            PointerEventKind::Scroll(scroll) if inside => {
+                self.scroller.scroll(&scroll, Instant::now())
+            }
+            PointerEventKind::Enter | PointerEventKind::Motion | PointerEventKind::Leave => {
+                let hovered = match event.kind {
+                    PointerEventKind::Leave => None,
+                    _ if inside => self.row_at(bounds, event.y),
+                    _ => None,
+                };
+                let changed = hovered != self.hovered;
+                self.hovered = hovered;
+                self.scroller.update_hover(event, bounds) || changed
+            }
+            _ => false,
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn focus_changed(&mut self, focused: bool) {
+        self.focused = focused;
+        self.type_ahead.reset();
+    }
+
+    fn is_animating(&self) -> bool {
+        self.scroller.is_gliding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Modifiers, make_key_event, make_pointer_event};
+    use crate::text::TextRenderer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Nodes are numbers; a node's children are its number times ten plus
+    /// one to three. Nodes in `loading` haven't loaded their children yet.
+    #[derive(Default)]
+    struct Numbers {
+        asked: Vec<u32>,
+        checked: RefCell<Vec<u32>>,
+        loading: HashSet<u32>,
+    }
+
+    impl TreeModel for Numbers {
+        type Node = u32;
+
+        fn roots(&mut self) -> Vec<u32> {
+            vec![1, 2]
+        }
+
+        fn children(&mut self, node: &u32) -> Option<Vec<u32>> {
+            self.asked.push(*node);
+            if self.loading.contains(node) {
+                return None;
+            }
+            Some((1..=3).map(|i| node * 10 + i).collect())
+        }
+
+        fn has_children(&self, node: &u32) -> bool {
+            self.checked.borrow_mut().push(*node);
+            *node < 100
+        }
+
+        fn label(&self, node: &u32) -> String {
+            format!("node {node}")
+        }
+    }
+
+    fn tree() -> TreeView<Numbers> {
+        let mut tree = TreeView::new(WidgetId(1), Numbers::default());
+        let mut text = TextRenderer::new();
+        let mut ctx = LayoutContext { text: &mut text };
+        tree.layout(Constraints::loose(200, 240), &mut ctx);
+        tree
+    }
+
+    fn click(x: f64, y: f64) -> PointerEvent {
+        let kind = PointerEventKind::Press(PointerButton::Left);
+        make_pointer_event(kind, x, y)
+    }
+
+    fn nodes(tree: &TreeView<Numbers>) -> Vec<Option<u32>> {
+        (0..tree.row_count())
+            .map(|i| tree.node_at(i).copied())
+            .collect()
+    }
+
+    #[test]
+    fn test_keyboard_expand_and_collapse() {
+        let mut tree = tree();
+        assert!(tree.model().asked.is_empty());
+
+        tree.handle_key(&make_key_event(Key::Down, None, Modifiers::default()));
+        tree.handle_key(&make_key_event(Key::Right, None, Modifiers::default()));
+        assert_eq!(tree.model().asked, [1]);
+        assert_eq!(
+            nodes(&tree),
+            [Some(1), Some(11), Some(12), Some(13), Some(2)]
+        );
+
+        // Right again steps in; Down and Right open a grandchild
+        tree.handle_key(&make_key_event(Key::Right, None, Modifiers::default()));
+        tree.handle_key(&make_key_event(Key::Down, None, Modifiers::default()));
+        tree.handle_key(&make_key_event(Key::Right, None, Modifiers::default()));
+        assert_eq!(tree.current(), Some(&12));
+        assert_eq!(tree.row_count(), 8);
+
+        // Left on a collapsed node steps to its parent, then collapses it
+        tree.handle_key(&make_key_event(Key::Down, None, Modifiers::default()));
+        assert_eq!(tree.current(), Some(&121));
+        tree.handle_key(&make_key_event(Key::Left, None, Modifiers::default()));
+        assert_eq!(tree.current(), Some(&12));
+        tree.handle_key(&make_key_event(Key::Left, None, Modifiers::default()));
+        tree.handle_key(&make_key_event(Key::Left, None, Modifiers::default()));
+        tree.handle_key(&make_key_event(Key::Left, None, Modifiers::default()));
+        assert_eq!(tree.current(), Some(&1));
+        assert_eq!(tree.selected(), [1]);
+        assert_eq!(nodes(&tree), [Some(1), Some(2)]);
+
+        // Children are only asked for once
+        tree.handle_key(&make_key_event(Key::Right, None, Modifiers::default()));
+        assert_eq!(tree.model().asked, [1, 12]);
+        assert_eq!(tree.row_count(), 5);
+    }
+
+    #[test]
+    fn test_children_load_later() {
+        let mut tree = tree();
+        tree.model_mut().loading.insert(2);
+        tree.expand(&2);
+        assert_eq!(nodes(&tree), [Some(1), Some(2), None]);
+
+        tree.model_mut().loading.clear();
+        tree.reload(&2);
+        assert_eq!(
+            nodes(&tree),
+            [Some(1), Some(2), Some(21), Some(22), Some(23)]
+        );
+        // Until rows are drawn, only expanded nodes are asked about
+        assert_eq!(*tree.model().checked.borrow(), [2, 2]);
+    }
+
+    #[test]
+    fn test_pointer_and_selection_follow_nodes() {
+        let expanded = Rc::new(RefCell::new(Vec::new()));
+        let log = expanded.clone();
+        let selections = Rc::new(RefCell::new(Vec::new()));
+        let log_selection = selections.clone();
+        let events = WidgetEvents::new();
+        let mut tree = tree()
+            .on_expand(move |node, open| log.borrow_mut().push((*node, open)))
+            .on_select(move |nodes| log_selection.borrow_mut().push(nodes.to_vec()))
+            .events(&events);
+        let bounds = Rect::new(0, 0, 200, 240);
+
+        // Row 1 is node 2; clicking its label selects, its triangle expands
+        tree.handle_pointer(&click(60.0, 30.0), bounds);
+        assert_eq!(tree.selected(), [2]);
+        tree.handle_pointer(&click(8.0, 6.0), bounds);
+        assert_eq!(tree.row_count(), 5);
+        // The selection stayed on node 2 as it moved down, and its new row
+        // is reported
+        assert_eq!(tree.selected(), [2]);
+        assert_eq!(tree.selection.current(), Some(4));
+        assert_eq!(*expanded.borrow(), [(1, true)]);
+        let id = WidgetId(1);
+        assert_eq!(
+            events.poll(),
+            [
+                WidgetEvent::SelectionChanged {
+                    id,
+                    selected: vec![1]
+                },
+                WidgetEvent::SelectionChanged {
+                    id,
+                    selected: vec![4]
+                },
+            ]
+        );
+        assert_eq!(*selections.borrow(), [vec![2]]);
+
+        assert!(tree.select(&12));
+        tree.collapse(&1);
+        assert!(tree.selected().is_empty());
+        assert_eq!(*selections.borrow(), [vec![2], vec![]]);
+    }
+}
@@ -1,5 +1,9 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::clipboard::Selection;
 use crate::input::{KeyEvent, PointerEvent};
-use crate::render::Canvas;
+use crate::render::{Canvas, Rgba};
 use crate::text::TextRenderer;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -29,6 +33,15 @@ impl Rect {
             && py >= self.y
             && py < self.y + self.height as i32
     }
+
+    /// The area inside both rects, if they overlap
+    pub fn intersect(&self, other: Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width as i32).min(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).min(other.y + other.height as i32);
+        (right > x && bottom > y).then(|| Rect::new(x, y, (right - x) as u32, (bottom - y) as u32))
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -59,7 +72,7 @@ impl Constraints {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Size {
     pub width: u32,
     pub height: u32,
@@ -71,150 +84,204 @@ impl Size {
     }
 }
 
-pub struct LayoutContext<'a> {
-    pub text: &'a mut TextRenderer,
+/// Something a user did to an interactive widget
+#[derive(Clone, Debug, PartialEq)]
+pub enum WidgetEvent {
+    /// A button was clicked or activated from the keyboard
+    Activated(WidgetId),
+    /// A checkbox or toggle switch changed state
+    Toggled { id: WidgetId, checked: bool },
+    /// A radio group option was chosen
+    Selected { id: WidgetId, index: usize },
+    /// A list's selected rows changed; `selected` is in ascending order
+    SelectionChanged { id: WidgetId, selected: Vec<usize> },
+    /// A list row was double-clicked or had Enter pressed on it
+    ItemActivated { id: WidgetId, index: usize },
+    /// A table's header was clicked to sort it by `column`
+    SortChanged {
+        id: WidgetId,
+        column: usize,
+        order: SortOrder,
+    },
+    /// The user edited a text field
+    TextChanged { id: WidgetId, text: String },
+    /// Enter was pressed in a single-line text field
+    Submitted { id: WidgetId, text: String },
+    /// A text field wants `text` put on the clipboard or primary selection,
+    /// e.g. with `App::set_clipboard_text`
+    Copy {
+        id: WidgetId,
+        selection: Selection,
+        text: String,
+    },
+    /// A text field wants the clipboard or primary selection pasted. Read it
+    /// (e.g. with `App::read_clipboard_text`) and hand the text to the widget's
+    /// `Widget::paste`, e.g. through `with_widget_mut`.
+    PasteRequested { id: WidgetId, selection: Selection },
 }
 
-pub struct RenderContext<'a> {
-    pub canvas: &'a mut Canvas<'a>,
-    pub text: &'a mut TextRenderer,
+/// Which way a sorted column runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
 }
 
-pub trait Widget {
-    fn id(&self) -> WidgetId;
-
-    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size;
+impl SortOrder {
+    pub fn reversed(self) -> Self {
+        match self {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
+        }
+    }
+}
 
-    fn render(&self, bounds: Rect, ctx: &mut RenderContext);
+/// A queue widgets push their events to, for apps that poll rather than
+/// use callbacks. Clones share the same queue.
+#[derive(Clone, Default)]
+pub struct WidgetEvents(Rc<RefCell<Vec<WidgetEvent>>>);
 
-    fn handle_key(&mut self, _event: &KeyEvent) -> bool {
-        false
+impl WidgetEvents {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn handle_pointer(&mut self, _event: &PointerEvent, _bounds: Rect) -> bool {
-        false
+    pub fn push(&self, event: WidgetEvent) {
+        self.0.borrow_mut().push(event);
     }
 
-    fn is_focusable(&self) -> bool {
-        false
+    pub fn poll(&self) -> Vec<WidgetEvent> {
+        std::mem::take(&mut self.0.borrow_mut())
     }
 }
 
-// Simple container for vertical layout
-pub struct VStack {
-    id: WidgetId,
-    children: Vec<Box<dyn Widget>>,
-    spacing: u32,
-    cached_sizes: Vec<Size>,
+pub struct LayoutContext<'a> {
+    pub text: &'a mut TextRenderer,
 }
 
-impl VStack {
-    pub fn new(id: WidgetId) -> Self {
+pub struct RenderContext<'a> {
+    pub canvas: &'a mut Canvas<'a>,
+    pub text: &'a mut TextRenderer,
+    /// The widget holding keyboard focus, which gets a focus ring
+    pub focused: Option<WidgetId>,
+    pub focus_ring_color: Rgba,
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn new(canvas: &'a mut Canvas<'a>, text: &'a mut TextRenderer) -> Self {
         Self {
-            id,
-            children: Vec::new(),
-            spacing: 0,
-            cached_sizes: Vec::new(),
+            canvas,
+            text,
+            focused: None,
+            focus_ring_color: Rgba::rgb(80, 150, 255),
         }
     }
 
-    pub fn spacing(mut self, spacing: u32) -> Self {
-        self.spacing = spacing;
-        self
+    pub fn is_focused(&self, id: WidgetId) -> bool {
+        self.focused == Some(id)
     }
 
-    pub fn child(mut self, widget: impl Widget + 'static) -> Self {
-        self.children.push(Box::new(widget));
-        self
+    /// Render `widget` into `bounds`, with a focus ring if it has focus.
+    /// Containers render their children through this.
+    pub fn render_child(&mut self, widget: &dyn Widget, bounds: Rect) {
+        widget.render(bounds, self);
+        if self.is_focused(widget.id()) {
+            self.draw_focus_ring(bounds);
+        }
     }
 
-    pub fn add_child(&mut self, widget: impl Widget + 'static) {
-        self.children.push(Box::new(widget));
+    pub fn draw_focus_ring(&mut self, bounds: Rect) {
+        self.canvas.stroke_rect(
+            bounds.x as f32 - FOCUS_RING_WIDTH,
+            bounds.y as f32 - FOCUS_RING_WIDTH,
+            bounds.width as f32 + FOCUS_RING_WIDTH * 2.0,
+            bounds.height as f32 + FOCUS_RING_WIDTH * 2.0,
+            FOCUS_RING_WIDTH,
+            self.focus_ring_color.to_color(),
+        );
     }
 }
 
-impl Widget for VStack {
-    fn id(&self) -> WidgetId {
-        self.id
-    }
+/// Drawn just outside the focused widget's bounds so it doesn't cover content
+const FOCUS_RING_WIDTH: f32 = 2.0;
 
-    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size {
-        self.cached_sizes.clear();
-
-        let mut total_height = 0u32;
-        let mut max_width = 0u32;
-
-        let child_constraints = Constraints {
-            min_width: constraints.min_width,
-            max_width: constraints.max_width,
-            min_height: 0,
-            max_height: constraints.max_height,
-        };
+pub trait Widget {
+    fn id(&self) -> WidgetId;
 
-        for (i, child) in self.children.iter_mut().enumerate() {
-            let size = child.layout(child_constraints, ctx);
-            self.cached_sizes.push(size);
+    fn layout(&mut self, constraints: Constraints, ctx: &mut LayoutContext) -> Size;
 
-            total_height += size.height;
-            if i > 0 {
-                total_height += self.spacing;
-            }
-            max_width = max_width.max(size.width);
-        }
+    fn render(&self, bounds: Rect, ctx: &mut RenderContext);
 
-        Size {
-            width: max_width.clamp(constraints.min_width, constraints.max_width),
-            height: total_height.clamp(constraints.min_height, constraints.max_height),
-        }
+    /// Keys come from `FocusManager::handle_key`, which offers them to the
+    /// focused widget and then its ancestors. Containers don't pass keys on
+    /// to their children, so an app without a focus manager only reaches
+    /// the widget it calls this on.
+    fn handle_key(&mut self, _event: &KeyEvent) -> bool {
+        false
     }
 
-    fn render(&self, bounds: Rect, ctx: &mut RenderContext) {
-        let mut y = bounds.y;
-
-        for (child, size) in self.children.iter().zip(self.cached_sizes.iter()) {
-            let child_bounds = Rect {
-                x: bounds.x,
-                y,
-                width: size.width,
-                height: size.height,
-            };
-            child.render(child_bounds, ctx);
-            y += size.height as i32 + self.spacing as i32;
-        }
+    /// Containers pass presses and scrolls to the child under the pointer, and
+    /// motion, releases and leaves to every child so each can track hover
+    fn handle_pointer(&mut self, _event: &PointerEvent, _bounds: Rect) -> bool {
+        false
     }
 
-    fn handle_key(&mut self, event: &KeyEvent) -> bool {
-        for child in &mut self.children {
-            if child.handle_key(event) {
-                return true;
-            }
-        }
+    fn is_focusable(&self) -> bool {
         false
     }
 
-    fn handle_pointer(&mut self, event: &PointerEvent, bounds: Rect) -> bool {
-        let mut y = bounds.y;
+    /// Called by `FocusManager` when this widget gains or loses keyboard focus
+    fn focus_changed(&mut self, _focused: bool) {}
 
-        for (child, size) in self.children.iter_mut().zip(self.cached_sizes.iter()) {
-            let child_bounds = Rect {
-                x: bounds.x,
-                y,
-                width: size.width,
-                height: size.height,
-            };
+    /// Insert pasted text, as requested by `WidgetEvent::PasteRequested`.
+    /// Returns false if this widget doesn't take text.
+    fn paste(&mut self, _text: &str) -> bool {
+        false
+    }
 
-            if child_bounds.contains(event.x as i32, event.y as i32)
-                && child.handle_pointer(event, child_bounds)
-            {
-                return true;
-            }
+    /// Call `f` with each child and the bounds it was last rendered into,
+    /// given this widget's `bounds`. Containers implement this so focus
+    /// traversal and click-to-focus can see inside them.
+    fn visit_children(&self, _bounds: Rect, _f: &mut dyn FnMut(&dyn Widget, Rect)) {}
 
-            y += size.height as i32 + self.spacing as i32;
-        }
+    fn visit_children_mut(&mut self, _f: &mut dyn FnMut(&mut dyn Widget)) {}
+
+    /// Whether this widget is mid-animation and needs layout and render again
+    /// on the next frame, e.g. a `ScrollView` gliding after a fling
+    fn is_animating(&self) -> bool {
         false
     }
 }
 
+/// Call `f` on the widget `id` in the tree under `root`; returns whether it was found
+pub fn with_widget_mut(
+    root: &mut dyn Widget,
+    id: WidgetId,
+    f: &mut dyn FnMut(&mut dyn Widget),
+) -> bool {
+    if root.id() == id {
+        f(root);
+        return true;
+    }
+    let mut found = false;
+    root.visit_children_mut(&mut |child| {
+        if !found {
+            found = with_widget_mut(child, id, f);
+        }
+    });
+    found
+}
+
+/// Whether any widget under `root` is animating. Apps call
+/// `App::request_animation_frame` while this is true.
+pub fn is_animating(root: &dyn Widget) -> bool {
+    let mut animating = root.is_animating();
+    root.visit_children(Rect::default(), &mut |child, _| {
+        animating = animating || is_animating(child);
+    });
+    animating
+}
+
 // Simple text label widget
 pub struct Label {
     id: WidgetId,
@@ -11,6 +11,7 @@ use smithay_client_toolkit::{
 use wayland_protocols::xdg::shell::client::xdg_positioner::{Anchor, Gravity};
 
 use crate::attached_surface::{AttachedSurface, AttachedSurfaceId};
+use crate::scale::SurfaceScale;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct WindowId(pub u64);
@@ -24,12 +25,59 @@ pub struct OverlayId(pub u64);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct SubsurfaceId(pub u64);
 
+/// Identifies which mkframe surface an input event was delivered to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SurfaceTarget {
+    Window(WindowId),
+    Popup(PopupId),
+    Overlay(OverlayId),
+    Subsurface(SubsurfaceId),
+    AttachedSurface(AttachedSurfaceId),
+}
+
+/// Frame-callback bookkeeping used to throttle rendering to the compositor's pace
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameState {
+    /// A `wl_surface.frame` callback was requested and hasn't fired yet
+    pub pending: bool,
+    /// An animation frame was requested with `App::request_animation_frame`
+    pub wants_frame: bool,
+    /// Timestamp in milliseconds (undefined base) of the last frame callback
+    pub time: Option<u32>,
+}
+
+impl FrameState {
+    /// Whether a surface with the given dirty flag should be rendered now
+    pub fn should_render(&self, dirty: bool) -> bool {
+        (dirty || self.wants_frame) && !self.pending
+    }
+
+    /// Start drawing a frame. An animation frame requested while drawing is
+    /// for the next frame, so the request is cleared first.
+    pub(crate) fn begin(&mut self) {
+        self.wants_frame = false;
+    }
+
+    /// The frame was committed; wait for its callback before the next
+    pub(crate) fn committed(&mut self) {
+        self.pending = true;
+    }
+
+    /// The compositor's frame callback fired at `time`
+    pub(crate) fn done(&mut self, time: u32) {
+        self.pending = false;
+        self.time = Some(time);
+    }
+}
+
 pub struct Window {
     pub id: WindowId,
     pub xdg: XdgWindow,
     pub width: u32,
     pub height: u32,
     pub dirty: bool,
+    pub scale: SurfaceScale,
+    pub frame: FrameState,
 }
 
 impl Window {
@@ -124,6 +172,8 @@ pub struct Popup {
     pub width: u32,
     pub height: u32,
     pub dirty: bool,
+    pub scale: SurfaceScale,
+    pub frame: FrameState,
 }
 
 impl Popup {
@@ -142,6 +192,8 @@ pub struct Overlay {
     pub width: u32,
     pub height: u32,
     pub dirty: bool,
+    pub scale: SurfaceScale,
+    pub frame: FrameState,
 }
 
 impl Overlay {
@@ -164,6 +216,8 @@ pub struct Subsurface {
     pub width: u32,
     pub height: u32,
     pub dirty: bool,
+    pub scale: SurfaceScale,
+    pub frame: FrameState,
 }
 
 impl Subsurface {
@@ -328,4 +382,150 @@ impl WindowManager {
             .find(|(_, s)| s.wl_surface() == surface)
             .map(|(id, _)| *id)
     }
+
+    /// Resolve a wl_surface to whichever kind of mkframe surface owns it
+    pub fn find_target_by_surface(&self, surface: &wl_surface::WlSurface) -> Option<SurfaceTarget> {
+        self.find_window_by_surface(surface)
+            .map(SurfaceTarget::Window)
+            .or_else(|| {
+                self.find_popup_by_surface(surface)
+                    .map(SurfaceTarget::Popup)
+            })
+            .or_else(|| {
+                self.find_overlay_by_surface(surface)
+                    .map(SurfaceTarget::Overlay)
+            })
+            .or_else(|| {
+                self.find_subsurface_by_surface(surface)
+                    .map(SurfaceTarget::Subsurface)
+            })
+            .or_else(|| {
+                self.find_attached_surface_by_surface(surface)
+                    .map(SurfaceTarget::AttachedSurface)
+            })
+    }
+
+    pub fn surface_for(&self, target: SurfaceTarget) -> Option<&wl_surface::WlSurface> {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window(id).map(|w| w.surface()),
+            SurfaceTarget::Popup(id) => self.get_popup(id).map(|p| p.surface()),
+            SurfaceTarget::Overlay(id) => self.get_overlay(id).map(|o| o.surface()),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface(id).map(|s| s.wl_surface()),
+            SurfaceTarget::AttachedSurface(id) => {
+                self.get_attached_surface(id).map(|s| s.wl_surface())
+            }
+        }
+    }
+
+    /// Logical (unscaled) size of a surface
+    pub fn size_of(&self, target: SurfaceTarget) -> Option<(u32, u32)> {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window(id).map(|w| (w.width, w.height)),
+            SurfaceTarget::Popup(id) => self.get_popup(id).map(|p| (p.width, p.height)),
+            SurfaceTarget::Overlay(id) => self.get_overlay(id).map(|o| (o.width, o.height)),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface(id).map(|s| (s.width, s.height)),
+            SurfaceTarget::AttachedSurface(id) => {
+                self.get_attached_surface(id).map(|s| (s.width, s.height))
+            }
+        }
+    }
+
+    pub fn scale_of(&self, target: SurfaceTarget) -> Option<&SurfaceScale> {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window(id).map(|w| &w.scale),
+            SurfaceTarget::Popup(id) => self.get_popup(id).map(|p| &p.scale),
+            SurfaceTarget::Overlay(id) => self.get_overlay(id).map(|o| &o.scale),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface(id).map(|s| &s.scale),
+            SurfaceTarget::AttachedSurface(id) => self.get_attached_surface(id).map(|s| &s.scale),
+        }
+    }
+
+    pub fn scale_of_mut(&mut self, target: SurfaceTarget) -> Option<&mut SurfaceScale> {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window_mut(id).map(|w| &mut w.scale),
+            SurfaceTarget::Popup(id) => self.get_popup_mut(id).map(|p| &mut p.scale),
+            SurfaceTarget::Overlay(id) => self.get_overlay_mut(id).map(|o| &mut o.scale),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface_mut(id).map(|s| &mut s.scale),
+            SurfaceTarget::AttachedSurface(id) => {
+                self.get_attached_surface_mut(id).map(|s| &mut s.scale)
+            }
+        }
+    }
+
+    pub fn mark_dirty(&mut self, target: SurfaceTarget) {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window_mut(id).map(|w| w.mark_dirty()),
+            SurfaceTarget::Popup(id) => self.get_popup_mut(id).map(|p| p.mark_dirty()),
+            SurfaceTarget::Overlay(id) => self.get_overlay_mut(id).map(|o| o.mark_dirty()),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface_mut(id).map(|s| s.mark_dirty()),
+            SurfaceTarget::AttachedSurface(id) => {
+                self.get_attached_surface_mut(id).map(|s| s.mark_dirty())
+            }
+        };
+    }
+
+    pub fn frame_of(&self, target: SurfaceTarget) -> Option<&FrameState> {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window(id).map(|w| &w.frame),
+            SurfaceTarget::Popup(id) => self.get_popup(id).map(|p| &p.frame),
+            SurfaceTarget::Overlay(id) => self.get_overlay(id).map(|o| &o.frame),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface(id).map(|s| &s.frame),
+            SurfaceTarget::AttachedSurface(id) => self.get_attached_surface(id).map(|s| &s.frame),
+        }
+    }
+
+    pub fn frame_of_mut(&mut self, target: SurfaceTarget) -> Option<&mut FrameState> {
+        match target {
+            SurfaceTarget::Window(id) => self.get_window_mut(id).map(|w| &mut w.frame),
+            SurfaceTarget::Popup(id) => self.get_popup_mut(id).map(|p| &mut p.frame),
+            SurfaceTarget::Overlay(id) => self.get_overlay_mut(id).map(|o| &mut o.frame),
+            SurfaceTarget::Subsurface(id) => self.get_subsurface_mut(id).map(|s| &mut s.frame),
+            SurfaceTarget::AttachedSurface(id) => {
+                self.get_attached_surface_mut(id).map(|s| &mut s.frame)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_render() {
+        let mut frame = FrameState::default();
+        assert!(!frame.should_render(false));
+        assert!(frame.should_render(true));
+
+        frame.wants_frame = true;
+        assert!(frame.should_render(false));
+
+        // Nothing renders while waiting on the compositor
+        frame.pending = true;
+        assert!(!frame.should_render(true));
+        assert!(!frame.should_render(false));
+    }
+
+    #[test]
+    fn test_frame_requested_while_rendering() {
+        let mut frame = FrameState {
+            wants_frame: true,
+            ..Default::default()
+        };
+        frame.begin();
+        // An animation asks for another frame from its render callback
+        frame.wants_frame = true;
+        frame.committed();
+        assert!(!frame.should_render(false));
+
+        frame.done(16);
+        assert!(frame.should_render(false));
+        assert_eq!(frame.time, Some(16));
+
+        // Without a new request, the animation stops
+        frame.begin();
+        frame.committed();
+        frame.done(32);
+        assert!(!frame.should_render(false));
+    }
 }